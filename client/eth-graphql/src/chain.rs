@@ -0,0 +1,230 @@
+//! Where the schema reads the chain from.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use ethereum::{BlockV2 as EthereumBlock, ReceiptV3 as EthereumReceipt};
+use fc_storage::StorageOverride;
+use fp_rpc::{EthereumRuntimeRPCApi, TransactionStatus};
+use polkadot_sdk::*;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+
+/// An Ethereum block and what the schema shows of it.
+#[derive(Clone, Debug)]
+pub struct BlockData {
+    /// Hash of the Substrate block holding it.
+    pub substrate_hash: H256,
+    /// The block, with its transactions.
+    pub block: EthereumBlock,
+    /// Receipts, in transaction order.
+    pub receipts: Vec<EthereumReceipt>,
+    /// Senders, callees, created contracts and logs, in transaction order.
+    pub statuses: Vec<TransactionStatus>,
+    /// Base fee per gas, as `eth_getBlockByNumber` reports it.
+    pub base_fee_per_gas: U256,
+}
+
+/// A log found by the log index: the Substrate block, the index of the transaction in the block
+/// and of the log in the transaction.
+pub type IndexedLog = (H256, u32, u32);
+
+/// The Ethereum data of the chain.
+///
+/// Blocks are identified by the hash of the Substrate block holding them, and only canonical
+/// ones are returned, as by the JSON-RPC.
+#[async_trait::async_trait]
+pub trait EthChain: Send + Sync {
+    /// Number of the latest block indexed by the Frontier mapping worker.
+    async fn latest_number(&self) -> Result<u64, String>;
+
+    /// The canonical block at `number`.
+    fn block_hash(&self, number: u64) -> Result<Option<H256>, String>;
+
+    /// The canonical block holding the Ethereum block `hash`.
+    async fn block_by_ethereum_hash(&self, hash: H256) -> Result<Option<H256>, String>;
+
+    /// The canonical block holding the transaction `hash`, and the index of the transaction.
+    async fn transaction(&self, hash: H256) -> Result<Option<(H256, u32)>, String>;
+
+    /// The Ethereum block of the block `at`.
+    fn block(&self, at: H256) -> Result<Option<BlockData>, String>;
+
+    /// Logs of the blocks `from..=to` matching `addresses` and one of the `topics` combinations,
+    /// from the log index, or `None` without one.
+    async fn indexed_logs(
+        &self,
+        from: u64,
+        to: u64,
+        addresses: Vec<H160>,
+        topics: Vec<Vec<Option<H256>>>,
+    ) -> Result<Option<Vec<IndexedLog>>, String>;
+
+    /// Balance and nonce of `address` after the block `at`.
+    fn account(&self, at: H256, address: H160) -> Result<(U256, U256), String>;
+
+    /// Code of `address` after the block `at`.
+    fn code(&self, at: H256, address: H160) -> Result<Vec<u8>, String>;
+
+    /// Storage `slot` of `address` after the block `at`.
+    fn storage(&self, at: H256, address: H160, slot: H256) -> Result<H256, String>;
+
+    /// Gas price after the block `at`.
+    fn gas_price(&self, at: H256) -> Result<U256, String>;
+
+    /// EIP-155 chain id.
+    fn chain_id(&self, at: H256) -> Result<u64, String>;
+}
+
+/// [`EthChain`] of a client, its Frontier mapping database and its storage override.
+pub struct ClientChain<B: BlockT, C> {
+    client: Arc<C>,
+    backend: Arc<dyn fc_api::Backend<B>>,
+    storage_override: Arc<dyn StorageOverride<B>>,
+    _marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C> ClientChain<B, C> {
+    /// Read the chain of `client`, with the mappings and the log index of `backend`.
+    pub fn new(
+        client: Arc<C>,
+        backend: Arc<dyn fc_api::Backend<B>>,
+        storage_override: Arc<dyn StorageOverride<B>>,
+    ) -> Self {
+        Self {
+            client,
+            backend,
+            storage_override,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<B, C> ClientChain<B, C>
+where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B>,
+{
+    fn is_canon(&self, hash: H256) -> bool {
+        match self.client.number(hash) {
+            Ok(Some(number)) => self.client.hash(number).ok().flatten() == Some(hash),
+            _ => false,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<B, C> EthChain for ClientChain<B, C>
+where
+    B: BlockT<Hash = H256>,
+    C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<B>,
+{
+    async fn latest_number(&self) -> Result<u64, String> {
+        let latest = self.backend.latest_block_hash().await?;
+        let number = self.client.number(latest).map_err(|e| e.to_string())?;
+        Ok(number.map_or(0, UniqueSaturatedInto::unique_saturated_into))
+    }
+
+    fn block_hash(&self, number: u64) -> Result<Option<H256>, String> {
+        self.client
+            .hash(number.unique_saturated_into())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn block_by_ethereum_hash(&self, hash: H256) -> Result<Option<H256>, String> {
+        let hashes = self.backend.block_hash(&hash).await?.unwrap_or_default();
+        Ok(hashes.into_iter().find(|hash| self.is_canon(*hash)))
+    }
+
+    async fn transaction(&self, hash: H256) -> Result<Option<(H256, u32)>, String> {
+        let metadata = self.backend.transaction_metadata(&hash).await?;
+        Ok(metadata
+            .into_iter()
+            .find(|metadata| self.is_canon(metadata.substrate_block_hash))
+            .map(|metadata| (metadata.substrate_block_hash, metadata.ethereum_index)))
+    }
+
+    fn block(&self, at: H256) -> Result<Option<BlockData>, String> {
+        let Some(block) = self.storage_override.current_block(at) else {
+            return Ok(None);
+        };
+        Ok(Some(BlockData {
+            substrate_hash: at,
+            block,
+            receipts: self
+                .storage_override
+                .current_receipts(at)
+                .unwrap_or_default(),
+            statuses: self
+                .storage_override
+                .current_transaction_statuses(at)
+                .unwrap_or_default(),
+            base_fee_per_gas: self.gas_price(at)?,
+        }))
+    }
+
+    async fn indexed_logs(
+        &self,
+        from: u64,
+        to: u64,
+        addresses: Vec<H160>,
+        topics: Vec<Vec<Option<H256>>>,
+    ) -> Result<Option<Vec<IndexedLog>>, String> {
+        let indexer = self.backend.log_indexer();
+        if !indexer.is_indexed() {
+            return Ok(None);
+        }
+        let logs = indexer.filter_logs(from, to, addresses, topics).await?;
+        Ok(Some(
+            logs.into_iter()
+                .map(|log| {
+                    (
+                        log.substrate_block_hash,
+                        log.transaction_index,
+                        log.log_index,
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    fn account(&self, at: H256, address: H160) -> Result<(U256, U256), String> {
+        let account = self
+            .client
+            .runtime_api()
+            .account_basic(at, address)
+            .map_err(|e| e.to_string())?;
+        Ok((account.balance, account.nonce))
+    }
+
+    fn code(&self, at: H256, address: H160) -> Result<Vec<u8>, String> {
+        Ok(self
+            .storage_override
+            .account_code_at(at, address)
+            .unwrap_or_default())
+    }
+
+    fn storage(&self, at: H256, address: H160, slot: H256) -> Result<H256, String> {
+        let index = U256::from_big_endian(slot.as_bytes());
+        Ok(self
+            .storage_override
+            .account_storage_at(at, address, index)
+            .unwrap_or_default())
+    }
+
+    fn gas_price(&self, at: H256) -> Result<U256, String> {
+        self.client
+            .runtime_api()
+            .gas_price(at)
+            .map_err(|e| e.to_string())
+    }
+
+    fn chain_id(&self, at: H256) -> Result<u64, String> {
+        self.client
+            .runtime_api()
+            .chain_id(at)
+            .map_err(|e| e.to_string())
+    }
+}
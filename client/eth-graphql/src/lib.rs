@@ -0,0 +1,373 @@
+//! EIP-1767 GraphQL API of the Ethereum data of the node.
+//!
+//! The schema is the one of EIP-1767 without `pending`, `call`, `estimateGas`, `syncing` and the
+//! `sendRawTransaction` mutation, which the JSON-RPC serves. Blocks, transactions and logs are read
+//! from the Frontier mapping database and the storage override, as by `eth_getBlockByNumber` and
+//! `eth_getLogs`, so a single query can walk from a block to its transactions and their logs
+//! without a round trip per object. Blocks are those indexed by the Frontier mapping worker:
+//! `block` without arguments is its latest block.
+//!
+//! Queries are limited in depth and cost, see [`schema`]: a query costing more than the limit is
+//! rejected before any of it is executed. `blocks` and `logs` read at most [`MAX_BLOCK_RANGE`]
+//! blocks. Queries are posted as JSON to `/graphql` of the address given to [`bind`].
+
+mod chain;
+mod scalars;
+mod schema;
+
+pub use chain::{BlockData, ClientChain, EthChain, IndexedLog};
+pub use schema::{schema, EthSchema, MAX_BLOCK_RANGE};
+
+use std::{convert::Infallible, future::Future, net::SocketAddr};
+
+use hyper::{
+    body::HttpBody,
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+
+/// Largest query accepted, in bytes.
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+/// Listen at `addr`, and return the server of `schema` at `http://{addr}/graphql`, which runs
+/// until it fails.
+///
+/// Binding is done here so that a busy address fails the caller rather than the server task.
+pub fn bind(
+    addr: SocketAddr,
+    schema: EthSchema,
+) -> Result<impl Future<Output = Result<(), hyper::Error>>, hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let schema = schema.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(schema.clone(), request))) }
+    });
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    log::info!(target: "eth-graphql", "Ethereum GraphQL server started at http://{addr}/graphql");
+    Ok(server)
+}
+
+async fn handle(schema: EthSchema, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::POST || request.uri().path() != "/graphql" {
+        return Ok(status(StatusCode::NOT_FOUND));
+    }
+
+    let mut body = request.into_body();
+    let mut query = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let Ok(chunk) = chunk else {
+            return Ok(status(StatusCode::BAD_REQUEST));
+        };
+        if query.len() + chunk.len() > MAX_REQUEST_SIZE {
+            return Ok(status(StatusCode::PAYLOAD_TOO_LARGE));
+        }
+        query.extend_from_slice(&chunk);
+    }
+    let Ok(query) = serde_json::from_slice::<async_graphql::Request>(&query) else {
+        return Ok(status(StatusCode::BAD_REQUEST));
+    };
+
+    let response = schema.execute(query).await;
+    let body = serde_json::to_vec(&response).expect("GraphQL responses serialize to JSON; qed");
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("the response is valid; qed"))
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{ScalarType, Value as GraphqlValue};
+    use ethereum::{
+        BlockV2, EIP658ReceiptData, LegacyTransaction, PartialHeader, ReceiptV3, TransactionAction,
+        TransactionSignature, TransactionV2,
+    };
+    use fp_rpc::TransactionStatus;
+    use polkadot_sdk::*;
+    use scalars::{BigInt, Bytes32, Long};
+    use serde_json::{json, Value};
+    use sp_core::{H160, H256, U256};
+    use std::sync::Arc;
+
+    const AT: H256 = H256::repeat_byte(0xaa);
+    const SENDER: H160 = H160::repeat_byte(0x11);
+    const CONTRACT: H160 = H160::repeat_byte(0x22);
+
+    fn topic(byte: u8) -> H256 {
+        H256::repeat_byte(byte)
+    }
+
+    fn log(topics: Vec<H256>) -> ethereum::Log {
+        ethereum::Log {
+            address: CONTRACT,
+            topics,
+            data: vec![0xca, 0xfe],
+        }
+    }
+
+    fn transaction(nonce: u64) -> TransactionV2 {
+        TransactionV2::Legacy(LegacyTransaction {
+            nonce: nonce.into(),
+            gas_price: 1_000.into(),
+            gas_limit: 100_000.into(),
+            action: TransactionAction::Call(CONTRACT),
+            value: U256::zero(),
+            input: vec![],
+            signature: TransactionSignature::new(27, H256::repeat_byte(1), H256::repeat_byte(1))
+                .expect("valid signature"),
+        })
+    }
+
+    /// Block 1, with two transactions emitting one and two logs.
+    fn block_data() -> BlockData {
+        let logs = [
+            vec![log(vec![topic(1)])],
+            vec![log(vec![topic(2)]), log(vec![topic(1)])],
+        ];
+        let transactions = vec![transaction(0), transaction(1)];
+        let header = PartialHeader {
+            parent_hash: H256::zero(),
+            beneficiary: H160::zero(),
+            state_root: H256::zero(),
+            receipts_root: H256::zero(),
+            logs_bloom: Default::default(),
+            difficulty: U256::zero(),
+            number: 1.into(),
+            gas_limit: 75_000_000.into(),
+            gas_used: 60_000.into(),
+            timestamp: 12_000,
+            extra_data: vec![],
+            mix_hash: H256::zero(),
+            nonce: Default::default(),
+        };
+        BlockData {
+            substrate_hash: AT,
+            receipts: (1..=logs.len())
+                .zip(&logs)
+                .map(|(count, logs)| {
+                    ReceiptV3::Legacy(EIP658ReceiptData {
+                        status_code: 1,
+                        used_gas: (count * 30_000).into(),
+                        logs_bloom: Default::default(),
+                        logs: logs.clone(),
+                    })
+                })
+                .collect(),
+            statuses: transactions
+                .iter()
+                .zip(logs)
+                .enumerate()
+                .map(|(index, (transaction, logs))| TransactionStatus {
+                    transaction_hash: transaction.hash(),
+                    transaction_index: index as u32,
+                    from: SENDER,
+                    to: Some(CONTRACT),
+                    logs,
+                    ..Default::default()
+                })
+                .collect(),
+            block: BlockV2::new(header, transactions, vec![]),
+            base_fee_per_gas: 1_000.into(),
+        }
+    }
+
+    struct MockChain(BlockData);
+
+    #[async_trait::async_trait]
+    impl EthChain for MockChain {
+        async fn latest_number(&self) -> Result<u64, String> {
+            Ok(1)
+        }
+
+        fn block_hash(&self, number: u64) -> Result<Option<H256>, String> {
+            Ok((number == 1).then_some(AT))
+        }
+
+        async fn block_by_ethereum_hash(&self, hash: H256) -> Result<Option<H256>, String> {
+            Ok((hash == self.0.block.header.hash()).then_some(AT))
+        }
+
+        async fn transaction(&self, hash: H256) -> Result<Option<(H256, u32)>, String> {
+            let index = self
+                .0
+                .statuses
+                .iter()
+                .position(|s| s.transaction_hash == hash);
+            Ok(index.map(|index| (AT, index as u32)))
+        }
+
+        fn block(&self, at: H256) -> Result<Option<BlockData>, String> {
+            Ok((at == AT).then(|| self.0.clone()))
+        }
+
+        async fn indexed_logs(
+            &self,
+            _: u64,
+            _: u64,
+            _: Vec<H160>,
+            _: Vec<Vec<Option<H256>>>,
+        ) -> Result<Option<Vec<IndexedLog>>, String> {
+            Ok(None)
+        }
+
+        fn account(&self, _: H256, _: H160) -> Result<(U256, U256), String> {
+            Ok((U256::exp10(18), 2.into()))
+        }
+
+        fn code(&self, _: H256, address: H160) -> Result<Vec<u8>, String> {
+            Ok(if address == CONTRACT {
+                vec![0x60, 0x00]
+            } else {
+                vec![]
+            })
+        }
+
+        fn storage(&self, _: H256, _: H160, slot: H256) -> Result<H256, String> {
+            Ok(slot)
+        }
+
+        fn gas_price(&self, _: H256) -> Result<U256, String> {
+            Ok(1_000.into())
+        }
+
+        fn chain_id(&self, _: H256) -> Result<u64, String> {
+            Ok(42)
+        }
+    }
+
+    async fn query(max_cost: usize, query: &str) -> Result<Value, String> {
+        let schema = schema(Arc::new(MockChain(block_data())), max_cost);
+        let response = schema.execute(query).await;
+        match response.errors.first() {
+            Some(error) => Err(error.message.clone()),
+            None => Ok(response.data.into_json().expect("data is JSON")),
+        }
+    }
+
+    #[test]
+    fn scalars_round_trip() {
+        let big = BigInt::parse(GraphqlValue::String("1000".into())).unwrap();
+        assert_eq!(big, BigInt(1_000.into()));
+        assert_eq!(big.to_value(), GraphqlValue::String("0x3e8".into()));
+        assert_eq!(
+            BigInt::parse(GraphqlValue::String("0x3e8".into())).unwrap(),
+            big
+        );
+
+        assert_eq!(
+            Long::parse(GraphqlValue::String("0x10".into())).unwrap(),
+            Long(16)
+        );
+        assert_eq!(Long(16).to_value(), GraphqlValue::Number(16.into()));
+        assert!(Long::parse(GraphqlValue::String("16".into())).is_err());
+
+        let hash = format!("{:?}", topic(1));
+        let parsed = Bytes32::parse(GraphqlValue::String(hash.clone())).unwrap();
+        assert_eq!(parsed.to_value(), GraphqlValue::String(hash));
+        assert!(Bytes32::parse(GraphqlValue::String("0x0102".into())).is_err());
+        assert!(Bytes32::parse(GraphqlValue::Null).is_err());
+    }
+
+    #[tokio::test]
+    async fn walks_from_a_block_to_the_logs_of_its_transactions() {
+        let data = block_data();
+        let response = query(
+            100_000,
+            r#"{
+                block(number: 1) {
+                    number
+                    hash
+                    transactionCount
+                    transactions {
+                        index
+                        from { address balance }
+                        gasUsed
+                        logs { index topics data account { code } }
+                    }
+                }
+            }"#,
+        )
+        .await
+        .unwrap();
+
+        let block = &response["block"];
+        assert_eq!(block["number"], json!(1));
+        assert_eq!(
+            block["hash"],
+            json!(format!("{:?}", data.block.header.hash()))
+        );
+        assert_eq!(block["transactionCount"], json!(2));
+        let transactions = block["transactions"].as_array().unwrap();
+        assert_eq!(
+            transactions[1]["from"]["address"],
+            json!(format!("{SENDER:?}"))
+        );
+        assert_eq!(
+            transactions[1]["from"]["balance"],
+            json!("0xde0b6b3a7640000")
+        );
+        assert_eq!(transactions[1]["gasUsed"], json!(30_000));
+        // Log indices are positions in the block.
+        let logs = transactions[1]["logs"].as_array().unwrap();
+        assert_eq!(
+            logs.iter()
+                .map(|log| log["index"].clone())
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert_eq!(logs[0]["topics"], json!([format!("{:?}", topic(2))]));
+        assert_eq!(logs[0]["data"], json!("0xcafe"));
+        assert_eq!(logs[0]["account"]["code"], json!("0x6000"));
+    }
+
+    #[tokio::test]
+    async fn filters_logs_by_topic() {
+        let response = query(
+            100_000,
+            &format!(
+                r#"{{ logs(filter: {{ fromBlock: 1, toBlock: 1, topics: [["{:?}"]] }}) {{
+                    index
+                    transaction {{ index }}
+                }} }}"#,
+                topic(1)
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response["logs"],
+            json!([
+                { "index": 0, "transaction": { "index": 0 } },
+                { "index": 2, "transaction": { "index": 1 } },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_queries_above_the_cost_limit() {
+        let nested = "{ blocks(from: 1) { transactions { logs { data } } } }";
+        assert!(query(100_000, nested).await.is_err());
+        assert!(query(1_000, "{ blocks(from: 1, to: 1) { number } }")
+            .await
+            .is_ok());
+        assert!(query(1, "{ block { number } }").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn logs_cost_scales_with_the_block_range() {
+        let one_block = "{ logs(filter: { fromBlock: 1, toBlock: 1 }) { data } }";
+        assert!(query(1_000, one_block).await.is_ok());
+        assert!(query(1_000, "{ logs(filter: {}) { data } }").await.is_ok());
+        let open_range = "{ logs(filter: { fromBlock: 1 }) { data } }";
+        assert!(query(100_000, open_range).await.is_err());
+        let wide_range = "{ logs(filter: { fromBlock: 1, toBlock: 100 }) { data } }";
+        assert!(query(1_000, wide_range).await.is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Scalars of the EIP-1767 schema.
+
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use polkadot_sdk::*;
+use sp_core::{
+    bytes::{from_hex, to_hex},
+    H160, H256, U256,
+};
+
+/// `Bytes32`: 32 bytes, hex encoded with a `0x` prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bytes32(pub H256);
+
+/// `Address`: 20 bytes, hex encoded with a `0x` prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Address(pub H160);
+
+/// `Bytes`: any number of bytes, hex encoded with a `0x` prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+/// `BigInt`: a 256 bit unsigned integer, read from a hex or decimal string and written in hex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BigInt(pub U256);
+
+/// `Long`: a 64 bit unsigned integer, read from a number or a hex string and written as a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Long(pub u64);
+
+fn hex_bytes(value: &Value, len: Option<usize>) -> InputValueResult<Vec<u8>> {
+    let Value::String(hex) = value else {
+        return Err(InputValueError::expected_type(value.clone()));
+    };
+    if !hex.starts_with("0x") || hex.len() % 2 != 0 {
+        return Err(InputValueError::custom(
+            "expected an even number of hex digits after 0x",
+        ));
+    }
+    let bytes = from_hex(hex).map_err(InputValueError::custom)?;
+    match len {
+        Some(len) if bytes.len() != len => Err(InputValueError::custom(format!(
+            "expected {len} bytes, got {}",
+            bytes.len()
+        ))),
+        _ => Ok(bytes),
+    }
+}
+
+#[Scalar]
+impl ScalarType for Bytes32 {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        hex_bytes(&value, Some(32)).map(|bytes| Self(H256::from_slice(&bytes)))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(to_hex(self.0.as_bytes(), false))
+    }
+}
+
+#[Scalar]
+impl ScalarType for Address {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        hex_bytes(&value, Some(20)).map(|bytes| Self(H160::from_slice(&bytes)))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(to_hex(self.0.as_bytes(), false))
+    }
+}
+
+#[Scalar]
+impl ScalarType for Bytes {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        hex_bytes(&value, None).map(Self)
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(to_hex(&self.0, false))
+    }
+}
+
+#[Scalar]
+impl ScalarType for BigInt {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        let parsed = match &value {
+            Value::String(hex) if hex.starts_with("0x") => U256::from_str_radix(&hex[2..], 16).ok(),
+            Value::String(decimal) => U256::from_dec_str(decimal).ok(),
+            Value::Number(number) => number.as_u64().map(U256::from),
+            _ => return Err(InputValueError::expected_type(value)),
+        };
+        parsed
+            .map(Self)
+            .ok_or_else(|| InputValueError::custom("not a 256 bit unsigned integer"))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(format!("{:#x}", self.0))
+    }
+}
+
+#[Scalar]
+impl ScalarType for Long {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        let parsed = match &value {
+            Value::Number(number) => number.as_u64(),
+            Value::String(hex) if hex.starts_with("0x") => u64::from_str_radix(&hex[2..], 16).ok(),
+            _ => return Err(InputValueError::expected_type(value)),
+        };
+        parsed
+            .map(Self)
+            .ok_or_else(|| InputValueError::custom("not a 64 bit unsigned integer"))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Number(self.0.into())
+    }
+}
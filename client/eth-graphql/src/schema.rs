@@ -0,0 +1,854 @@
+//! The EIP-1767 schema, without `pending`, `call`, `estimateGas` and mutations.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, InputObject, Object, Result, Schema, SimpleObject,
+};
+use ethereum::{
+    EnvelopedEncodable, ReceiptV3 as EthereumReceipt, TransactionAction,
+    TransactionV2 as EthereumTransaction,
+};
+use polkadot_sdk::*;
+use sp_core::{H160, H256, U256};
+
+use crate::{
+    chain::{BlockData, EthChain},
+    scalars::{Address, BigInt, Bytes, Bytes32, Long},
+};
+
+/// The schema served by [`crate::serve`].
+pub type EthSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Deepest nesting of a query.
+const MAX_DEPTH: usize = 16;
+/// Most blocks `blocks` and `logs` read in a query.
+pub const MAX_BLOCK_RANGE: u64 = 1024;
+/// Most topic combinations of a `logs` filter looked up in the log index.
+const MAX_TOPIC_COMBINATIONS: usize = 256;
+/// Cost of the fields listing the transactions or logs of a block, as a multiple of the cost of
+/// one of them.
+const LIST_COST: usize = 100;
+
+/// The schema of `chain`, rejecting queries costing more than `max_cost`.
+///
+/// Each field costs one, lists of blocks the cost of a block times the number of blocks asked for,
+/// and lists of transactions or logs [`LIST_COST`] times the cost of one. `logs` also scales with
+/// the number of blocks its filter covers.
+pub fn schema(chain: Arc<dyn EthChain>, max_cost: usize) -> EthSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(chain)
+        .limit_complexity(max_cost)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+fn chain<'a>(ctx: &Context<'a>) -> &'a Arc<dyn EthChain> {
+    ctx.data_unchecked::<Arc<dyn EthChain>>()
+}
+
+/// Number of blocks in `from..=to`, a missing `to` counting as the longest range.
+fn range_cost(from: &Long, to: &Option<Long>) -> usize {
+    let to = to.map_or(from.0.saturating_add(MAX_BLOCK_RANGE - 1), |to| to.0);
+    to.saturating_sub(from.0).saturating_add(1) as usize
+}
+
+/// Cost of the logs matching `filter`: [`LIST_COST`] times the cost of a log, for each block of
+/// the range as [`range_cost`] counts them. Without `fromBlock` the range starts at the latest
+/// block, so it holds at most one.
+fn logs_cost(child_complexity: usize, filter: &FilterCriteria) -> usize {
+    let blocks = filter
+        .from_block
+        .map_or(1, |from| range_cost(&from, &filter.to_block));
+    child_complexity
+        .saturating_mul(LIST_COST)
+        .saturating_mul(blocks)
+}
+
+/// The canonical block `number`.
+fn block_at(chain: &dyn EthChain, number: u64) -> Result<Option<Block>> {
+    match chain.block_hash(number)? {
+        Some(hash) => Ok(chain.block(hash)?.map(|data| Block(Arc::new(data)))),
+        None => Ok(None),
+    }
+}
+
+/// The block `number`, or the latest one.
+async fn block_or_latest(chain: &dyn EthChain, number: Option<Long>) -> Result<u64> {
+    match number {
+        Some(number) => Ok(number.0),
+        None => Ok(chain.latest_number().await?),
+    }
+}
+
+/// `address` after the block `number`, or after `default` without one.
+fn account(
+    ctx: &Context<'_>,
+    address: H160,
+    number: Option<Long>,
+    default: H256,
+) -> Result<Account> {
+    let at = match number {
+        Some(number) => chain(ctx).block_hash(number.0)?.ok_or("block not found")?,
+        None => default,
+    };
+    Ok(Account { address, at })
+}
+
+/// Root of the queries.
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The block `number` or `hash`, or the latest one without either.
+    async fn block(
+        &self,
+        ctx: &Context<'_>,
+        number: Option<Long>,
+        hash: Option<Bytes32>,
+    ) -> Result<Option<Block>> {
+        let chain = chain(ctx).as_ref();
+        match (number, hash) {
+            (Some(_), Some(_)) => Err("only one of number or hash must be specified".into()),
+            (_, Some(hash)) => match chain.block_by_ethereum_hash(hash.0).await? {
+                Some(at) => Ok(chain.block(at)?.map(|data| Block(Arc::new(data)))),
+                None => Ok(None),
+            },
+            (number, None) => block_at(chain, block_or_latest(chain, number).await?),
+        }
+    }
+
+    /// The blocks `from..=to`, up to the latest one.
+    #[graphql(complexity = "child_complexity.saturating_mul(range_cost(&from, &to))")]
+    async fn blocks(&self, ctx: &Context<'_>, from: Long, to: Option<Long>) -> Result<Vec<Block>> {
+        let chain = chain(ctx).as_ref();
+        let latest = chain.latest_number().await?;
+        let to = to.map_or(latest, |to| to.0.min(latest));
+        if to.saturating_sub(from.0) >= MAX_BLOCK_RANGE {
+            return Err(format!("block range greater than {MAX_BLOCK_RANGE}").into());
+        }
+        let mut blocks = Vec::new();
+        for number in from.0..=to {
+            blocks.extend(block_at(chain, number)?);
+        }
+        Ok(blocks)
+    }
+
+    /// The transaction `hash`, once in a block.
+    async fn transaction(&self, ctx: &Context<'_>, hash: Bytes32) -> Result<Option<Transaction>> {
+        let chain = chain(ctx).as_ref();
+        let Some((at, index)) = chain.transaction(hash.0).await? else {
+            return Ok(None);
+        };
+        Ok(chain.block(at)?.map(|data| Transaction {
+            block: Arc::new(data),
+            index,
+        }))
+    }
+
+    /// The logs matching `filter`.
+    #[graphql(complexity = "logs_cost(child_complexity, &filter)")]
+    async fn logs(&self, ctx: &Context<'_>, filter: FilterCriteria) -> Result<Vec<Log>> {
+        let chain = chain(ctx).as_ref();
+        let latest = chain.latest_number().await?;
+        let from = filter.from_block.map_or(latest, |from| from.0);
+        let to = filter.to_block.map_or(latest, |to| to.0.min(latest));
+        if to.saturating_sub(from) >= MAX_BLOCK_RANGE {
+            return Err(format!("block range greater than {MAX_BLOCK_RANGE}").into());
+        }
+        let matcher = LogMatcher::new(filter.addresses, filter.topics);
+
+        let combinations = matcher.topic_combinations()?;
+        let Some(indexed) = chain
+            .indexed_logs(from, to, matcher.addresses.clone(), combinations)
+            .await?
+        else {
+            let mut logs = Vec::new();
+            for number in from..=to {
+                if let Some(block) = block_at(chain, number)? {
+                    logs.extend(block.matching_logs(&matcher));
+                }
+            }
+            return Ok(logs);
+        };
+
+        let mut blocks = HashMap::new();
+        let mut logs = Vec::new();
+        for (at, transaction, index) in indexed {
+            if !blocks.contains_key(&at) {
+                let Some(data) = chain.block(at)? else {
+                    continue;
+                };
+                blocks.insert(at, Arc::new(data));
+            }
+            let log = Log {
+                block: blocks[&at].clone(),
+                transaction,
+                index,
+            };
+            if log.raw().is_some_and(|raw| matcher.matches(raw)) {
+                logs.push(log);
+            }
+        }
+        Ok(logs)
+    }
+
+    /// Gas price of the next block.
+    async fn gas_price(&self, ctx: &Context<'_>) -> Result<BigInt> {
+        let chain = chain(ctx).as_ref();
+        let latest = chain.latest_number().await?;
+        let at = chain.block_hash(latest)?.ok_or("latest block not found")?;
+        Ok(BigInt(chain.gas_price(at)?))
+    }
+
+    /// EIP-155 chain id.
+    #[graphql(name = "chainID")]
+    async fn chain_id(&self, ctx: &Context<'_>) -> Result<BigInt> {
+        let chain = chain(ctx).as_ref();
+        let latest = chain.latest_number().await?;
+        let at = chain.block_hash(latest)?.ok_or("latest block not found")?;
+        Ok(BigInt(chain.chain_id(at)?.into()))
+    }
+}
+
+/// Logs wanted by a `logs` query.
+#[derive(InputObject)]
+pub struct FilterCriteria {
+    /// First block, the latest one by default.
+    from_block: Option<Long>,
+    /// Last block, the latest one by default.
+    to_block: Option<Long>,
+    /// Emitters, any by default.
+    addresses: Option<Vec<Address>>,
+    /// Topics, by position: a log matches when, for each position, its topic is one of those
+    /// listed or the list is empty.
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// Logs of a block wanted by `Block.logs`.
+#[derive(InputObject)]
+pub struct BlockFilterCriteria {
+    /// Emitters, any by default.
+    addresses: Option<Vec<Address>>,
+    /// Topics, as in [`FilterCriteria`].
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// Addresses and topics logs are matched against.
+struct LogMatcher {
+    addresses: Vec<H160>,
+    topics: Vec<Vec<H256>>,
+}
+
+impl LogMatcher {
+    fn new(addresses: Option<Vec<Address>>, topics: Option<Vec<Vec<Bytes32>>>) -> Self {
+        Self {
+            addresses: addresses
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| a.0)
+                .collect(),
+            topics: topics
+                .unwrap_or_default()
+                .into_iter()
+                .map(|topics| topics.into_iter().map(|topic| topic.0).collect())
+                .collect(),
+        }
+    }
+
+    fn matches(&self, log: &ethereum::Log) -> bool {
+        (self.addresses.is_empty() || self.addresses.contains(&log.address))
+            && self.topics.iter().enumerate().all(|(position, wanted)| {
+                wanted.is_empty() || log.topics.get(position).is_some_and(|t| wanted.contains(t))
+            })
+    }
+
+    /// The topics as the combinations the log index looks up, `None` matching any topic.
+    fn topic_combinations(&self) -> Result<Vec<Vec<Option<H256>>>> {
+        let mut combinations: Vec<Vec<Option<H256>>> = vec![vec![]];
+        for wanted in &self.topics {
+            let choices: Vec<Option<H256>> = match wanted.is_empty() {
+                true => vec![None],
+                false => wanted.iter().copied().map(Some).collect(),
+            };
+            if combinations.len() * choices.len() > MAX_TOPIC_COMBINATIONS {
+                return Err(
+                    format!("more than {MAX_TOPIC_COMBINATIONS} topic combinations").into(),
+                );
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(|prefix| {
+                    choices.iter().map(move |choice| {
+                        let mut combination = prefix.clone();
+                        combination.push(*choice);
+                        combination
+                    })
+                })
+                .collect();
+        }
+        if combinations
+            .iter()
+            .all(|combination| combination.iter().all(Option::is_none))
+        {
+            return Ok(vec![]);
+        }
+        Ok(combinations)
+    }
+}
+
+/// An Ethereum block.
+#[derive(Clone)]
+pub struct Block(Arc<BlockData>);
+
+impl Block {
+    fn all_transactions(&self) -> impl Iterator<Item = Transaction> + '_ {
+        (0..self.0.block.transactions.len() as u32).map(|index| Transaction {
+            block: self.0.clone(),
+            index,
+        })
+    }
+
+    fn matching_logs<'a>(&'a self, matcher: &'a LogMatcher) -> impl Iterator<Item = Log> + 'a {
+        self.all_transactions().flat_map(move |transaction| {
+            transaction
+                .transaction_status()
+                .map(|status| status.logs.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+                .filter(|(_, log)| matcher.matches(log))
+                .map(|(index, _)| Log {
+                    block: self.0.clone(),
+                    transaction: transaction.index,
+                    index: index as u32,
+                })
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+#[Object]
+impl Block {
+    /// Block number.
+    async fn number(&self) -> Long {
+        Long(self.0.block.header.number.low_u64())
+    }
+
+    /// Block hash.
+    async fn hash(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.hash())
+    }
+
+    /// The parent block, if not the genesis one.
+    async fn parent(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+        match self.0.block.header.number.low_u64().checked_sub(1) {
+            Some(number) => block_at(chain(ctx).as_ref(), number),
+            None => Ok(None),
+        }
+    }
+
+    /// Proof-of-work nonce, always zero.
+    async fn nonce(&self) -> Bytes {
+        Bytes(self.0.block.header.nonce.as_bytes().to_vec())
+    }
+
+    /// Root of the transaction trie.
+    async fn transactions_root(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.transactions_root)
+    }
+
+    /// Number of transactions.
+    async fn transaction_count(&self) -> Long {
+        Long(self.0.block.transactions.len() as u64)
+    }
+
+    /// Root of the state trie, as held by the Ethereum block.
+    async fn state_root(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.state_root)
+    }
+
+    /// Root of the receipt trie.
+    async fn receipts_root(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.receipts_root)
+    }
+
+    /// Author of the block, after the block `block` or this one.
+    async fn miner(&self, ctx: &Context<'_>, block: Option<Long>) -> Result<Account> {
+        account(
+            ctx,
+            self.0.block.header.beneficiary,
+            block,
+            self.0.substrate_hash,
+        )
+    }
+
+    /// Extra data of the header.
+    async fn extra_data(&self) -> Bytes {
+        Bytes(self.0.block.header.extra_data.clone())
+    }
+
+    /// Gas limit.
+    async fn gas_limit(&self) -> Long {
+        Long(self.0.block.header.gas_limit.low_u64())
+    }
+
+    /// Gas used by the transactions.
+    async fn gas_used(&self) -> Long {
+        Long(self.0.block.header.gas_used.low_u64())
+    }
+
+    /// Base fee per gas.
+    async fn base_fee_per_gas(&self) -> BigInt {
+        BigInt(self.0.base_fee_per_gas)
+    }
+
+    /// Unix timestamp, in seconds.
+    async fn timestamp(&self) -> Long {
+        Long(self.0.block.header.timestamp / 1000)
+    }
+
+    /// Bloom filter of the logs.
+    async fn logs_bloom(&self) -> Bytes {
+        Bytes(self.0.block.header.logs_bloom.as_bytes().to_vec())
+    }
+
+    /// Mix hash, always zero.
+    async fn mix_hash(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.mix_hash)
+    }
+
+    /// Difficulty, always zero.
+    async fn difficulty(&self) -> BigInt {
+        BigInt(self.0.block.header.difficulty)
+    }
+
+    /// Total difficulty, always zero.
+    async fn total_difficulty(&self) -> BigInt {
+        BigInt(U256::zero())
+    }
+
+    /// Number of ommers, always zero.
+    async fn ommer_count(&self) -> Long {
+        Long(0)
+    }
+
+    /// Ommers, always empty.
+    async fn ommers(&self) -> Vec<Block> {
+        vec![]
+    }
+
+    /// Ommer at `index`, always missing.
+    async fn ommer_at(&self, _index: Long) -> Option<Block> {
+        None
+    }
+
+    /// Hash of the ommers.
+    async fn ommer_hash(&self) -> Bytes32 {
+        Bytes32(self.0.block.header.ommers_hash)
+    }
+
+    /// Transactions, in order.
+    #[graphql(complexity = "child_complexity.saturating_mul(LIST_COST)")]
+    async fn transactions(&self) -> Vec<Transaction> {
+        self.all_transactions().collect()
+    }
+
+    /// Transaction at `index`.
+    async fn transaction_at(&self, index: Long) -> Option<Transaction> {
+        self.all_transactions().nth(index.0.try_into().ok()?)
+    }
+
+    /// Logs matching `filter`.
+    #[graphql(complexity = "child_complexity.saturating_mul(LIST_COST)")]
+    async fn logs(&self, filter: BlockFilterCriteria) -> Vec<Log> {
+        self.matching_logs(&LogMatcher::new(filter.addresses, filter.topics))
+            .collect()
+    }
+
+    /// `address` after this block.
+    async fn account(&self, address: Address) -> Account {
+        Account {
+            address: address.0,
+            at: self.0.substrate_hash,
+        }
+    }
+
+    /// RLP encoding of the block.
+    async fn raw(&self) -> Bytes {
+        Bytes(rlp::encode(&self.0.block).to_vec())
+    }
+
+    /// RLP encoding of the header.
+    async fn raw_header(&self) -> Bytes {
+        Bytes(rlp::encode(&self.0.block.header).to_vec())
+    }
+}
+
+/// A transaction in a block.
+#[derive(Clone)]
+pub struct Transaction {
+    block: Arc<BlockData>,
+    index: u32,
+}
+
+impl Transaction {
+    fn transaction(&self) -> &EthereumTransaction {
+        &self.block.block.transactions[self.index as usize]
+    }
+
+    fn transaction_status(&self) -> Option<&fp_rpc::TransactionStatus> {
+        self.block.statuses.get(self.index as usize)
+    }
+
+    fn cumulative_gas(&self, index: u32) -> Option<U256> {
+        match self.block.receipts.get(index as usize)? {
+            EthereumReceipt::Legacy(data)
+            | EthereumReceipt::EIP2930(data)
+            | EthereumReceipt::EIP1559(data) => Some(data.used_gas),
+        }
+    }
+
+    fn price(&self) -> U256 {
+        match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.gas_price,
+            EthereumTransaction::EIP2930(t) => t.gas_price,
+            EthereumTransaction::EIP1559(t) => t.max_fee_per_gas.min(
+                self.block
+                    .base_fee_per_gas
+                    .saturating_add(t.max_priority_fee_per_gas),
+            ),
+        }
+    }
+}
+
+#[Object]
+impl Transaction {
+    /// Transaction hash.
+    async fn hash(&self) -> Bytes32 {
+        Bytes32(self.transaction().hash())
+    }
+
+    /// Nonce of the sender.
+    async fn nonce(&self) -> Long {
+        Long(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.nonce.low_u64(),
+            EthereumTransaction::EIP2930(t) => t.nonce.low_u64(),
+            EthereumTransaction::EIP1559(t) => t.nonce.low_u64(),
+        })
+    }
+
+    /// Index in the block.
+    async fn index(&self) -> Long {
+        Long(self.index.into())
+    }
+
+    /// Sender, after the block `block` or the block of the transaction.
+    async fn from(&self, ctx: &Context<'_>, block: Option<Long>) -> Result<Account> {
+        let from = self
+            .transaction_status()
+            .map(|status| status.from)
+            .unwrap_or_default();
+        account(ctx, from, block, self.block.substrate_hash)
+    }
+
+    /// Callee, missing for contract creations.
+    async fn to(&self, ctx: &Context<'_>, block: Option<Long>) -> Result<Option<Account>> {
+        let action = match self.transaction() {
+            EthereumTransaction::Legacy(t) => &t.action,
+            EthereumTransaction::EIP2930(t) => &t.action,
+            EthereumTransaction::EIP1559(t) => &t.action,
+        };
+        match action {
+            TransactionAction::Call(to) => {
+                account(ctx, *to, block, self.block.substrate_hash).map(Some)
+            }
+            TransactionAction::Create => Ok(None),
+        }
+    }
+
+    /// Value transferred, in wei.
+    async fn value(&self) -> BigInt {
+        BigInt(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.value,
+            EthereumTransaction::EIP2930(t) => t.value,
+            EthereumTransaction::EIP1559(t) => t.value,
+        })
+    }
+
+    /// Gas price, the effective one for EIP-1559 transactions.
+    async fn gas_price(&self) -> BigInt {
+        BigInt(self.price())
+    }
+
+    /// Maximum fee per gas of EIP-1559 transactions.
+    async fn max_fee_per_gas(&self) -> Option<BigInt> {
+        match self.transaction() {
+            EthereumTransaction::EIP1559(t) => Some(BigInt(t.max_fee_per_gas)),
+            _ => None,
+        }
+    }
+
+    /// Maximum priority fee per gas of EIP-1559 transactions.
+    async fn max_priority_fee_per_gas(&self) -> Option<BigInt> {
+        match self.transaction() {
+            EthereumTransaction::EIP1559(t) => Some(BigInt(t.max_priority_fee_per_gas)),
+            _ => None,
+        }
+    }
+
+    /// Fee per gas above the base fee.
+    async fn effective_tip(&self) -> BigInt {
+        BigInt(self.price().saturating_sub(self.block.base_fee_per_gas))
+    }
+
+    /// Gas limit.
+    async fn gas(&self) -> Long {
+        Long(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.gas_limit.low_u64(),
+            EthereumTransaction::EIP2930(t) => t.gas_limit.low_u64(),
+            EthereumTransaction::EIP1559(t) => t.gas_limit.low_u64(),
+        })
+    }
+
+    /// Call data, or init code of contract creations.
+    async fn input_data(&self) -> Bytes {
+        Bytes(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.input.clone(),
+            EthereumTransaction::EIP2930(t) => t.input.clone(),
+            EthereumTransaction::EIP1559(t) => t.input.clone(),
+        })
+    }
+
+    /// Block holding the transaction.
+    async fn block(&self) -> Block {
+        Block(self.block.clone())
+    }
+
+    /// 1 when the transaction succeeded, 0 when it failed.
+    async fn status(&self) -> Option<Long> {
+        match self.block.receipts.get(self.index as usize)? {
+            EthereumReceipt::Legacy(data)
+            | EthereumReceipt::EIP2930(data)
+            | EthereumReceipt::EIP1559(data) => Some(Long(data.status_code.into())),
+        }
+    }
+
+    /// Gas used by the transaction.
+    async fn gas_used(&self) -> Option<Long> {
+        let cumulative = self.cumulative_gas(self.index)?;
+        let before = match self.index.checked_sub(1) {
+            Some(previous) => self.cumulative_gas(previous)?,
+            None => U256::zero(),
+        };
+        Some(Long(cumulative.saturating_sub(before).low_u64()))
+    }
+
+    /// Gas used by the transactions of the block up to this one.
+    async fn cumulative_gas_used(&self) -> Option<Long> {
+        self.cumulative_gas(self.index)
+            .map(|gas| Long(gas.low_u64()))
+    }
+
+    /// Gas price paid.
+    async fn effective_gas_price(&self) -> BigInt {
+        BigInt(self.price())
+    }
+
+    /// Contract created by the transaction.
+    async fn created_contract(
+        &self,
+        ctx: &Context<'_>,
+        block: Option<Long>,
+    ) -> Result<Option<Account>> {
+        match self
+            .transaction_status()
+            .and_then(|status| status.contract_address)
+        {
+            Some(address) => account(ctx, address, block, self.block.substrate_hash).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Logs emitted.
+    #[graphql(complexity = "child_complexity.saturating_mul(LIST_COST)")]
+    async fn logs(&self) -> Vec<Log> {
+        let count = self
+            .transaction_status()
+            .map_or(0, |status| status.logs.len() as u32);
+        (0..count)
+            .map(|index| Log {
+                block: self.block.clone(),
+                transaction: self.index,
+                index,
+            })
+            .collect()
+    }
+
+    /// `r` of the signature.
+    async fn r(&self) -> BigInt {
+        BigInt(U256::from_big_endian(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.signature.r().as_bytes(),
+            EthereumTransaction::EIP2930(t) => t.r.as_bytes(),
+            EthereumTransaction::EIP1559(t) => t.r.as_bytes(),
+        }))
+    }
+
+    /// `s` of the signature.
+    async fn s(&self) -> BigInt {
+        BigInt(U256::from_big_endian(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.signature.s().as_bytes(),
+            EthereumTransaction::EIP2930(t) => t.s.as_bytes(),
+            EthereumTransaction::EIP1559(t) => t.s.as_bytes(),
+        }))
+    }
+
+    /// `v` of legacy signatures, the parity of `y` of the others.
+    async fn v(&self) -> BigInt {
+        BigInt(U256::from(match self.transaction() {
+            EthereumTransaction::Legacy(t) => t.signature.v(),
+            EthereumTransaction::EIP2930(t) => t.odd_y_parity as u64,
+            EthereumTransaction::EIP1559(t) => t.odd_y_parity as u64,
+        }))
+    }
+
+    /// EIP-2718 transaction type.
+    #[graphql(name = "type")]
+    async fn kind(&self) -> Long {
+        Long(match self.transaction() {
+            EthereumTransaction::Legacy(_) => 0,
+            EthereumTransaction::EIP2930(_) => 1,
+            EthereumTransaction::EIP1559(_) => 2,
+        })
+    }
+
+    /// EIP-2930 access list, missing for legacy transactions.
+    async fn access_list(&self) -> Option<Vec<AccessTuple>> {
+        let list = match self.transaction() {
+            EthereumTransaction::Legacy(_) => return None,
+            EthereumTransaction::EIP2930(t) => &t.access_list,
+            EthereumTransaction::EIP1559(t) => &t.access_list,
+        };
+        Some(
+            list.iter()
+                .map(|item| AccessTuple {
+                    address: Address(item.address),
+                    storage_keys: item.storage_keys.iter().copied().map(Bytes32).collect(),
+                })
+                .collect(),
+        )
+    }
+
+    /// EIP-2718 encoding of the transaction.
+    async fn raw(&self) -> Bytes {
+        Bytes(EnvelopedEncodable::encode(self.transaction()).to_vec())
+    }
+
+    /// EIP-2718 encoding of the receipt.
+    async fn raw_receipt(&self) -> Option<Bytes> {
+        let receipt = self.block.receipts.get(self.index as usize)?;
+        Some(Bytes(EnvelopedEncodable::encode(receipt).to_vec()))
+    }
+}
+
+/// An entry of an access list.
+#[derive(SimpleObject)]
+pub struct AccessTuple {
+    /// Account accessed.
+    address: Address,
+    /// Slots of the account accessed.
+    storage_keys: Vec<Bytes32>,
+}
+
+/// A log emitted by a transaction.
+pub struct Log {
+    block: Arc<BlockData>,
+    transaction: u32,
+    index: u32,
+}
+
+impl Log {
+    fn raw(&self) -> Option<&ethereum::Log> {
+        self.block
+            .statuses
+            .get(self.transaction as usize)?
+            .logs
+            .get(self.index as usize)
+    }
+}
+
+#[Object]
+impl Log {
+    /// Index in the block.
+    async fn index(&self) -> Long {
+        let before: usize = (self.block.statuses.iter())
+            .take(self.transaction as usize)
+            .map(|status| status.logs.len())
+            .sum();
+        Long((before + self.index as usize) as u64)
+    }
+
+    /// Emitter, after the block `block` or the block of the log.
+    async fn account(&self, ctx: &Context<'_>, block: Option<Long>) -> Result<Account> {
+        let address = self.raw().map(|log| log.address).unwrap_or_default();
+        account(ctx, address, block, self.block.substrate_hash)
+    }
+
+    /// Topics.
+    async fn topics(&self) -> Vec<Bytes32> {
+        self.raw()
+            .map(|log| log.topics.iter().copied().map(Bytes32).collect())
+            .unwrap_or_default()
+    }
+
+    /// Data.
+    async fn data(&self) -> Bytes {
+        Bytes(self.raw().map(|log| log.data.clone()).unwrap_or_default())
+    }
+
+    /// Transaction emitting the log.
+    async fn transaction(&self) -> Transaction {
+        Transaction {
+            block: self.block.clone(),
+            index: self.transaction,
+        }
+    }
+}
+
+/// An account after a block.
+pub struct Account {
+    address: H160,
+    at: H256,
+}
+
+#[Object]
+impl Account {
+    /// Address.
+    async fn address(&self) -> Address {
+        Address(self.address)
+    }
+
+    /// Balance, in wei.
+    async fn balance(&self, ctx: &Context<'_>) -> Result<BigInt> {
+        Ok(BigInt(chain(ctx).account(self.at, self.address)?.0))
+    }
+
+    /// Nonce.
+    async fn transaction_count(&self, ctx: &Context<'_>) -> Result<Long> {
+        Ok(Long(chain(ctx).account(self.at, self.address)?.1.low_u64()))
+    }
+
+    /// Code, empty for externally owned accounts.
+    async fn code(&self, ctx: &Context<'_>) -> Result<Bytes> {
+        Ok(Bytes(chain(ctx).code(self.at, self.address)?))
+    }
+
+    /// Value of storage `slot`.
+    async fn storage(&self, ctx: &Context<'_>, slot: Bytes32) -> Result<Bytes32> {
+        Ok(Bytes32(chain(ctx).storage(
+            self.at,
+            self.address,
+            slot.0,
+        )?))
+    }
+}
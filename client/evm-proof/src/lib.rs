@@ -0,0 +1,525 @@
+//! Substrate read proofs of EVM accounts, returned by `eth_getProof`.
+//!
+//! The EVM state is not kept in Ethereum Merkle-Patricia tries but in the Substrate state trie, so
+//! the response of `eth_getProof` has the shape of EIP-1186 with Substrate proofs in it:
+//!
+//! - `address`, `balance`, `nonce`, `codeHash` and `storageProof[].key`/`value` are as in
+//!   EIP-1186; the balance is the one of `eth_getBalance`, the free balance less what is frozen
+//!   and the existential deposit;
+//! - `blockHash` and `stateRoot` are the hash and state root of the Substrate block the proof was
+//!   read at;
+//! - `accountProof` is a read proof of `System::Account` and `EVM::AccountCodes` of the address,
+//!   `storageProof[].proof` one of `EVM::AccountStorages` of the address and slot. A read proof is
+//!   the set of trie nodes, in no particular order, visited when reading the keys from the state
+//!   root; hashed values of state version 1 are included as nodes too.
+//!
+//! [`verify`] checks the proofs against a state root the caller trusts, e.g. the one of a
+//! GRANDPA-finalized header, and the claimed values against the proven ones. The keys are built by
+//! [`account_key`], [`code_key`] and [`storage_key`]. Test vectors are under `res/vectors`.
+//!
+//! The other way around, [`proven_keys`] lists the keys read while recording a proof, which
+//! [`decode_key`] turns back into accounts and slots, e.g. to build the access list of a call.
+
+use std::collections::HashMap;
+
+use codec::Decode;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    hashing::{blake2_128, keccak_256, twox_128},
+    Bytes, H160, H256, U256,
+};
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_trie::{LayoutV1, NodeCodec, StorageProof};
+use trie_db::{
+    node::{Node, NodeHandle},
+    NibbleSlice, NodeCodec as _,
+};
+
+/// `eth_getProof` response.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmProof {
+    /// The account.
+    pub address: H160,
+    /// Substrate block the proof was read at.
+    pub block_hash: H256,
+    /// State root of that block.
+    pub state_root: H256,
+    /// Balance as returned by `eth_getBalance`.
+    pub balance: U256,
+    /// Nonce of the account.
+    pub nonce: U256,
+    /// Keccak-256 hash of the code, that of the empty string for accounts without code.
+    pub code_hash: H256,
+    /// Read proof of `System::Account` and `EVM::AccountCodes` of the address.
+    pub account_proof: Vec<Bytes>,
+    /// Storage slots requested.
+    pub storage_proof: Vec<StorageSlotProof>,
+}
+
+/// A storage slot of [`EvmProof`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageSlotProof {
+    /// The slot.
+    pub key: H256,
+    /// Its value, zero if it is not set.
+    pub value: U256,
+    /// Read proof of `EVM::AccountStorages` of the address and slot.
+    pub proof: Vec<Bytes>,
+}
+
+/// What [`verify`] proved about an account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifiedAccount {
+    /// Nonce of the account.
+    pub nonce: U256,
+    /// Balance as returned by `eth_getBalance`.
+    pub balance: U256,
+    /// Code of the account.
+    pub code: Vec<u8>,
+    /// Requested slots and their values.
+    pub storage: Vec<(H256, U256)>,
+}
+
+/// Why a proof was refused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The proof was read at another state root than the trusted one.
+    StateRoot {
+        /// The trusted state root.
+        expected: H256,
+        /// The state root of the proof.
+        found: H256,
+    },
+    /// The nodes do not prove the value of a key under the state root.
+    InvalidProof(String),
+    /// A proven value does not decode.
+    Decode(&'static str),
+    /// A claimed value differs from the proven one.
+    Mismatch(&'static str),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::StateRoot { expected, found } => {
+                write!(f, "state root {found:?} is not the trusted {expected:?}")
+            }
+            Error::InvalidProof(e) => write!(f, "invalid proof: {e}"),
+            Error::Decode(what) => write!(f, "cannot decode {what}"),
+            Error::Mismatch(what) => write!(f, "mismatch: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// `frame_system::AccountInfo<u32, pallet_balances::AccountData<u128>>`.
+#[derive(Decode, Default)]
+struct AccountInfo {
+    nonce: u32,
+    _consumers: u32,
+    _providers: u32,
+    _sufficients: u32,
+    data: AccountData,
+}
+
+/// `pallet_balances::AccountData<u128>`.
+#[derive(Decode, Default)]
+struct AccountData {
+    free: u128,
+    reserved: u128,
+    frozen: u128,
+    _flags: u128,
+}
+
+impl AccountData {
+    /// `reducible_balance` of `pallet_balances` when the account is preserved, which is what
+    /// `pallet_evm` reports as the balance.
+    fn reducible_balance(&self, existential_deposit: u128) -> u128 {
+        let untouchable = self
+            .frozen
+            .saturating_sub(self.reserved)
+            .max(existential_deposit);
+        self.free.saturating_sub(untouchable)
+    }
+}
+
+fn map_key(pallet: &str, storage: &str) -> Vec<u8> {
+    [twox_128(pallet.as_bytes()), twox_128(storage.as_bytes())].concat()
+}
+
+fn blake2_128_concat(key: &[u8]) -> Vec<u8> {
+    [&blake2_128(key)[..], key].concat()
+}
+
+/// Key of `System::Account` of `address`.
+pub fn account_key(address: H160) -> Vec<u8> {
+    [
+        map_key("System", "Account"),
+        blake2_128_concat(address.as_bytes()),
+    ]
+    .concat()
+}
+
+/// Key of `EVM::AccountCodes` of `address`.
+pub fn code_key(address: H160) -> Vec<u8> {
+    [
+        map_key("EVM", "AccountCodes"),
+        blake2_128_concat(address.as_bytes()),
+    ]
+    .concat()
+}
+
+/// Key of `EVM::AccountStorages` of `address` and `slot`.
+pub fn storage_key(address: H160, slot: H256) -> Vec<u8> {
+    [
+        map_key("EVM", "AccountStorages"),
+        blake2_128_concat(address.as_bytes()),
+        blake2_128_concat(slot.as_bytes()),
+    ]
+    .concat()
+}
+
+/// An EVM key of the state, as built by [`account_key`], [`code_key`] and [`storage_key`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvmKey {
+    /// `System::Account` of the address.
+    Account(H160),
+    /// `EVM::AccountCodes` of the address.
+    Code(H160),
+    /// `EVM::AccountStorages` of the address and slot.
+    Storage(H160, H256),
+}
+
+/// The EVM key `key` is, if any.
+pub fn decode_key(key: &[u8]) -> Option<EvmKey> {
+    let (prefix, rest) = (key.get(..32)?, &key[32..]);
+    // The hashers are `Blake2_128Concat`: the address and the slot follow their 16 byte hashes.
+    match rest.len() {
+        36 if prefix == map_key("System", "Account") => {
+            Some(EvmKey::Account(H160::from_slice(&rest[16..])))
+        }
+        36 if prefix == map_key("EVM", "AccountCodes") => {
+            Some(EvmKey::Code(H160::from_slice(&rest[16..])))
+        }
+        84 if prefix == map_key("EVM", "AccountStorages") => Some(EvmKey::Storage(
+            H160::from_slice(&rest[16..36]),
+            H256::from_slice(&rest[52..]),
+        )),
+        _ => None,
+    }
+}
+
+/// Keys with a value in the read proof `nodes` under `state_root`.
+///
+/// Keys read without a value are not found: their proof ends on the branch where they would be.
+pub fn proven_keys(state_root: H256, nodes: impl IntoIterator<Item = Vec<u8>>) -> Vec<Vec<u8>> {
+    let nodes: HashMap<H256, Vec<u8>> = nodes
+        .into_iter()
+        .map(|node| (BlakeTwo256::hash(&node), node))
+        .collect();
+    let mut keys = Vec::new();
+    if let Some(root) = nodes.get(&state_root) {
+        collect_keys(&nodes, root, Vec::new(), &mut keys);
+    }
+    keys
+}
+
+/// Walk the node `data` at the nibble `path`, following the children that are in `nodes`.
+fn collect_keys(
+    nodes: &HashMap<H256, Vec<u8>>,
+    data: &[u8],
+    mut path: Vec<u8>,
+    keys: &mut Vec<Vec<u8>>,
+) {
+    let Ok(node) = NodeCodec::<BlakeTwo256>::decode(data) else {
+        return;
+    };
+    let extend = |path: &mut Vec<u8>, partial: NibbleSlice| {
+        path.extend((0..partial.len()).map(|i| partial.at(i)))
+    };
+    let (children, has_value) = match node {
+        Node::Empty => return,
+        Node::Leaf(partial, _) => {
+            extend(&mut path, partial);
+            (Default::default(), true)
+        }
+        Node::Extension(partial, child) => {
+            extend(&mut path, partial);
+            return follow(nodes, child, path, keys);
+        }
+        Node::Branch(children, value) => (children, value.is_some()),
+        Node::NibbledBranch(partial, children, value) => {
+            extend(&mut path, partial);
+            (children, value.is_some())
+        }
+    };
+
+    if has_value && path.len() % 2 == 0 {
+        keys.push(path.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect());
+    }
+    for (nibble, child) in children.into_iter().enumerate() {
+        if let Some(child) = child {
+            let mut path = path.clone();
+            path.push(nibble as u8);
+            follow(nodes, child, path, keys);
+        }
+    }
+}
+
+fn follow(
+    nodes: &HashMap<H256, Vec<u8>>,
+    child: NodeHandle,
+    path: Vec<u8>,
+    keys: &mut Vec<Vec<u8>>,
+) {
+    match child {
+        NodeHandle::Hash(hash) => {
+            if let Some(data) = nodes.get(&H256::from_slice(hash)) {
+                collect_keys(nodes, data, path, keys);
+            }
+        }
+        NodeHandle::Inline(data) => collect_keys(nodes, data, path, keys),
+    }
+}
+
+/// Value of `key` under `state_root`, proven by `nodes`.
+fn read(state_root: H256, nodes: &[Bytes], key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let db =
+        StorageProof::new(nodes.iter().map(|node| node.0.clone())).into_memory_db::<BlakeTwo256>();
+    sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(&db, &state_root, key, None, None)
+        .map_err(|e| Error::InvalidProof(e.to_string()))
+}
+
+fn ensure(proven: bool, what: &'static str) -> Result<(), Error> {
+    if proven {
+        Ok(())
+    } else {
+        Err(Error::Mismatch(what))
+    }
+}
+
+/// Check `proof` against the trusted `state_root`.
+///
+/// `existential_deposit` is the one of the runtime, needed to derive the balance.
+pub fn verify(
+    proof: &EvmProof,
+    state_root: H256,
+    existential_deposit: u128,
+) -> Result<VerifiedAccount, Error> {
+    if proof.state_root != state_root {
+        return Err(Error::StateRoot {
+            expected: state_root,
+            found: proof.state_root,
+        });
+    }
+
+    let address = proof.address;
+    let nodes = &proof.account_proof;
+    let account: AccountInfo = match read(state_root, nodes, &account_key(address))? {
+        Some(value) => {
+            Decode::decode(&mut &value[..]).map_err(|_| Error::Decode("System::Account"))?
+        }
+        None => Default::default(),
+    };
+    let code: Vec<u8> = match read(state_root, nodes, &code_key(address))? {
+        Some(value) => {
+            Decode::decode(&mut &value[..]).map_err(|_| Error::Decode("EVM::AccountCodes"))?
+        }
+        None => Vec::new(),
+    };
+
+    let nonce = U256::from(account.nonce);
+    let balance = U256::from(account.data.reducible_balance(existential_deposit));
+    ensure(nonce == proof.nonce, "nonce")?;
+    ensure(balance == proof.balance, "balance")?;
+    ensure(H256(keccak_256(&code)) == proof.code_hash, "code hash")?;
+
+    let mut storage = Vec::with_capacity(proof.storage_proof.len());
+    for slot in &proof.storage_proof {
+        let value = match read(state_root, &slot.proof, &storage_key(address, slot.key))? {
+            Some(value) => {
+                H256::decode(&mut &value[..]).map_err(|_| Error::Decode("EVM::AccountStorages"))?
+            }
+            None => H256::zero(),
+        };
+        let value = U256::from_big_endian(value.as_bytes());
+        ensure(value == slot.value, "storage value")?;
+        storage.push((slot.key, value));
+    }
+
+    Ok(VerifiedAccount {
+        nonce,
+        balance,
+        code,
+        storage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::BTreeSet, path::PathBuf};
+
+    /// A test vector: a proof, the trusted state root and the expected outcome.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Vector {
+        description: String,
+        state_root: H256,
+        existential_deposit: u128,
+        proof: EvmProof,
+        /// Beginning of the error, if the proof must be refused.
+        error: Option<String>,
+    }
+
+    /// The state the vectors were read from.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct State {
+        state_root: H256,
+        storage: std::collections::BTreeMap<Bytes, Bytes>,
+    }
+
+    fn vectors_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("res/vectors")
+    }
+
+    fn vectors() -> Vec<(String, Vector)> {
+        let mut vectors = Vec::new();
+        for file in std::fs::read_dir(vectors_dir()).unwrap() {
+            let path = file.unwrap().path();
+            if path.file_name().unwrap() == "state.json" {
+                continue;
+            }
+            let vector = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            vectors.push((path.display().to_string(), vector));
+        }
+        vectors
+    }
+
+    #[test]
+    fn verifies_the_test_vectors() {
+        let vectors = vectors();
+        assert!(!vectors.is_empty());
+        for (name, vector) in vectors {
+            let result = verify(&vector.proof, vector.state_root, vector.existential_deposit);
+            match (result, vector.error) {
+                (Ok(account), None) => {
+                    assert_eq!(account.nonce, vector.proof.nonce, "{name}");
+                    assert_eq!(
+                        account.storage.len(),
+                        vector.proof.storage_proof.len(),
+                        "{name}"
+                    );
+                }
+                (Err(e), Some(expected)) => {
+                    assert!(e.to_string().starts_with(&expected), "{name}: {e}")
+                }
+                (result, expected) => panic!(
+                    "{name} ({}): got {result:?}, expected {expected:?}",
+                    vector.description
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn vectors_are_read_proofs_of_the_state() {
+        let state: State =
+            serde_json::from_slice(&std::fs::read(vectors_dir().join("state.json")).unwrap())
+                .unwrap();
+        let storage: Vec<(Vec<u8>, Option<Vec<u8>>)> = state
+            .storage
+            .into_iter()
+            .map(|(key, value)| (key.0, Some(value.0)))
+            .collect();
+        let backend = sp_state_machine::InMemoryBackend::<BlakeTwo256>::from((
+            vec![(None, storage)],
+            sp_core::storage::StateVersion::V1,
+        ));
+        assert_eq!(*backend.root(), state.state_root);
+
+        for (name, vector) in vectors() {
+            if vector.error.is_some() {
+                continue;
+            }
+            let address = vector.proof.address;
+            let keys = [account_key(address), code_key(address)];
+            let proof = sp_state_machine::prove_read(backend.clone(), keys).unwrap();
+            let expected: BTreeSet<_> = proof.into_iter_nodes().collect();
+            let found: BTreeSet<_> = vector
+                .proof
+                .account_proof
+                .iter()
+                .map(|node| node.0.clone())
+                .collect();
+            assert_eq!(found, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn keys_match_the_runtime_storage_layout() {
+        // `twox_128("System") ++ twox_128("Account")`, as in every Substrate chain.
+        const SYSTEM_ACCOUNT: [u8; 32] = [
+            0x26, 0xaa, 0x39, 0x4e, 0xea, 0x56, 0x30, 0xe0, 0x7c, 0x48, 0xae, 0x0c, 0x95, 0x58,
+            0xce, 0xf7, 0xb9, 0x9d, 0x88, 0x0e, 0xc6, 0x81, 0x79, 0x9c, 0x0c, 0xf3, 0x0e, 0x88,
+            0x86, 0x37, 0x1d, 0xa9,
+        ];
+        let address = H160::repeat_byte(0x11);
+        let key = account_key(address);
+        assert_eq!(key[..32], SYSTEM_ACCOUNT);
+        assert_eq!(&key[48..], address.as_bytes());
+        assert_eq!(storage_key(address, H256::zero()).len(), 32 + 36 + 48);
+    }
+
+    #[test]
+    fn recovers_the_keys_read_from_a_proof() {
+        let (contract, other) = (H160::repeat_byte(0xc0), H160::repeat_byte(0xc1));
+        let slot = |n: u64| H256::from_low_u64_be(n);
+        let mut storage = vec![
+            (account_key(contract), vec![0; 80]),
+            (code_key(contract), vec![0x60; 40]),
+            (account_key(other), vec![0; 80]),
+        ];
+        for n in 0..200 {
+            storage.push((
+                storage_key(contract, slot(n)),
+                slot(n + 1).as_bytes().to_vec(),
+            ));
+            storage.push((storage_key(other, slot(n)), slot(n + 1).as_bytes().to_vec()));
+        }
+        let storage = storage
+            .into_iter()
+            .map(|(key, value)| (key, Some(value)))
+            .collect();
+        let backend = sp_state_machine::InMemoryBackend::<BlakeTwo256>::from((
+            vec![(None, storage)],
+            sp_core::storage::StateVersion::V1,
+        ));
+
+        // Many cold slots of one contract, every other one, and a slot that is not set.
+        let mut read: Vec<Vec<u8>> = (0..200)
+            .step_by(2)
+            .map(|n| storage_key(contract, slot(n)))
+            .chain([account_key(contract), code_key(contract)])
+            .collect();
+        let absent = storage_key(contract, slot(1_000));
+        let proof =
+            sp_state_machine::prove_read(backend.clone(), read.iter().chain([&absent])).unwrap();
+
+        let mut found = proven_keys(*backend.root(), proof.into_iter_nodes());
+        found.sort();
+        read.sort();
+        assert_eq!(found, read);
+
+        let decoded: BTreeSet<_> = found.iter().filter_map(|key| decode_key(key)).collect();
+        assert_eq!(decoded.len(), 102);
+        assert!(decoded.contains(&EvmKey::Account(contract)));
+        assert!(decoded.contains(&EvmKey::Code(contract)));
+        assert!(decoded.contains(&EvmKey::Storage(contract, slot(198))));
+        assert!(!decoded.contains(&EvmKey::Storage(contract, slot(1))));
+        assert_eq!(decode_key(&map_key("EVM", "AccountStorages")), None);
+    }
+}
@@ -0,0 +1,49 @@
+-- Same tables as the SQLite backend of `fc-db`, with PostgreSQL types.
+
+CREATE TABLE IF NOT EXISTS blocks (
+    id BIGSERIAL PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    ethereum_block_hash BYTEA NOT NULL,
+    substrate_block_hash BYTEA NOT NULL,
+    ethereum_storage_schema BYTEA NOT NULL,
+    is_canon BOOLEAN NOT NULL,
+    UNIQUE (ethereum_block_hash, substrate_block_hash)
+);
+CREATE INDEX IF NOT EXISTS eth_block_hash_idx ON blocks (ethereum_block_hash);
+CREATE INDEX IF NOT EXISTS substrate_block_hash_idx ON blocks (substrate_block_hash);
+CREATE INDEX IF NOT EXISTS block_number_canon_idx ON blocks (block_number, is_canon);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    id BIGSERIAL PRIMARY KEY,
+    ethereum_transaction_hash BYTEA NOT NULL,
+    substrate_block_hash BYTEA NOT NULL,
+    ethereum_block_hash BYTEA NOT NULL,
+    ethereum_transaction_index INTEGER NOT NULL,
+    UNIQUE (ethereum_transaction_hash, substrate_block_hash)
+);
+CREATE INDEX IF NOT EXISTS eth_tx_hash_idx ON transactions (ethereum_transaction_hash);
+CREATE INDEX IF NOT EXISTS tx_substrate_block_hash_idx ON transactions (substrate_block_hash);
+
+CREATE TABLE IF NOT EXISTS logs (
+    id BIGSERIAL PRIMARY KEY,
+    address BYTEA NOT NULL,
+    topic_1 BYTEA,
+    topic_2 BYTEA,
+    topic_3 BYTEA,
+    topic_4 BYTEA,
+    log_index INTEGER NOT NULL,
+    transaction_index INTEGER NOT NULL,
+    substrate_block_hash BYTEA NOT NULL,
+    UNIQUE (log_index, transaction_index, substrate_block_hash)
+);
+CREATE INDEX IF NOT EXISTS logs_main_idx ON logs (address, topic_1, topic_2, topic_3, topic_4);
+CREATE INDEX IF NOT EXISTS logs_substrate_block_hash_idx ON logs (substrate_block_hash);
+
+-- `status` is 0 once the block metadata is indexed and 1 once its logs are.
+CREATE TABLE IF NOT EXISTS sync_status (
+    id BIGSERIAL PRIMARY KEY,
+    substrate_block_hash BYTEA NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    UNIQUE (substrate_block_hash)
+);
+CREATE INDEX IF NOT EXISTS sync_status_status_idx ON sync_status (status);
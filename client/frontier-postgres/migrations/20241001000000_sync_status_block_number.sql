@@ -0,0 +1,5 @@
+-- Number of the block, so that the worker can resume above the last indexed block even when it
+-- holds no Ethereum block and so has no row in `blocks`. Rows written before are left NULL.
+
+ALTER TABLE sync_status ADD COLUMN IF NOT EXISTS block_number BIGINT;
+CREATE INDEX IF NOT EXISTS sync_status_block_number_idx ON sync_status (block_number);
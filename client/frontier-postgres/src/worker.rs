@@ -0,0 +1,200 @@
+//! Keeps the PostgreSQL backend in sync with the chain.
+
+use crate::Backend;
+use fc_mapping_sync::{EthereumBlockNotification, EthereumBlockNotificationSinks};
+use fc_storage::StorageOverride;
+use futures::StreamExt;
+use polkadot_sdk::*;
+use sc_client_api::{
+    backend::{Backend as BackendT, StorageProvider},
+    client::ImportNotifications,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, UniqueSaturatedInto, Zero};
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+/// Highest indexed blocks [`Worker::resume_from`] looks for a canonical one in.
+const RESUME_CANDIDATES: u32 = 64;
+
+/// Timing of [`SyncWorker`].
+#[derive(Clone, Debug)]
+pub struct SyncWorkerConfig {
+    /// Interval between two scans for canonical blocks that were not indexed on import.
+    pub check_indexed_blocks_interval: Duration,
+    /// Maximum number of blocks indexed by one scan, or behind one imported block.
+    pub max_blocks_per_round: u32,
+}
+
+/// Indexes imported blocks and follows reorgs.
+///
+/// Every imported block is indexed along with its unindexed ancestors. When the best block
+/// changes branch, the canonical flag moves from the retracted blocks to the enacted ones. The
+/// periodic scan indexes the canonical blocks above the last indexed one, so that the worker
+/// catches up after a restart; older gaps are left to `frontier-db backfill`.
+pub struct SyncWorker<Block, C, BE>(PhantomData<(Block, C, BE)>);
+
+impl<Block, C, BE> SyncWorker<Block, C, BE>
+where
+    Block: BlockT<Hash = H256>,
+    C: HeaderBackend<Block> + StorageProvider<Block, BE> + Send + Sync + 'static,
+    BE: BackendT<Block> + 'static,
+{
+    /// Run the worker until the import notification stream ends.
+    pub async fn run(
+        client: Arc<C>,
+        backend: Arc<Backend<Block>>,
+        storage_override: Arc<dyn StorageOverride<Block>>,
+        import_notifications: ImportNotifications<Block>,
+        config: SyncWorkerConfig,
+        pubsub_notification_sinks: Arc<
+            EthereumBlockNotificationSinks<EthereumBlockNotification<Block>>,
+        >,
+    ) {
+        let worker = Worker {
+            client,
+            backend,
+            storage_override,
+            config,
+            _marker: PhantomData,
+        };
+        let mut import_notifications = import_notifications.fuse();
+        let mut check = tokio::time::interval(worker.config.check_indexed_blocks_interval);
+
+        loop {
+            tokio::select! {
+                notification = import_notifications.next() => {
+                    let Some(notification) = notification else { break };
+                    if let Some(route) = &notification.tree_route {
+                        let retracted: Vec<_> = route.retracted().iter().map(|b| b.hash).collect();
+                        let enacted: Vec<_> = route.enacted().iter().map(|b| b.hash).collect();
+                        if let Err(e) = worker.backend.canonicalize(&retracted, &enacted).await {
+                            log::warn!(target: "frontier-postgres", "Failed to canonicalize: {e}");
+                        }
+                    }
+                    if let Err(e) = worker.index_with_ancestors(notification.hash).await {
+                        log::warn!(
+                            target: "frontier-postgres",
+                            "Failed to index block {:?}: {e}",
+                            notification.hash,
+                        );
+                        continue;
+                    }
+                    if notification.is_new_best {
+                        pubsub_notification_sinks.lock().retain(|sink| {
+                            sink.unbounded_send(EthereumBlockNotification {
+                                is_new_best: true,
+                                hash: notification.hash,
+                            })
+                            .is_ok()
+                        });
+                    }
+                }
+                _ = check.tick() => {
+                    if let Err(e) = worker.catch_up().await {
+                        log::warn!(target: "frontier-postgres", "Failed to index missed blocks: {e}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Worker<Block: BlockT, C, BE> {
+    client: Arc<C>,
+    backend: Arc<Backend<Block>>,
+    storage_override: Arc<dyn StorageOverride<Block>>,
+    config: SyncWorkerConfig,
+    _marker: PhantomData<BE>,
+}
+
+impl<Block, C, BE> Worker<Block, C, BE>
+where
+    Block: BlockT<Hash = H256>,
+    C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+    BE: BackendT<Block>,
+{
+    async fn index(&self, hash: H256) -> Result<(), String> {
+        self.backend
+            .index_block::<C, BE>(&*self.client, &*self.storage_override, hash)
+            .await
+    }
+
+    /// Index `hash` and its unindexed ancestors, oldest first.
+    async fn index_with_ancestors(&self, hash: H256) -> Result<(), String> {
+        let sql_error = |e: sqlx::Error| e.to_string();
+        let mut pending = Vec::new();
+        let mut current = hash;
+        while pending.len() < self.config.max_blocks_per_round as usize
+            && !self
+                .backend
+                .is_block_indexed(current)
+                .await
+                .map_err(sql_error)?
+        {
+            pending.push(current);
+            let Some(header) = self.client.header(current).map_err(|e| e.to_string())? else {
+                break;
+            };
+            if header.number().is_zero() {
+                break;
+            }
+            current = *header.parent_hash();
+        }
+        for hash in pending.into_iter().rev() {
+            self.index(hash).await?;
+        }
+        Ok(())
+    }
+
+    /// Number of the first canonical block above the last indexed one.
+    ///
+    /// Blocks without an Ethereum block have no row in `blocks`, so the highest indexed blocks
+    /// are read from `sync_status` and the first canonical one is kept. The last indexed block
+    /// holding an Ethereum block is the fallback if a reorg retracted all of them.
+    async fn resume_from(&self) -> Result<u32, String> {
+        let sql_error = |e: sqlx::Error| e.to_string();
+        for (number, hash) in self
+            .backend
+            .last_indexed_blocks(RESUME_CANDIDATES)
+            .await
+            .map_err(sql_error)?
+        {
+            if self.client.hash(number.into()).map_err(|e| e.to_string())? == Some(hash) {
+                return Ok(number + 1);
+            }
+        }
+        Ok(
+            match self
+                .backend
+                .last_indexed_canon_block()
+                .await
+                .map_err(sql_error)?
+            {
+                Some((number, _)) => number + 1,
+                None => 0,
+            },
+        )
+    }
+
+    /// Index the canonical blocks above the last indexed one.
+    async fn catch_up(&self) -> Result<(), String> {
+        let from = self.resume_from().await?;
+        let best: u32 = self.client.info().best_number.unique_saturated_into();
+        let to = best.min(from.saturating_add(self.config.max_blocks_per_round));
+        for number in from..=to {
+            let Some(hash) = self.client.hash(number.into()).map_err(|e| e.to_string())? else {
+                break;
+            };
+            if !self
+                .backend
+                .is_block_indexed(hash)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                self.index(hash).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! HTTP health and readiness of the node, for load balancers.
+//!
+//! `GET /health` reports the [`Status`] of the node: Substrate sync, peers, GRANDPA finality lag
+//! and the last block indexed by the Frontier mapping worker. It answers `200` while the status
+//! can be read, so it tells whether the node is alive. `GET /ready` answers `200` only when the
+//! status is within the [`Thresholds`], and `503` with the reasons otherwise, so that traffic is
+//! not routed to a node whose Substrate sync is done but whose Ethereum mappings are behind, and
+//! which would answer `null` receipts.
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use polkadot_sdk::*;
+use sc_network_sync::SyncingService;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::SyncOracle;
+use sp_runtime::{traits::Block as BlockT, SaturatedConversion};
+
+/// What the node reports of its sync.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    /// Whether the node is downloading the chain, far from its tip.
+    pub is_syncing: bool,
+    /// Number of peers the node syncs with.
+    pub peers: u32,
+    /// Best block of the node.
+    pub best_block: u64,
+    /// Best block announced by the peers, if any.
+    pub best_seen_block: Option<u64>,
+    /// Last block finalized by GRANDPA.
+    pub finalized_block: u64,
+    /// Best blocks not finalized yet.
+    pub finality_lag: u64,
+    /// Last best block indexed by the Frontier mapping worker.
+    pub frontier_indexed_block: u64,
+    /// Best blocks not indexed yet by the Frontier mapping worker.
+    pub frontier_lag: u64,
+}
+
+/// Bounds of the [`Status`] of a ready node.
+#[derive(Clone, Copy, Debug)]
+pub struct Thresholds {
+    /// Most best blocks not finalized yet.
+    pub max_finality_lag: u64,
+    /// Most best blocks not indexed yet by the Frontier mapping worker.
+    pub max_frontier_lag: u64,
+    /// Fewest peers.
+    pub min_peers: u32,
+}
+
+impl Thresholds {
+    /// Why a node of `status` is not ready, nothing if it is.
+    pub fn check(&self, status: &Status) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if status.is_syncing {
+            reasons.push("the node is syncing".to_string());
+        }
+        if status.peers < self.min_peers {
+            reasons.push(format!(
+                "{} peers, fewer than {}",
+                status.peers, self.min_peers
+            ));
+        }
+        if status.finality_lag > self.max_finality_lag {
+            reasons.push(format!(
+                "finality lags {} blocks, more than {}",
+                status.finality_lag, self.max_finality_lag
+            ));
+        }
+        if status.frontier_lag > self.max_frontier_lag {
+            reasons.push(format!(
+                "the Frontier mapping lags {} blocks, more than {}",
+                status.frontier_lag, self.max_frontier_lag
+            ));
+        }
+        reasons
+    }
+}
+
+/// Where the [`Status`] is read from.
+#[async_trait::async_trait]
+pub trait HealthSource: Send + Sync {
+    /// The current status of the node.
+    async fn status(&self) -> Result<Status, String>;
+}
+
+/// The [`Status`] of a full node, read from its client, syncing service and the last block
+/// indexed by the Frontier mapping worker.
+///
+/// The last indexed block is not `latest_block_hash` of the Frontier backend: the key-value
+/// backend answers it with the best block, indexed or not. The node reads it from the syncing
+/// tips of the key-value backend or the sync status of the SQL backends and keeps it up to date.
+pub struct NodeHealth<B: BlockT, C> {
+    client: Arc<C>,
+    sync: Arc<SyncingService<B>>,
+    last_indexed_block: Arc<AtomicU64>,
+}
+
+impl<B: BlockT, C> NodeHealth<B, C> {
+    /// Read the status from `client`, `sync` and `last_indexed_block`.
+    pub fn new(
+        client: Arc<C>,
+        sync: Arc<SyncingService<B>>,
+        last_indexed_block: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            client,
+            sync,
+            last_indexed_block,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<B, C> HealthSource for NodeHealth<B, C>
+where
+    B: BlockT,
+    C: HeaderBackend<B> + Send + Sync + 'static,
+{
+    async fn status(&self) -> Result<Status, String> {
+        let info = self.client.info();
+        let sync = self
+            .sync
+            .status()
+            .await
+            .map_err(|()| "the syncing service is down")?;
+
+        let best_block = info.best_number.saturated_into();
+        let finalized_block = info.finalized_number.saturated_into();
+        let frontier_indexed_block = self.last_indexed_block.load(Ordering::Relaxed);
+        Ok(Status {
+            is_syncing: self.sync.is_major_syncing(),
+            peers: sync.num_peers,
+            best_block,
+            best_seen_block: sync.best_seen_block.map(|number| number.saturated_into()),
+            finalized_block,
+            finality_lag: best_block.saturating_sub(finalized_block),
+            frontier_indexed_block,
+            frontier_lag: best_block.saturating_sub(frontier_indexed_block),
+        })
+    }
+}
+
+/// Serve `/health` and `/ready` of `source` at `http://{addr}` until the server fails.
+pub async fn serve(
+    addr: SocketAddr,
+    source: Arc<dyn HealthSource>,
+    thresholds: Thresholds,
+) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let source = source.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle(source.clone(), thresholds, request)
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    log::info!("Health server started at http://{addr}/health and http://{addr}/ready");
+    server.await
+}
+
+async fn handle(
+    source: Arc<dyn HealthSource>,
+    thresholds: Thresholds,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let ready = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/health") => false,
+        (&Method::GET, "/ready") => true,
+        _ => return Ok(response(StatusCode::NOT_FOUND, serde_json::Value::Null)),
+    };
+
+    let status = match source.status().await {
+        Ok(status) => status,
+        Err(error) => {
+            let body = serde_json::json!({ "ready": false, "error": error });
+            return Ok(response(StatusCode::SERVICE_UNAVAILABLE, body));
+        }
+    };
+    let reasons = thresholds.check(&status);
+    let code = if ready && !reasons.is_empty() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    let mut body = serde_json::to_value(&status).expect("the status serializes to JSON; qed");
+    body["ready"] = reasons.is_empty().into();
+    body["reasons"] = reasons.into();
+    Ok(response(code, body))
+}
+
+fn response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    let body = if body.is_null() {
+        Body::empty()
+    } else {
+        Body::from(body.to_string())
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .expect("the response is valid; qed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        max_finality_lag: 20,
+        max_frontier_lag: 5,
+        min_peers: 1,
+    };
+
+    struct MockSource(Result<Status, String>);
+
+    #[async_trait::async_trait]
+    impl HealthSource for MockSource {
+        async fn status(&self) -> Result<Status, String> {
+            self.0.clone()
+        }
+    }
+
+    fn synced() -> Status {
+        Status {
+            is_syncing: false,
+            peers: 8,
+            best_block: 1_000,
+            best_seen_block: Some(1_000),
+            finalized_block: 998,
+            finality_lag: 2,
+            frontier_indexed_block: 1_000,
+            frontier_lag: 0,
+        }
+    }
+
+    async fn get(status: Result<Status, String>, path: &str) -> (StatusCode, Value) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = handle(Arc::new(MockSource(status)), THRESHOLDS, request)
+            .await
+            .unwrap();
+        let code = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (code, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[test]
+    fn thresholds_give_the_reasons_a_node_is_not_ready() {
+        assert!(THRESHOLDS.check(&synced()).is_empty());
+
+        let behind = Status {
+            peers: 0,
+            frontier_indexed_block: 900,
+            frontier_lag: 100,
+            ..synced()
+        };
+        assert_eq!(
+            THRESHOLDS.check(&behind),
+            [
+                "0 peers, fewer than 1",
+                "the Frontier mapping lags 100 blocks, more than 5"
+            ]
+        );
+
+        let syncing = Status {
+            is_syncing: true,
+            finality_lag: 21,
+            ..synced()
+        };
+        assert_eq!(
+            THRESHOLDS.check(&syncing),
+            [
+                "the node is syncing",
+                "finality lags 21 blocks, more than 20"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn ready_only_when_the_frontier_mapping_has_caught_up() {
+        let (code, body) = get(Ok(synced()), "/ready").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["ready"], json!(true));
+        assert_eq!(body["frontierIndexedBlock"], json!(1_000));
+
+        let behind = Status {
+            frontier_indexed_block: 900,
+            frontier_lag: 100,
+            ..synced()
+        };
+        let (code, body) = get(Ok(behind.clone()), "/ready").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], json!(false));
+        assert_eq!(body["reasons"].as_array().unwrap().len(), 1);
+
+        // The node is alive all the same.
+        let (code, body) = get(Ok(behind), "/health").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["frontierLag"], json!(100));
+    }
+
+    #[tokio::test]
+    async fn unavailable_when_the_status_cannot_be_read() {
+        let (code, body) = get(Err("database closed".into()), "/health").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], json!("database closed"));
+
+        let (code, _) = get(Ok(synced()), "/metrics").await;
+        assert_eq!(code, StatusCode::NOT_FOUND);
+    }
+}
@@ -17,10 +17,24 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 fn main() {
+    embed_chain_specs();
     #[cfg(feature = "cli")]
     cli::main();
 }
 
+/// Embed the raw chain specs checked in under `res/`, see `chain_spec::verify`.
+///
+/// A missing spec is not an error: the binary then needs `--chain <file>` for that network.
+fn embed_chain_specs() {
+    println!("cargo:rerun-if-changed=res");
+    for network in ["scs", "tscs"] {
+        println!("cargo:rustc-check-cfg=cfg(embedded_{network}_spec)");
+        if std::path::Path::new(&format!("res/{network}-chain-spec.json")).exists() {
+            println!("cargo:rustc-cfg=embedded_{network}_spec");
+        }
+    }
+}
+
 #[cfg(feature = "cli")]
 mod cli {
     include!("src/cli.rs");
@@ -21,8 +21,9 @@
 use hex_literal::hex;
 use common_runtime::{AccountId};
 use kitchensink_mainnet_runtime::{
-    constants::currency::*, wasm_binary_unwrap, Block, MaxNominations, SessionKeys,
-    StakerStatus,
+    constants::currency::*,
+    predeploys::{self, Predeploy},
+    wasm_binary_unwrap, Block, MaxNominations, SessionKeys, StakerStatus,
 };
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use polkadot_sdk::*;
@@ -76,9 +77,22 @@ pub type ChainSpec = sc_service::GenericChainSpec<Extensions>;
 // 	ChainSpec::from_json_bytes(&include_bytes!("../res/flaming-fir.json")[..])
 // }
 
+/// The canonical raw chain spec of the `scs` network, embedded from `res/scs-chain-spec.json`.
+#[cfg(embedded_scs_spec)]
 pub fn scs_config() -> Result<ChainSpec, String> {
     ChainSpec::from_json_bytes(&include_bytes!("../../res/scs-chain-spec.json")[..])
 }
+
+/// The canonical raw chain spec of the `scs` network, embedded from `res/scs-chain-spec.json`.
+#[cfg(not(embedded_scs_spec))]
+pub fn scs_config() -> Result<ChainSpec, String> {
+    Err(
+        "This binary was built without `node/cli/res/scs-chain-spec.json`; \
+         pass the network's raw chain spec with `--chain <file>`"
+            .into(),
+    )
+}
+
 fn session_keys(
     // ed25519
     grandpa: GrandpaId,
@@ -221,6 +235,7 @@ fn staging_testnet_config_genesis(chain_id: u32) -> serde_json::Value {
         Some(endowed_accounts),
         extra_endowed_accounts_balance,
         chain_id,
+        predeploys::bundled(),
     )
 }
 
@@ -413,6 +428,7 @@ pub fn testnet_genesis(
     endowed_accounts: Option<Vec<AccountId>>,
     extra_endowed_accounts_balance: Vec<(AccountId, u128)>,
     evm_chain_id: u32,
+    predeploys: Vec<Predeploy>,
 ) -> serde_json::Value {
     let (initial_authorities, endowed_accounts, num_endowed_accounts, stakers) = configure_accounts(
         initial_authorities,
@@ -421,49 +437,7 @@ pub fn testnet_genesis(
         STASH,
     );
 
-    // let evm_accounts = {
-    // 	let mut map = BTreeMap::new();
-    // map.insert(
-    // 	// H160 address of Alice dev account
-    // 	// Derived from SS58 (42 prefix) address
-    // 	// SS58: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY
-    // 	// hex: 0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d
-    // 	// Using the full hex key, truncating to the first 20 bytes (the first 40 hex chars)
-    // 	H160::from_str("d43593c715fdd31c61141abd04a99fd6822c8558")
-    // 		.expect("internal H160 is valid; qed"),
-    // 	fp_evm::GenesisAccount {
-    // 		balance: U256::from_str("0xffffffffffffffffffffffffffffffff")
-    // 			.expect("internal U256 is valid; qed"),
-    // 		code: Default::default(),
-    // 		nonce: Default::default(),
-    // 		storage: Default::default(),
-    // 	},
-    // );
-    // map.insert(
-    // 	// H160 address of CI test runner account
-    // 	H160::from_str("6be02d1d3665660d22ff9624b7be0551ee1ac91b")
-    // 		.expect("internal H160 is valid; qed"),
-    // 	fp_evm::GenesisAccount {
-    // 		balance: U256::from_str("0xffffffffffffffffffffffffffffffff")
-    // 			.expect("internal U256 is valid; qed"),
-    // 		code: Default::default(),
-    // 		nonce: Default::default(),
-    // 		storage: Default::default(),
-    // 	},
-    // );
-    // map.insert(
-    // 	// H160 address for benchmark usage
-    // 	H160::from_str("1000000000000000000000000000000000000001")
-    // 		.expect("internal H160 is valid; qed"),
-    // 	fp_evm::GenesisAccount {
-    // 		nonce: U256::from(1),
-    // 		balance: U256::from(1_000_000_000_000_000_000_000_000u128),
-    // 		storage: Default::default(),
-    // 		code: vec![0x00],
-    // 	},
-    // );
-    // 	map
-    // };
+    let evm_accounts = predeploys::genesis_accounts(&predeploys);
 
     serde_json::json!({
         "balances": {
@@ -528,7 +502,7 @@ pub fn testnet_genesis(
             "minJoinBond": 1 * DOLLARS,
         },
         "evmChainId": { "chainId": evm_chain_id },
-        // "evm": { "accounts": evm_accounts },
+        "evm": { "accounts": evm_accounts },
     })
 }
 
@@ -556,9 +530,133 @@ fn development_config_genesis_json() -> serde_json::Value {
         ))]),
         extra_endowed_accounts_balance,
         42u32,
+        predeploys::bundled(),
     )
 }
 
+/// Read predeploys from a JSON file, in the format of
+/// `runtime/mainnet/src/predeploys/predeploys.json`, used instead of [`predeploys::bundled`] by
+/// custom chains setting `predeploys` in their `validators.toml`.
+pub fn load_predeploys(path: &std::path::Path) -> Result<Vec<Predeploy>, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    predeploys::parse(&json).map_err(|e| format!("invalid predeploys in {}: {e}", path.display()))
+}
+
+/// Genesis of a custom chain described by a `validators.toml`, see [`validators`].
+///
+/// Validators are bonded with their `bond` (default [`STASH`]) and council members stake
+/// [`STASH`] in the elections pallet; both are endowed on top of the configured balances.
+pub fn config_genesis(config: &ChainConfig, predeploys: &[Predeploy]) -> serde_json::Value {
+    let mut balances: BTreeMap<AccountId, Balance> = config.endowed.iter().cloned().collect();
+    let mut endow = |who: &AccountId, amount: Balance| {
+        let balance = balances.entry(who.clone()).or_default();
+        *balance = balance.saturating_add(amount);
+    };
+
+    let stakers = config
+        .validators
+        .iter()
+        .map(|v| {
+            let bond = v.bond.unwrap_or(STASH);
+            endow(&v.stash, bond);
+            (
+                v.stash.clone(),
+                v.stash.clone(),
+                bond,
+                StakerStatus::<AccountId>::Validator,
+            )
+        })
+        .collect::<Vec<_>>();
+    for member in &config.council {
+        endow(member, STASH);
+    }
+
+    serde_json::json!({
+        "balances": {
+            "balances": balances.into_iter().collect::<Vec<_>>(),
+        },
+        "session": {
+            "keys": config
+                .validators
+                .iter()
+                .map(|v| {
+                    let keys = v.session_keys.clone();
+                    (
+                        v.stash.clone(),
+                        v.stash.clone(),
+                        session_keys(
+                            keys.grandpa,
+                            keys.babe,
+                            keys.im_online,
+                            keys.authority_discovery,
+                            keys.mixnet,
+                            keys.beefy,
+                        ),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        },
+        "staking": {
+            "validatorCount": config.validators.len() as u32,
+            "minimumValidatorCount": config.validators.len() as u32,
+            "invulnerables": config.validators.iter().map(|v| v.stash.clone()).collect::<Vec<_>>(),
+            "slashRewardFraction": Perbill::from_percent(10),
+            "stakers": stakers,
+        },
+        "elections": {
+            "members": config.council.iter().map(|m| (m.clone(), STASH)).collect::<Vec<_>>(),
+        },
+        "vesting": {
+            "vesting": config
+                .vesting
+                .iter()
+                .map(|v| (v.account.clone(), v.begin, v.length, v.liquid))
+                .collect::<Vec<_>>(),
+        },
+        "sudo": { "key": config.sudo.clone() },
+        "babe": {
+            "epochConfig": Some(kitchensink_mainnet_runtime::BABE_GENESIS_EPOCH_CONFIG),
+        },
+        "society": { "pot": 0 },
+        "nominationPools": {
+            "minCreateBond": 10 * DOLLARS,
+            "minJoinBond": 1 * DOLLARS,
+        },
+        "evmChainId": { "chainId": config.evm_chain_id },
+        "evm": { "accounts": predeploys::genesis_accounts(predeploys) },
+    })
+}
+
+/// Chain spec of a custom chain described by the `validators.toml` at `path`.
+pub fn config_file_chain_spec(path: &std::path::Path) -> Result<ChainSpec, String> {
+    let config = validators::load(path)?;
+    let predeploys = match &config.predeploys {
+        Some(path) => load_predeploys(path)?,
+        None => predeploys::bundled(),
+    };
+    let properties = serde_json::json!({
+        "isEthereum": true,
+        "tokenDecimals": 18,
+        "tokenSymbol": config.token_symbol,
+    });
+    let mut builder = ChainSpec::builder(wasm_binary_unwrap(), Default::default())
+        .with_name(&config.name)
+        .with_id(&config.id)
+        .with_chain_type(config.chain_type.clone())
+        .with_properties(
+            properties
+                .as_object()
+                .cloned()
+                .expect("object literal; qed"),
+        )
+        .with_genesis_config_patch(config_genesis(&config, &predeploys));
+    if let Some(protocol_id) = &config.protocol_id {
+        builder = builder.with_protocol_id(protocol_id);
+    }
+    Ok(builder.build())
+}
+
 /// Development config (single validator Alice).
 pub fn development_config() -> ChainSpec {
     ChainSpec::builder(wasm_binary_unwrap(), Default::default())
@@ -662,6 +760,42 @@ pub(crate) mod tests {
         local_testnet_config().build_storage().unwrap();
     }
 
+    #[test]
+    fn embedded_spec_matches_pinned_genesis() {
+        use crate::chain_spec::verify;
+
+        match scs_config() {
+            Ok(spec) => {
+                let network = verify::known_network(&spec).expect("`scs` is a known network");
+                let expected = network
+                    .genesis_hash
+                    .expect("the genesis hash of the embedded `scs` spec is pinned");
+                assert_eq!(verify::genesis_hash(&spec).unwrap(), expected);
+            }
+            // Not checked in yet, see `chain_spec::verify`.
+            Err(e) => assert!(e.contains("res/scs-chain-spec.json"), "{e}"),
+        }
+    }
+
+    #[test]
+    fn spec_with_another_genesis_is_refused() {
+        use crate::chain_spec::verify::{self, KnownNetwork};
+
+        let spec = staging_testnet_config();
+        let actual = verify::genesis_hash(&spec).unwrap();
+        let pinned = |genesis_hash| {
+            [KnownNetwork {
+                id: "scs",
+                chain: "mainnet",
+                genesis_hash: Some(genesis_hash),
+            }]
+        };
+
+        assert!(verify::check_genesis_against(&spec, &pinned(actual)).is_ok());
+        let error = verify::check_genesis_against(&spec, &pinned(Default::default())).unwrap_err();
+        assert!(error.contains("Refusing to start"), "{error}");
+    }
+
     // #[test]
     // fn test_staging_test_net_chain_spec() {
     // 	staging_testnet_config().build_storage().unwrap();
@@ -2,3 +2,5 @@
 pub mod mainnet;
 #[cfg(feature="tscs")]
 pub mod testnet;
+pub mod validators;
+pub mod verify;
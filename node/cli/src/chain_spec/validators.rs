@@ -0,0 +1,485 @@
+//! Validator sets and genesis allocations of custom chains, read from a TOML file by
+//! `build-spec --from-config`.
+//!
+//! ```toml
+//! name = "Consortium"
+//! id = "consortium"
+//! chain_type = "Live"            # Development, Local or Live
+//! token_symbol = "CSM"
+//! evm_chain_id = 4242
+//! sudo = "0x79BD79C274C845E8a29378513c0053b19395E863"
+//! council = ["0x8B3f123cf9F3b2E147142d3e99396695c09A34E7"]
+//! predeploys = "predeploys.json" # optional, replaces the bundled predeploys
+//!
+//! [[validators]]
+//! stash = "0x6816562B9589ccf4297952A8558A0451c1EB5aEc"
+//! bond = "100000000000000000000"
+//! [validators.session_keys]
+//! grandpa = "0x1bc2...9364"              # ed25519
+//! babe = "0xa4b2...6f77"                 # sr25519
+//! im_online = "0x..."                    # sr25519
+//! authority_discovery = "0x..."          # sr25519
+//! mixnet = "0x..."                       # sr25519
+//! beefy = "0x03e6...a5b6"                # compressed ecdsa
+//!
+//! [[endowed]]
+//! account = "0x93A3A1c3dbccdbA8Df744a97f4Cc702e2F8663D1"
+//! balance = "50000000000000000000000000"
+//!
+//! [[vesting]]
+//! account = "0x93A3A1c3dbccdbA8Df744a97f4Cc702e2F8663D1"
+//! begin = 0
+//! length = 5256000
+//! liquid = "10000000000000000000000000"
+//! ```
+//!
+//! Balances are strings in the smallest unit (18 decimals). Validator bonds and the council stake
+//! are endowed on top of the `endowed` balances. A session key may not be used by two validators
+//! or for two roles.
+//!
+//! `predeploys` is resolved relative to the TOML file and read in the format of
+//! `pallet_evm_predeploys::parse`; without it the chain gets the predeploys bundled in the runtime.
+
+use common_runtime::AccountId;
+use node_primitives::{Balance, BlockNumber};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use polkadot_sdk::*;
+use sc_service::ChainType;
+use serde::Deserialize;
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_consensus_beefy::ecdsa_crypto::AuthorityId as BeefyId;
+use sp_consensus_grandpa::AuthorityId as GrandpaId;
+use sp_core::{
+    crypto::{ByteArray, UncheckedInto},
+    H160,
+};
+use sp_mixnet::types::AuthorityId as MixnetId;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Session keys of one validator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionKeysConfig {
+    pub grandpa: GrandpaId,
+    pub babe: BabeId,
+    pub im_online: ImOnlineId,
+    pub authority_discovery: AuthorityDiscoveryId,
+    pub mixnet: MixnetId,
+    pub beefy: BeefyId,
+}
+
+/// A genesis validator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidatorConfig {
+    /// Stash, also used as controller and session account.
+    pub stash: AccountId,
+    /// Amount bonded by the stash, `None` for the runtime default.
+    pub bond: Option<Balance>,
+    pub session_keys: SessionKeysConfig,
+}
+
+/// A vesting schedule, in the format of `pallet_vesting`'s genesis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingConfig {
+    pub account: AccountId,
+    /// Block at which the schedule starts.
+    pub begin: BlockNumber,
+    /// Number of blocks over which the locked amount unlocks.
+    pub length: BlockNumber,
+    /// Part of the balance that is free from the start.
+    pub liquid: Balance,
+}
+
+/// A validated `validators.toml`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainConfig {
+    pub name: String,
+    pub id: String,
+    pub chain_type: ChainType,
+    pub protocol_id: Option<String>,
+    pub token_symbol: String,
+    pub evm_chain_id: u32,
+    pub sudo: Option<AccountId>,
+    pub council: Vec<AccountId>,
+    pub validators: Vec<ValidatorConfig>,
+    pub endowed: Vec<(AccountId, Balance)>,
+    pub vesting: Vec<VestingConfig>,
+    /// JSON file of predeploys replacing the bundled ones.
+    pub predeploys: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawBalance {
+    Integer(u64),
+    String(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSessionKeys {
+    grandpa: String,
+    babe: String,
+    im_online: String,
+    authority_discovery: String,
+    mixnet: String,
+    beefy: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawValidator {
+    stash: H160,
+    bond: Option<RawBalance>,
+    session_keys: RawSessionKeys,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEndowed {
+    account: H160,
+    balance: RawBalance,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawVesting {
+    account: H160,
+    begin: BlockNumber,
+    length: BlockNumber,
+    liquid: RawBalance,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    name: String,
+    id: String,
+    #[serde(default = "default_chain_type")]
+    chain_type: ChainType,
+    protocol_id: Option<String>,
+    token_symbol: String,
+    evm_chain_id: u32,
+    sudo: Option<H160>,
+    #[serde(default)]
+    council: Vec<H160>,
+    validators: Vec<RawValidator>,
+    #[serde(default)]
+    endowed: Vec<RawEndowed>,
+    #[serde(default)]
+    vesting: Vec<RawVesting>,
+    predeploys: Option<PathBuf>,
+}
+
+fn default_chain_type() -> ChainType {
+    ChainType::Live
+}
+
+fn balance(value: RawBalance, what: &str) -> Result<Balance, String> {
+    match value {
+        RawBalance::Integer(value) => Ok(value.into()),
+        RawBalance::String(value) => value
+            .parse()
+            .map_err(|e| format!("{what}: `{value}` is not a balance: {e}")),
+    }
+}
+
+fn key_bytes<const N: usize>(value: &str, what: &str) -> Result<[u8; N], String> {
+    let bytes = array_bytes::hex2bytes(value).map_err(|e| format!("{what}: invalid hex: {e:?}"))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("{what}: expected {N} bytes, got {}", bytes.len()))
+}
+
+impl RawSessionKeys {
+    fn parse(self, validator: usize) -> Result<SessionKeysConfig, String> {
+        let what = |role: &str| format!("validators[{validator}].session_keys.{role}");
+
+        let beefy = key_bytes::<33>(&self.beefy, &what("beefy"))?;
+        if !matches!(beefy[0], 0x02 | 0x03) {
+            return Err(format!(
+                "{}: not a compressed ecdsa public key",
+                what("beefy")
+            ));
+        }
+
+        Ok(SessionKeysConfig {
+            grandpa: key_bytes::<32>(&self.grandpa, &what("grandpa"))?.unchecked_into(),
+            babe: key_bytes::<32>(&self.babe, &what("babe"))?.unchecked_into(),
+            im_online: key_bytes::<32>(&self.im_online, &what("im_online"))?.unchecked_into(),
+            authority_discovery: key_bytes::<32>(
+                &self.authority_discovery,
+                &what("authority_discovery"),
+            )?
+            .unchecked_into(),
+            mixnet: key_bytes::<32>(&self.mixnet, &what("mixnet"))?.unchecked_into(),
+            beefy: beefy.unchecked_into(),
+        })
+    }
+}
+
+impl SessionKeysConfig {
+    fn raw_keys(&self) -> [(&'static str, Vec<u8>); 6] {
+        [
+            ("grandpa", self.grandpa.to_raw_vec()),
+            ("babe", self.babe.to_raw_vec()),
+            ("im_online", self.im_online.to_raw_vec()),
+            ("authority_discovery", self.authority_discovery.to_raw_vec()),
+            ("mixnet", self.mixnet.to_raw_vec()),
+            ("beefy", self.beefy.to_raw_vec()),
+        ]
+    }
+}
+
+/// Parse and validate the content of a `validators.toml`.
+pub fn parse(toml: &str) -> Result<ChainConfig, String> {
+    let raw: RawConfig = toml::from_str(toml).map_err(|e| e.to_string())?;
+
+    if raw.validators.is_empty() {
+        return Err("at least one validator is required".into());
+    }
+
+    let mut validators = Vec::with_capacity(raw.validators.len());
+    let mut stashes = BTreeSet::new();
+    let mut keys = BTreeMap::new();
+    for (i, validator) in raw.validators.into_iter().enumerate() {
+        if !stashes.insert(validator.stash) {
+            return Err(format!(
+                "validators[{i}]: stash {:?} is listed twice",
+                validator.stash
+            ));
+        }
+        let session_keys = validator.session_keys.parse(i)?;
+        for (role, key) in session_keys.raw_keys() {
+            if let Some((other, other_role)) = keys.insert(key, (i, role)) {
+                return Err(format!(
+                    "validators[{i}].session_keys.{role} is already used as \
+                     validators[{other}].session_keys.{other_role}"
+                ));
+            }
+        }
+        validators.push(ValidatorConfig {
+            stash: validator.stash.into(),
+            bond: validator
+                .bond
+                .map(|bond| balance(bond, &format!("validators[{i}].bond")))
+                .transpose()?,
+            session_keys,
+        });
+    }
+
+    let mut endowed = Vec::with_capacity(raw.endowed.len());
+    let mut endowed_accounts = BTreeSet::new();
+    for (i, entry) in raw.endowed.into_iter().enumerate() {
+        if !endowed_accounts.insert(entry.account) {
+            return Err(format!("endowed[{i}]: {:?} is listed twice", entry.account));
+        }
+        endowed.push((
+            entry.account.into(),
+            balance(entry.balance, &format!("endowed[{i}]"))?,
+        ));
+    }
+
+    let mut council = Vec::with_capacity(raw.council.len());
+    for member in raw.council {
+        if council.contains(&AccountId::from(member)) {
+            return Err(format!("council: {member:?} is listed twice"));
+        }
+        council.push(member.into());
+    }
+
+    let mut vesting = Vec::with_capacity(raw.vesting.len());
+    let mut vested = BTreeSet::new();
+    for (i, schedule) in raw.vesting.into_iter().enumerate() {
+        if !endowed_accounts.contains(&schedule.account) {
+            return Err(format!(
+                "vesting[{i}]: {:?} is not endowed",
+                schedule.account
+            ));
+        }
+        if !vested.insert(schedule.account) {
+            return Err(format!(
+                "vesting[{i}]: {:?} already has a schedule",
+                schedule.account
+            ));
+        }
+        if schedule.length == 0 {
+            return Err(format!("vesting[{i}]: length must be at least one block"));
+        }
+        vesting.push(VestingConfig {
+            account: schedule.account.into(),
+            begin: schedule.begin,
+            length: schedule.length,
+            liquid: balance(schedule.liquid, &format!("vesting[{i}].liquid"))?,
+        });
+    }
+
+    Ok(ChainConfig {
+        name: raw.name,
+        id: raw.id,
+        chain_type: raw.chain_type,
+        protocol_id: raw.protocol_id,
+        token_symbol: raw.token_symbol,
+        evm_chain_id: raw.evm_chain_id,
+        sudo: raw.sudo.map(Into::into),
+        council,
+        validators,
+        endowed,
+        vesting,
+        predeploys: raw.predeploys,
+    })
+}
+
+/// Read and validate a `validators.toml`.
+pub fn load(path: &Path) -> Result<ChainConfig, String> {
+    let toml = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let mut config =
+        parse(&toml).map_err(|e| format!("invalid validator config {}: {e}", path.display()))?;
+    if let (Some(predeploys), Some(dir)) = (&mut config.predeploys, path.parent()) {
+        *predeploys = dir.join(&*predeploys);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRANDPA: &str = "0x1bc2636a29f850f49a69ccb416cd14812759651205d6b655298307f9365e9364";
+    const BEEFY: &str = "0x03e6456ae62e2fd038fa90458fc76481bfc2b4eb9529a17891ecb10f745a06a5b6";
+
+    fn sr25519(byte: u8) -> String {
+        format!("0x{}", hex_string(&[byte; 32]))
+    }
+
+    fn hex_string(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn config(validators: &str) -> String {
+        format!(
+            r#"
+            name = "Consortium"
+            id = "consortium"
+            token_symbol = "CSM"
+            evm_chain_id = 4242
+            sudo = "0x79BD79C274C845E8a29378513c0053b19395E863"
+
+            [[endowed]]
+            account = "0x93A3A1c3dbccdbA8Df744a97f4Cc702e2F8663D1"
+            balance = "50000000000000000000000000"
+
+            [[vesting]]
+            account = "0x93A3A1c3dbccdbA8Df744a97f4Cc702e2F8663D1"
+            begin = 0
+            length = 100
+            liquid = "1000"
+
+            {validators}
+            "#
+        )
+    }
+
+    fn validator(stash: &str, first_sr25519: u8, beefy: &str) -> String {
+        format!(
+            r#"
+            [[validators]]
+            stash = "{stash}"
+            [validators.session_keys]
+            grandpa = "{}"
+            babe = "{}"
+            im_online = "{}"
+            authority_discovery = "{}"
+            mixnet = "{}"
+            beefy = "{beefy}"
+            "#,
+            sr25519(first_sr25519 + 4),
+            sr25519(first_sr25519),
+            sr25519(first_sr25519 + 1),
+            sr25519(first_sr25519 + 2),
+            sr25519(first_sr25519 + 3),
+        )
+    }
+
+    #[test]
+    fn parses_a_valid_config() {
+        let config = parse(&config(&validator(
+            "0x6816562B9589ccf4297952A8558A0451c1EB5aEc",
+            1,
+            BEEFY,
+        )))
+        .unwrap();
+        assert_eq!(config.validators.len(), 1);
+        assert_eq!(config.chain_type, ChainType::Live);
+        assert_eq!(config.endowed[0].1, 50_000_000 * 10u128.pow(18));
+        assert_eq!(config.vesting[0].liquid, 1000);
+        assert_eq!(config.predeploys, None);
+    }
+
+    #[test]
+    fn resolves_predeploys_next_to_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("validators.toml");
+        let toml = config(&validator(
+            "0x6816562B9589ccf4297952A8558A0451c1EB5aEc",
+            1,
+            BEEFY,
+        ))
+        .replacen(
+            "evm_chain_id",
+            "predeploys = \"predeploys.json\"\nevm_chain_id",
+            1,
+        );
+        std::fs::write(&path, toml).unwrap();
+
+        let config = load(&path).unwrap();
+        assert_eq!(config.predeploys, Some(dir.path().join("predeploys.json")));
+    }
+
+    #[test]
+    fn rejects_duplicate_stashes_and_keys() {
+        let stash = "0x6816562B9589ccf4297952A8558A0451c1EB5aEc";
+        let twice = format!(
+            "{}{}",
+            validator(stash, 1, BEEFY),
+            validator(stash, 10, BEEFY)
+        );
+        assert!(parse(&config(&twice)).unwrap_err().contains("listed twice"));
+
+        let other = "0x3E3d44a81C8773d7C27270E2f9830f25d87a5f41";
+        let shared = format!(
+            "{}{}",
+            validator(stash, 1, BEEFY),
+            validator(other, 10, BEEFY)
+        );
+        assert!(parse(&config(&shared))
+            .unwrap_err()
+            .contains("already used"));
+
+        let reused = validator(stash, 1, BEEFY)
+            .replace(&sr25519(5), GRANDPA)
+            .replace(
+                &format!("babe = \"{}\"", sr25519(1)),
+                &format!("babe = \"{GRANDPA}\""),
+            );
+        assert!(parse(&config(&reused))
+            .unwrap_err()
+            .contains("already used"));
+    }
+
+    #[test]
+    fn rejects_malformed_keys() {
+        let stash = "0x6816562B9589ccf4297952A8558A0451c1EB5aEc";
+        let uncompressed = format!("0x04{}", &BEEFY[4..]);
+        assert!(parse(&config(&validator(stash, 1, &uncompressed)))
+            .unwrap_err()
+            .contains("compressed ecdsa"));
+        assert!(parse(&config(&validator(stash, 1, GRANDPA)))
+            .unwrap_err()
+            .contains("expected 33 bytes"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Genesis pinning for the public networks.
+//!
+//! `build.rs` embeds the raw chain specs found under `node/cli/res/` into the binary. The
+//! canonical specs of `scs` and `tscs` are not checked in yet, so until they are `--chain
+//! mainnet`/`testnet` fail and the spec has to be given as a file. Genesis hashes are pinned in
+//! [`KNOWN_NETWORKS`]: a spec whose `id` names a network with a pinned hash is only accepted if it
+//! produces that genesis block, whether it is the embedded copy or a file given with `--chain`.
+
+use codec::Encode;
+use common_runtime::opaque::Block;
+use polkadot_sdk::*;
+use sc_chain_spec::ChainSpec;
+use sc_executor::WasmExecutor;
+use sp_core::{storage::well_known_keys, H256};
+use sp_runtime::traits::{Block as BlockT, Hash as HashT, HashingFor, Header as HeaderT};
+use std::collections::BTreeSet;
+
+/// A public network with a pinned genesis.
+pub struct KnownNetwork {
+    /// `id` field of the network's chain spec.
+    pub id: &'static str,
+    /// `--chain` value that loads the embedded raw spec.
+    pub chain: &'static str,
+    /// Hash of the genesis block.
+    ///
+    /// `None` until the canonical raw spec is checked in. Specs claiming to be this network are
+    /// accepted with a warning meanwhile.
+    pub genesis_hash: Option<H256>,
+}
+
+/// The public networks this binary can join.
+pub const KNOWN_NETWORKS: &[KnownNetwork] = &[
+    #[cfg(feature = "scs")]
+    KnownNetwork {
+        id: "scs",
+        chain: "mainnet",
+        genesis_hash: None,
+    },
+    #[cfg(feature = "tscs")]
+    KnownNetwork {
+        id: "tscs",
+        chain: "testnet",
+        genesis_hash: None,
+    },
+];
+
+/// The known network `spec` claims to be, if any.
+pub fn known_network(spec: &dyn ChainSpec) -> Option<&'static KnownNetwork> {
+    KNOWN_NETWORKS
+        .iter()
+        .find(|network| network.id == spec.id())
+}
+
+/// Compute the hash of the genesis block described by `spec`.
+pub fn genesis_hash(spec: &dyn ChainSpec) -> Result<H256, String> {
+    let storage = spec.as_storage_builder().build_storage()?;
+    let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder().build();
+    let state_version =
+        sc_chain_spec::resolve_state_version_from_wasm::<_, HashingFor<Block>>(&storage, &executor)
+            .map_err(|e| format!("Failed to read the state version of the genesis runtime: {e}"))?;
+
+    let mut top = storage.top;
+    for child in storage.children_default.into_values() {
+        // Empty child tries are not part of the state, as in `sp_state_machine`.
+        if child.data.is_empty() {
+            continue;
+        }
+        let root = HashingFor::<Block>::trie_root(child.data.into_iter().collect(), state_version);
+        top.insert(
+            child.child_info.prefixed_storage_key().into_inner(),
+            root.encode(),
+        );
+    }
+    let state_root = HashingFor::<Block>::trie_root(top.into_iter().collect(), state_version);
+
+    let block: Block = sc_chain_spec::construct_genesis_block(state_root, state_version);
+    Ok(block.header().hash())
+}
+
+/// Refuse `spec` if it claims to be a known network but does not produce its pinned genesis.
+pub fn check_genesis(spec: &dyn ChainSpec) -> Result<(), String> {
+    check_genesis_against(spec, KNOWN_NETWORKS)
+}
+
+/// [`check_genesis`] against the given `networks` instead of [`KNOWN_NETWORKS`].
+pub(crate) fn check_genesis_against(
+    spec: &dyn ChainSpec,
+    networks: &[KnownNetwork],
+) -> Result<(), String> {
+    let Some(network) = networks.iter().find(|network| network.id == spec.id()) else {
+        return Ok(());
+    };
+    let Some(expected) = network.genesis_hash else {
+        log::warn!(
+            "⚠️  No genesis hash is pinned for `{}`, the chain spec cannot be verified",
+            network.id
+        );
+        return Ok(());
+    };
+
+    let actual = genesis_hash(spec)?;
+    if actual != expected {
+        return Err(format!(
+            "The chain spec `{}` claims to be the `{}` network, but its genesis hash is {actual:?} \
+             instead of {expected:?}. Refusing to start; run `verify-spec` on the file for details.",
+            spec.name(),
+            network.id,
+        ));
+    }
+    Ok(())
+}
+
+/// Fields of the chain spec JSON compared by [`differences`], besides the genesis state.
+const COMPARED_FIELDS: &[&str] = &[
+    "name",
+    "id",
+    "chainType",
+    "bootNodes",
+    "telemetryEndpoints",
+    "protocolId",
+    "forkId",
+    "properties",
+    "codeSubstitutes",
+];
+
+/// Number of differing storage keys listed before the rest are summarized.
+const MAX_LISTED_KEYS: usize = 20;
+
+/// Human readable differences between `reference` and `other`, both compared in raw form.
+///
+/// An empty result means the two specs are equivalent.
+pub fn differences(
+    reference: &dyn ChainSpec,
+    other: &dyn ChainSpec,
+) -> Result<Vec<String>, String> {
+    let parse = |spec: &dyn ChainSpec| -> Result<serde_json::Value, String> {
+        let json = spec.as_json(true)?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid chain spec JSON: {e}"))
+    };
+    let (reference, other) = (parse(reference)?, parse(other)?);
+
+    let mut out = Vec::new();
+    for field in COMPARED_FIELDS {
+        if reference[field] != other[field] {
+            out.push(format!(
+                "`{field}`: expected {}, found {}",
+                reference[field], other[field]
+            ));
+        }
+    }
+
+    let (reference, other) = (&reference["genesis"]["raw"], &other["genesis"]["raw"]);
+    if reference["childrenDefault"] != other["childrenDefault"] {
+        out.push("default child tries differ".into());
+    }
+
+    let empty = serde_json::Map::new();
+    let reference_top = reference["top"].as_object().unwrap_or(&empty);
+    let other_top = other["top"].as_object().unwrap_or(&empty);
+    let code_key = array_bytes::bytes2hex("0x", well_known_keys::CODE);
+    let keys: BTreeSet<_> = reference_top.keys().chain(other_top.keys()).collect();
+
+    let mut differing = 0;
+    for key in keys {
+        let (expected, found) = (reference_top.get(key), other_top.get(key));
+        if expected == found {
+            continue;
+        }
+        if *key == code_key {
+            out.push("genesis runtime code differs".into());
+            continue;
+        }
+        differing += 1;
+        if differing > MAX_LISTED_KEYS {
+            continue;
+        }
+        out.push(match (expected, found) {
+            (Some(_), None) => format!("storage {key}: missing"),
+            (None, Some(value)) => format!("storage {key}: unexpected value {value}"),
+            (Some(expected), Some(found)) => {
+                format!("storage {key}: expected {expected}, found {found}")
+            }
+            (None, None) => unreachable!("key comes from one of the two maps; qed"),
+        });
+    }
+    if differing > MAX_LISTED_KEYS {
+        out.push(format!(
+            "... {} more differing storage keys",
+            differing - MAX_LISTED_KEYS
+        ));
+    }
+    Ok(out)
+}
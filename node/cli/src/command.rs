@@ -20,9 +20,10 @@ use polkadot_sdk::*;
 
 // use super::benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder};
 use crate::{
-    chain_spec, service,
+    chain_spec::{self, verify},
+    eth, service,
     service::{new_partial, FullClient},
-    Cli, Subcommand,
+    Cli, PurgeChainCmd, Subcommand, VerifySpecCmd,
 };
 use frame_benchmarking_cli::*;
 use common_runtime::opaque::Block;
@@ -35,11 +36,14 @@ use kitchensink_testnet_runtime::{ExistentialDeposit, RuntimeApi};
 use sc_network::{Litep2pNetworkBackend, NetworkBackend};
 // use node_primitives::Block;
 use sc_cli::{Result, SubstrateCli};
-use sc_service::PartialComponents;
+use sc_service::{Configuration, PartialComponents};
 // use sp_keyring::Sr25519Keyring;
 use sp_runtime::traits::HashingFor;
 
-use std::sync::Arc;
+use std::{
+    io::{BufRead, Write},
+    sync::Arc,
+};
 
 impl SubstrateCli for Cli {
     fn impl_name() -> String {
@@ -68,22 +72,26 @@ impl SubstrateCli for Cli {
 
     fn load_spec(&self, id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
         #[cfg(feature="scs")]
-        let spec = match id {
+        let spec: Box<dyn sc_service::ChainSpec> = match id {
             "" | "mainnet" => Box::new(chain_spec::mainnet::scs_config()?),
-            "scs-local" => Box::new(chain_spec::mainnet::staging_testnet_config()),
+            "scs-local" => return Ok(Box::new(chain_spec::mainnet::staging_testnet_config())),
             path => Box::new(chain_spec::mainnet::ChainSpec::from_json_file(
                 std::path::PathBuf::from(path),
             )?),
         };
 
         #[cfg(feature="tscs")]
-        let spec = match id {
+        let spec: Box<dyn sc_service::ChainSpec> = match id {
             "staging" | "testnet" | "" => Box::new(chain_spec::testnet::tscs_config()?),
-            "tscs-local" => Box::new(chain_spec::testnet::staging_testnet_config()),
+            "tscs-local" => return Ok(Box::new(chain_spec::testnet::staging_testnet_config())),
             path => Box::new(chain_spec::testnet::ChainSpec::from_json_file(
                 std::path::PathBuf::from(path),
             )?),
         };
+
+        // The local configs reuse the network ids but are regenerated from code, so only the
+        // embedded and user provided specs are held to the pinned genesis.
+        verify::check_genesis(spec.as_ref())?;
         Ok(spec)
     }
 
@@ -185,8 +193,18 @@ pub fn run() -> Result<()> {
         Some(Subcommand::Verify(cmd)) => cmd.run(),
         Some(Subcommand::Vanity(cmd)) => cmd.run(),
         Some(Subcommand::BuildSpec(cmd)) => {
-            let runner = cli.create_runner(cmd)?;
-            runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+            if let Some(path) = &cmd.from_config {
+                #[cfg(feature = "scs")]
+                let spec = chain_spec::mainnet::config_file_chain_spec(path)?;
+                #[cfg(feature = "tscs")]
+                let spec = chain_spec::testnet::config_file_chain_spec(path)?;
+
+                let json = sc_service::chain_ops::build_spec(&spec, cmd.base.raw)?;
+                std::io::stdout().write_all(json.as_bytes())?;
+                return Ok(());
+            }
+            let runner = cli.create_runner(&cmd.base)?;
+            runner.sync_run(|config| cmd.base.run(config.chain_spec, config.network))
         }
         Some(Subcommand::CheckBlock(cmd)) => {
             let runner = cli.create_runner(cmd)?;
@@ -235,8 +253,8 @@ pub fn run() -> Result<()> {
             })
         }
         Some(Subcommand::PurgeChain(cmd)) => {
-            let runner = cli.create_runner(cmd)?;
-            runner.sync_run(|config| cmd.run(config.database))
+            let runner = cli.create_runner(&cmd.base)?;
+            runner.sync_run(|config| cmd.run(&config))
         }
         Some(Subcommand::Revert(cmd)) => {
             let runner = cli.create_runner(cmd)?;
@@ -247,7 +265,20 @@ pub fn run() -> Result<()> {
                     backend,
                     ..
                 } = new_partial::<Litep2pNetworkBackend>(&config, &cli.eth, None)?;
-                let aux_revert = Box::new(|client: Arc<FullClient>, backend, blocks| {
+                // The Frontier mappings are rewound along with the chain, so refuse to revert
+                // when their database cannot be opened.
+                let frontier_backend =
+                    service::open_frontier_backend(client.clone(), &config, &cli.eth)?;
+                let database = config.database.clone();
+                let db_config_dir = eth::db_config_dir(&config);
+                let aux_revert = Box::new(move |client: Arc<FullClient>, backend, blocks| {
+                    eth::revert_frontier(
+                        &*client,
+                        frontier_backend,
+                        &database,
+                        &db_config_dir,
+                        blocks,
+                    )?;
                     sc_consensus_babe::revert(client.clone(), backend, blocks)?;
                     sc_consensus_grandpa::revert(client, blocks)?;
                     Ok(())
@@ -259,6 +290,22 @@ pub fn run() -> Result<()> {
             let runner = cli.create_runner(cmd)?;
             runner.sync_run(|config| cmd.run::<Block>(&config))
         }
+        Some(Subcommand::VerifySpec(cmd)) => cmd.run(&cli),
+        Some(Subcommand::SessionKeys(cmd)) => cmd.run(&cli),
+        Some(Subcommand::FrontierDb(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    ..
+                } = new_partial::<Litep2pNetworkBackend>(&config, &cli.eth, None)?;
+                let frontier_backend =
+                    service::open_frontier_backend(client.clone(), &config, &cli.eth)?;
+                let config_dir = eth::db_config_dir(&config);
+                Ok((cmd.run(client, frontier_backend, config_dir), task_manager))
+            })
+        }
 
         // 测试专用
         Some(_) => {
@@ -266,3 +313,103 @@ pub fn run() -> Result<()> {
         }
     }
 }
+
+impl PurgeChainCmd {
+    /// Remove the chain database and the Frontier databases of either backend type.
+    ///
+    /// Unless `-y` is given, every directory is listed and confirmation is asked for once.
+    pub fn run(&self, config: &Configuration) -> Result<()> {
+        if self.keep_frontier {
+            return self.base.run(config.database.clone());
+        }
+        // Both backends are removed, whichever `--frontier-backend-type` is selected, so that
+        // switching type does not leave mismatched mappings behind.
+        let dir = eth::db_config_dir(config);
+        // Only `frontier-db` checkpoints; a PostgreSQL database is not managed by the node.
+        let mut paths = vec![dir.join("frontier"), dir.join("sql"), dir.join("postgres")];
+        if !self.frontier_only {
+            let Some(path) = config.database.path() else {
+                return Err("Cannot purge custom database implementation".into());
+            };
+            paths.insert(0, path.to_path_buf());
+        }
+        paths.retain(|path| path.exists());
+        if paths.is_empty() {
+            println!("Nothing to purge.");
+            return Ok(());
+        }
+
+        if !self.base.yes {
+            println!("The following directories will be removed:");
+            for path in &paths {
+                println!("  {}", path.display());
+            }
+            print!("Are you sure? [y/N]: ");
+            std::io::stdout().flush()?;
+
+            let mut input = String::new();
+            std::io::stdin().lock().read_line(&mut input)?;
+            if !matches!(input.trim(), "y" | "Y") {
+                println!("Aborted");
+                return Ok(());
+            }
+        }
+
+        for path in paths {
+            std::fs::remove_dir_all(&path)?;
+            println!("{:?} removed.", path);
+        }
+        Ok(())
+    }
+}
+
+impl VerifySpecCmd {
+    /// Report how `self.file` differs from the network it claims to be.
+    ///
+    /// Fails if the genesis hash differs from the pinned one or if the spec differs from the
+    /// embedded canonical spec.
+    pub fn run(&self, cli: &Cli) -> Result<()> {
+        #[cfg(feature = "scs")]
+        let spec = chain_spec::mainnet::ChainSpec::from_json_file(self.file.clone())?;
+        #[cfg(feature = "tscs")]
+        let spec = chain_spec::testnet::ChainSpec::from_json_file(self.file.clone())?;
+
+        let Some(network) = verify::known_network(&spec) else {
+            return Err(format!(
+                "`{}` is not the id of a network known to this binary",
+                sc_service::ChainSpec::id(&spec)
+            )
+            .into());
+        };
+        let genesis_hash = verify::genesis_hash(&spec)?;
+        println!("Network:      {}", network.id);
+        println!("Genesis hash: {genesis_hash:?}");
+
+        let mut differences = Vec::new();
+        match network.genesis_hash {
+            Some(expected) if expected != genesis_hash => differences.push(format!(
+                "genesis hash: expected {expected:?}, found {genesis_hash:?}"
+            )),
+            Some(_) => {}
+            None => println!("No genesis hash is pinned for `{}`", network.id),
+        }
+        match cli.load_spec(network.chain) {
+            Ok(reference) => differences.extend(verify::differences(reference.as_ref(), &spec)?),
+            Err(e) => println!("Not compared with the embedded spec: {e}"),
+        }
+
+        if differences.is_empty() {
+            println!("✅ The chain spec matches `{}`", network.id);
+            return Ok(());
+        }
+        for difference in &differences {
+            println!("  - {difference}");
+        }
+        Err(format!(
+            "The chain spec differs from `{}` in {} places",
+            network.id,
+            differences.len()
+        )
+        .into())
+    }
+}
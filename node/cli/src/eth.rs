@@ -1,21 +1,31 @@
 pub use crate::cli::{BackendType, EthConfiguration};
-use futures::{future, prelude::*};
+use codec::{Decode, Encode};
+use futures::{future, prelude::*, stream};
 use polkadot_sdk::*;
 use std::{
     collections::BTreeMap,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 // Substrate
 use sc_client_api::BlockchainEvents;
 use sc_executor::HostFunctions;
 use sc_network_sync::SyncingService;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, Configuration, DatabaseSource, TaskManager};
 use sp_api::ConstructRuntimeApi;
+use sp_blockchain::HeaderBackend;
 use sp_core::H256;
-use sp_runtime::traits::Block as BlockT;
+use sp_runtime::{
+    traits::{Block as BlockT, Header as HeaderT, NumberFor, One, Saturating, Zero},
+    SaturatedConversion,
+};
+use substrate_prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
 // Frontier
+use fc_api::Backend as _;
 pub use fc_consensus::FrontierBlockImport;
 use fc_rpc::EthTask;
 pub use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
@@ -23,8 +33,108 @@ pub use fc_storage::{StorageOverride, StorageOverrideHandler};
 
 use crate::client::{FullBackend, FullClient};
 
-/// Frontier DB backend type.
-pub type FrontierBackend<B, C> = fc_db::Backend<B, C>;
+/// Frontier DB backend.
+pub enum FrontierBackend<B: BlockT, C: HeaderBackend<B>> {
+    /// Mappings in the node's key-value database.
+    KeyValue(Arc<fc_db::kv::Backend<B, C>>),
+    /// Mappings and log index in SQLite.
+    Sql(Arc<fc_db::sql::Backend<B>>),
+    /// Mappings and log index in PostgreSQL, selected with `--frontier-sql-url`.
+    Postgres(Arc<frontier_postgres::Backend<B>>),
+}
+
+impl<B, C> FrontierBackend<B, C>
+where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B> + 'static,
+{
+    /// The backend, as used by the RPC.
+    pub fn as_api(&self) -> Arc<dyn fc_api::Backend<B>> {
+        match self {
+            FrontierBackend::KeyValue(b) => b.clone(),
+            FrontierBackend::Sql(b) => b.clone(),
+            FrontierBackend::Postgres(b) => b.clone(),
+        }
+    }
+}
+
+impl<B, C> FrontierBackend<B, C>
+where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B> + 'static,
+{
+    /// Number of the last best block indexed by the mapping worker.
+    ///
+    /// The SQL backends record the blocks they indexed; the key-value backend is read as
+    /// [`kv_last_indexed_block`] explains.
+    pub async fn last_indexed_block(&self, client: &C) -> Result<NumberFor<B>, String> {
+        let latest = match self {
+            FrontierBackend::KeyValue(b) => return kv_last_indexed_block(client, b),
+            FrontierBackend::Sql(b) => b.latest_block_hash().await?,
+            FrontierBackend::Postgres(b) => {
+                match b
+                    .last_indexed_canon_block()
+                    .await
+                    .map_err(|e| e.to_string())?
+                {
+                    Some((_, hash)) => hash,
+                    None => return Ok(Zero::zero()),
+                }
+            }
+        };
+        Ok(client
+            .number(latest)
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default())
+    }
+}
+
+/// Most blocks at the top of the best chain [`kv_last_indexed_block`] checks, so that a stalled
+/// worker does not have every check walk the whole chain.
+const KV_MAX_UNINDEXED_BLOCKS: u32 = 1_000;
+
+/// Number of the last best block the key-value mapping worker has indexed.
+///
+/// The key-value backend answers `latest_block_hash` with the best block, indexed or not. The
+/// worker indexes from the leaves down, one syncing tip at a time, and only takes the new leaves up
+/// once it has no tips left. So the blocks at the top of the best chain are not indexed until it
+/// has caught up, and while it walks down from a syncing tip of the best chain, that tip and the
+/// blocks below it are not indexed either.
+fn kv_last_indexed_block<B, C>(
+    client: &C,
+    backend: &fc_db::kv::Backend<B, C>,
+) -> Result<NumberFor<B>, String>
+where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B>,
+{
+    let mapping = backend.mapping();
+    let info = client.info();
+    let (mut indexed, mut hash) = (info.best_number, info.best_hash);
+    for _ in 0..KV_MAX_UNINDEXED_BLOCKS {
+        if indexed.is_zero() || mapping.is_synced(&hash)? {
+            break;
+        }
+        match client.header(hash).map_err(|e| e.to_string())? {
+            Some(header) => hash = *header.parent_hash(),
+            None => break,
+        }
+        indexed -= One::one();
+    }
+
+    for tip in backend.meta().current_syncing_tips()? {
+        if mapping.is_synced(&tip)? {
+            continue;
+        }
+        let Some(number) = client.number(tip).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        if client.hash(number).map_err(|e| e.to_string())? == Some(tip) {
+            indexed = indexed.min(number.saturating_sub(One::one()));
+        }
+    }
+    Ok(indexed)
+}
 
 pub fn db_config_dir(config: &Configuration) -> PathBuf {
     config.base_path.config_dir(config.chain_spec.id())
@@ -69,6 +179,9 @@ pub fn spawn_frontier_tasks<B, RA, HF>(
             fc_mapping_sync::EthereumBlockNotification<B>,
         >,
     >,
+    last_indexed_block: Arc<AtomicU64>,
+    eth_config: &EthConfiguration,
+    prometheus_registry: Option<&Registry>,
 ) where
     B: BlockT<Hash = H256>,
     RA: ConstructRuntimeApi<B, FullClient<B, RA, HF>>,
@@ -76,20 +189,44 @@ pub fn spawn_frontier_tasks<B, RA, HF>(
     RA::RuntimeApi: EthCompatRuntimeApiCollection<B>,
     HF: HostFunctions + 'static,
 {
-    // Spawn main mapping sync worker background task.
+    task_manager.spawn_handle().spawn(
+        "frontier-last-indexed-block",
+        Some("frontier"),
+        track_last_indexed_block(
+            client.clone(),
+            frontier_backend.clone(),
+            last_indexed_block.clone(),
+        ),
+    );
+
+    if let Some(registry) = prometheus_registry {
+        match MappingSyncMetrics::register(registry) {
+            Ok(metrics) => {
+                task_manager.spawn_handle().spawn(
+                    "frontier-mapping-sync-metrics",
+                    Some("frontier"),
+                    metrics.run(client.clone(), last_indexed_block),
+                );
+            }
+            Err(e) => log::warn!("Failed to register the Frontier mapping sync metrics: {e}"),
+        }
+    }
+
+    // Spawn main mapping sync worker background task. Both workers follow the best chain and
+    // re-canonicalize mappings on reorgs; `Parachain` would only index finalized blocks.
     match &*frontier_backend {
-        fc_db::Backend::KeyValue(b) => {
+        FrontierBackend::KeyValue(b) => {
             task_manager.spawn_essential_handle().spawn(
                 "frontier-mapping-sync-worker",
                 Some("frontier"),
                 fc_mapping_sync::kv::MappingSyncWorker::new(
                     client.import_notification_stream(),
-                    Duration::new(6, 0),
+                    Duration::from_secs(eth_config.frontier_kv_sync_timeout),
                     client.clone(),
                     backend,
                     storage_override.clone(),
                     b.clone(),
-                    3,
+                    eth_config.frontier_kv_sync_batch,
                     0u32.into(),
                     fc_mapping_sync::SyncStrategy::Normal,
                     sync,
@@ -99,7 +236,7 @@ pub fn spawn_frontier_tasks<B, RA, HF>(
             );
         }
 
-        fc_db::Backend::Sql(b) => {
+        FrontierBackend::Sql(b) => {
             task_manager.spawn_essential_handle().spawn_blocking(
                 "frontier-mapping-sync-worker",
                 Some("frontier"),
@@ -109,15 +246,39 @@ pub fn spawn_frontier_tasks<B, RA, HF>(
                     b.clone(),
                     client.import_notification_stream(),
                     fc_mapping_sync::sql::SyncWorkerConfig {
-                        read_notification_timeout: Duration::from_secs(30),
-                        check_indexed_blocks_interval: Duration::from_secs(60),
+                        read_notification_timeout: Duration::from_secs(
+                            eth_config.frontier_sql_read_notification_timeout,
+                        ),
+                        check_indexed_blocks_interval: Duration::from_secs(
+                            eth_config.frontier_sql_check_indexed_blocks_interval,
+                        ),
                     },
-                    fc_mapping_sync::SyncStrategy::Parachain,
+                    fc_mapping_sync::SyncStrategy::Normal,
                     sync,
                     pubsub_notification_sinks,
                 ),
             );
         }
+
+        FrontierBackend::Postgres(b) => {
+            task_manager.spawn_essential_handle().spawn(
+                "frontier-mapping-sync-worker",
+                Some("frontier"),
+                frontier_postgres::SyncWorker::<B, _, FullBackend<B>>::run(
+                    client.clone(),
+                    b.clone(),
+                    storage_override.clone(),
+                    client.import_notification_stream(),
+                    frontier_postgres::SyncWorkerConfig {
+                        check_indexed_blocks_interval: Duration::from_secs(
+                            eth_config.frontier_sql_check_indexed_blocks_interval,
+                        ),
+                        max_blocks_per_round: POSTGRES_MAX_BLOCKS_PER_ROUND,
+                    },
+                    pubsub_notification_sinks,
+                ),
+            );
+        }
     }
 
     // Spawn Frontier EthFilterApi maintenance task.
@@ -144,6 +305,96 @@ pub fn spawn_frontier_tasks<B, RA, HF>(
     );
 }
 
+/// Prometheus metrics of the Frontier mapping sync workers.
+struct MappingSyncMetrics {
+    last_indexed_block: Gauge<U64>,
+    lag: Gauge<U64>,
+    reorgs: Counter<U64>,
+    retracted_blocks: Counter<U64>,
+}
+
+impl MappingSyncMetrics {
+    fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            last_indexed_block: register(
+                Gauge::new(
+                    "substrate_frontier_mapping_last_indexed_block",
+                    "Number of the last best block indexed by the Frontier mapping worker",
+                )?,
+                registry,
+            )?,
+            lag: register(
+                Gauge::new(
+                    "substrate_frontier_mapping_lag",
+                    "Number of best blocks not yet indexed by the Frontier mapping worker",
+                )?,
+                registry,
+            )?,
+            reorgs: register(
+                Counter::new(
+                    "substrate_frontier_mapping_reorgs_total",
+                    "Number of reorgs the Frontier mapping worker had to re-canonicalize",
+                )?,
+                registry,
+            )?,
+            retracted_blocks: register(
+                Counter::new(
+                    "substrate_frontier_mapping_retracted_blocks_total",
+                    "Number of blocks whose Frontier mappings were retracted by reorgs",
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    /// Follow block imports, and read `last_indexed_block` every [`INDEXED_BLOCK_INTERVAL`].
+    async fn run<B, C>(self, client: Arc<C>, last_indexed_block: Arc<AtomicU64>)
+    where
+        B: BlockT<Hash = H256>,
+        C: HeaderBackend<B> + BlockchainEvents<B> + 'static,
+    {
+        enum Event<B: BlockT> {
+            Imported(sc_client_api::BlockImportNotification<B>),
+            Tick,
+        }
+
+        let mut interval = tokio::time::interval(INDEXED_BLOCK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let ticks = stream::unfold(interval, |mut interval| async move {
+            interval.tick().await;
+            Some((Event::Tick, interval))
+        });
+        let mut events = stream::select(
+            client.import_notification_stream().map(Event::Imported),
+            ticks,
+        );
+        while let Some(event) = events.next().await {
+            match event {
+                Event::Imported(notification) => {
+                    if !notification.is_new_best {
+                        continue;
+                    }
+                    if let Some(retracted) = notification
+                        .tree_route
+                        .as_ref()
+                        .map(|route| route.retracted().len() as u64)
+                        .filter(|retracted| *retracted > 0)
+                    {
+                        self.reorgs.inc();
+                        self.retracted_blocks.inc_by(retracted);
+                    }
+                }
+                Event::Tick => self
+                    .last_indexed_block
+                    .set(last_indexed_block.load(Ordering::Relaxed)),
+            }
+            let best: u64 = client.info().best_number.saturated_into();
+            self.lag
+                .set(best.saturating_sub(self.last_indexed_block.get()));
+        }
+    }
+}
+
 impl<Block, Api> EthCompatRuntimeApiCollection<Block> for Api
 where
     Block: BlockT,
@@ -152,3 +403,463 @@ where
         + fp_rpc::EthereumRuntimeRPCApi<Block>,
 {
 }
+
+/// Keep `last_indexed_block` set to the number of the last best block indexed by the mapping
+/// worker, read from `frontier_backend` every [`INDEXED_BLOCK_INTERVAL`]: the worker does not
+/// announce the blocks it indexes while the node is major syncing.
+async fn track_last_indexed_block<B, C>(
+    client: Arc<C>,
+    frontier_backend: Arc<FrontierBackend<B, C>>,
+    last_indexed_block: Arc<AtomicU64>,
+) where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B> + 'static,
+{
+    let mut interval = tokio::time::interval(INDEXED_BLOCK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        match frontier_backend.last_indexed_block(&*client).await {
+            Ok(number) => last_indexed_block.store(number.saturated_into(), Ordering::Relaxed),
+            Err(e) => log::debug!("Failed to read the last indexed Frontier block: {e}"),
+        }
+    }
+}
+
+/// How often the last block indexed by the mapping worker is read.
+const INDEXED_BLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of blocks the PostgreSQL worker indexes in one round.
+const POSTGRES_MAX_BLOCKS_PER_ROUND: u32 = 1_000;
+
+/// Tables of the SQL backend keyed by the Substrate block they were indexed from.
+const SQL_BLOCK_TABLES: &[&str] = &["logs", "transactions", "blocks", "sync_status"];
+
+/// Rewind the Frontier mapping database so that it does not outlive the `blocks` best blocks
+/// about to be reverted.
+///
+/// Must run before the blocks are removed from the Substrate backend, as it reads their hashes
+/// and headers from the canonical chain. Finalized blocks are never reverted, so neither are their
+/// mappings.
+///
+/// The key-value backend has the reverted blocks dropped from its syncing tips, then is closed and
+/// reopened at `database` under `db_config_dir` to delete their mappings, see
+/// [`delete_kv_mappings`]. The SQLite and PostgreSQL backends have the rows of the reverted blocks
+/// deleted. Either way the blocks are indexed again if they are re-imported.
+pub fn revert_frontier<B, C>(
+    client: &C,
+    frontier_backend: FrontierBackend<B, C>,
+    database: &DatabaseSource,
+    db_config_dir: &Path,
+    blocks: NumberFor<B>,
+) -> Result<(), sp_blockchain::Error>
+where
+    B: BlockT<Hash = H256>,
+    C: HeaderBackend<B>,
+{
+    let info = client.info();
+    let target = info
+        .best_number
+        .saturating_sub(blocks)
+        .max(info.finalized_number);
+
+    let mut reverted = Vec::new();
+    let mut number = info.best_number;
+    while number > target {
+        if let Some(hash) = client.hash(number)? {
+            reverted.push(hash);
+        }
+        number -= One::one();
+    }
+    if reverted.is_empty() {
+        return Ok(());
+    }
+
+    match frontier_backend {
+        FrontierBackend::KeyValue(b) => {
+            let mut tips = b
+                .meta()
+                .current_syncing_tips()
+                .map_err(sp_blockchain::Error::Backend)?;
+            tips.retain(|tip| !reverted.contains(tip));
+            b.meta()
+                .write_current_syncing_tips(tips)
+                .map_err(sp_blockchain::Error::Backend)?;
+            // Release the database so that it can be opened directly.
+            drop(b);
+
+            let mut mapped = Vec::with_capacity(reverted.len());
+            for hash in &reverted {
+                let header = client.header(*hash)?;
+                mapped.push((*hash, header.as_ref().and_then(logged_hashes::<B>)));
+            }
+            let db = KvDatabase::open(database, db_config_dir).map_err(|e| {
+                sp_blockchain::Error::Backend(format!("Failed to open the Frontier db: {e}"))
+            })?;
+            delete_kv_mappings::<B>(&db, &mapped).map_err(|e| {
+                sp_blockchain::Error::Backend(format!("Failed to revert the Frontier db: {e}"))
+            })?;
+        }
+        FrontierBackend::Sql(b) => {
+            futures::executor::block_on(delete_sql_blocks(b.pool(), &reverted)).map_err(|e| {
+                sp_blockchain::Error::Backend(format!("Failed to revert the Frontier SQL db: {e}"))
+            })?;
+        }
+        FrontierBackend::Postgres(b) => {
+            futures::executor::block_on(b.delete_blocks(&reverted)).map_err(|e| {
+                sp_blockchain::Error::Backend(format!(
+                    "Failed to revert the Frontier PostgreSQL db: {e}"
+                ))
+            })?;
+        }
+    }
+
+    log::info!(
+        "Reverted the Frontier mappings of {} blocks",
+        reverted.len()
+    );
+    Ok(())
+}
+
+/// The Ethereum block and transaction hashes a Substrate block logs in its digest, as the mapping
+/// sync worker reads them.
+fn logged_hashes<B: BlockT>(header: &B::Header) -> Option<(H256, Vec<H256>)> {
+    use fp_consensus::{Log, PostLog, PreLog};
+    match fp_consensus::find_log(header.digest()).ok()? {
+        Log::Post(PostLog::Hashes(hashes)) => Some((hashes.block_hash, hashes.transaction_hashes)),
+        Log::Post(PostLog::Block(block)) | Log::Pre(PreLog::Block(block)) => Some((
+            block.header.hash(),
+            block
+                .transactions
+                .iter()
+                .map(|transaction| transaction.hash())
+                .collect(),
+        )),
+        Log::Post(PostLog::BlockHash(hash)) => Some((hash, Vec::new())),
+    }
+}
+
+/// Columns of the key-value mapping database, as laid out by `fc_db::kv`.
+///
+/// `fc_db::kv` keeps its columns private and its `MappingDb` can only add mappings, so the layout
+/// is mirrored here until the Frontier fork exposes a way to delete them.
+/// `revert_deletes_the_key_value_mappings` writes through `fc_db` and reads back through it, so
+/// it fails if the two drift apart.
+mod kv_columns {
+    pub const NUM_COLUMNS: u32 = 4;
+    pub const BLOCK_MAPPING: u32 = 1;
+    pub const TRANSACTION_MAPPING: u32 = 2;
+    pub const SYNCED_MAPPING: u32 = 3;
+}
+
+/// The database of the key-value Frontier backend, opened directly.
+pub enum KvDatabase {
+    RocksDb(kvdb_rocksdb::Database),
+    ParityDb(parity_db::Db),
+}
+
+impl KvDatabase {
+    /// Open the database `fc_db::kv::Backend::open` uses for `database` under `db_config_dir`.
+    /// The backend must be closed.
+    pub fn open(database: &DatabaseSource, db_config_dir: &Path) -> Result<Self, String> {
+        let dir = db_config_dir.join("frontier");
+        let rocksdb = |path: PathBuf| {
+            let mut config = kvdb_rocksdb::DatabaseConfig::with_columns(kv_columns::NUM_COLUMNS);
+            config.create_if_missing = false;
+            kvdb_rocksdb::Database::open(&config, path)
+                .map(Self::RocksDb)
+                .map_err(|e| e.to_string())
+        };
+        let paritydb = |path: PathBuf| {
+            let mut options =
+                parity_db::Options::with_columns(&path, kv_columns::NUM_COLUMNS as u8);
+            options.columns[kv_columns::BLOCK_MAPPING as usize].btree_index = true;
+            parity_db::Db::open(&options)
+                .map(Self::ParityDb)
+                .map_err(|e| e.to_string())
+        };
+        match database {
+            DatabaseSource::RocksDb { .. } => rocksdb(dir.join("db")),
+            DatabaseSource::ParityDb { .. } => paritydb(dir.join("paritydb")),
+            DatabaseSource::Auto { .. } => {
+                rocksdb(dir.join("db")).or_else(|_| paritydb(dir.join("paritydb")))
+            }
+            _ => Err("Supported db sources: `auto` | `rocksdb` | `paritydb`".into()),
+        }
+    }
+
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            Self::RocksDb(db) => db.get(column, key).map_err(|e| e.to_string()),
+            Self::ParityDb(db) => db.get(column as u8, key).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Write `changes`, each setting or, with `None`, deleting a key of a column.
+    fn commit(&self, changes: Vec<(u32, Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String> {
+        match self {
+            Self::RocksDb(db) => {
+                let mut transaction = kvdb::DBTransaction::new();
+                for (column, key, value) in changes {
+                    match value {
+                        Some(value) => transaction.put_vec(column, &key, value),
+                        None => transaction.delete(column, &key),
+                    }
+                }
+                db.write(transaction).map_err(|e| e.to_string())
+            }
+            Self::ParityDb(db) => db
+                .commit(
+                    changes
+                        .into_iter()
+                        .map(|(column, key, value)| (column as u8, key, value)),
+                )
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Delete the key-value mappings of the Substrate blocks of `reverted`, each given with the
+/// Ethereum block and transaction hashes it logged, if any.
+///
+/// The blocks are no longer marked as synced, and are removed from the Substrate blocks of their
+/// Ethereum block and from the metadata of their transactions.
+pub fn delete_kv_mappings<B: BlockT<Hash = H256>>(
+    db: &KvDatabase,
+    reverted: &[(H256, Option<(H256, Vec<H256>)>)],
+) -> Result<(), String> {
+    let is_reverted = |hash: &H256| reverted.iter().any(|(reverted, _)| reverted == hash);
+    let decode_error = |e: codec::Error| format!("invalid mapping: {e}");
+    // Write the entries left once all reverted blocks are removed, so that an entry shared by two
+    // of them is written only once.
+    let mut changes = BTreeMap::new();
+    for (hash, logged) in reverted {
+        changes.insert((kv_columns::SYNCED_MAPPING, hash.encode()), None);
+        let Some((ethereum_block, transactions)) = logged else {
+            continue;
+        };
+
+        let key = ethereum_block.encode();
+        if let Some(value) = db.get(kv_columns::BLOCK_MAPPING, &key)? {
+            let mut blocks = Vec::<H256>::decode(&mut &value[..]).map_err(decode_error)?;
+            blocks.retain(|block| !is_reverted(block));
+            let value = (!blocks.is_empty()).then(|| blocks.encode());
+            changes.insert((kv_columns::BLOCK_MAPPING, key), value);
+        }
+        for transaction in transactions {
+            let key = transaction.encode();
+            if let Some(value) = db.get(kv_columns::TRANSACTION_MAPPING, &key)? {
+                let mut metadata = Vec::<fc_api::TransactionMetadata<B>>::decode(&mut &value[..])
+                    .map_err(decode_error)?;
+                metadata.retain(|metadata| !is_reverted(&metadata.substrate_block_hash));
+                let value = (!metadata.is_empty()).then(|| metadata.encode());
+                changes.insert((kv_columns::TRANSACTION_MAPPING, key), value);
+            }
+        }
+    }
+    db.commit(
+        changes
+            .into_iter()
+            .map(|((column, key), value)| (column, key, value))
+            .collect(),
+    )
+}
+
+/// Delete the rows the SQL backend indexed from `blocks`.
+pub async fn delete_sql_blocks(
+    pool: &sqlx::SqlitePool,
+    blocks: &[H256],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for hash in blocks {
+        for table in SQL_BLOCK_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE substrate_block_hash = ?"
+            ))
+            .bind(hash.as_bytes())
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fc_db::kv::MappingCommitment;
+    use sc_block_builder::BlockBuilderBuilder;
+    use sp_consensus::BlockOrigin;
+    use substrate_test_runtime_client::{runtime::Block, ClientBlockImportExt};
+
+    /// A client with `blocks` blocks imported on top of genesis, and their hashes from genesis.
+    fn imported_chain(blocks: u64) -> (Arc<substrate_test_runtime_client::TestClient>, Vec<H256>) {
+        let mut client = Arc::new(substrate_test_runtime_client::new());
+        let mut hashes = vec![client.info().genesis_hash];
+        for number in 0..blocks {
+            let block = BlockBuilderBuilder::new(&*client)
+                .on_parent_block(hashes[number as usize])
+                .with_parent_block_number(number)
+                .build()
+                .unwrap()
+                .build()
+                .unwrap()
+                .block;
+            hashes.push(block.hash());
+            futures::executor::block_on(client.import(BlockOrigin::Own, block)).unwrap();
+        }
+        (client, hashes)
+    }
+
+    fn write_hashes<C: HeaderBackend<Block>>(backend: &fc_db::kv::Backend<Block, C>, hash: H256) {
+        let commitment = MappingCommitment {
+            block_hash: hash,
+            ethereum_block_hash: H256::from(sp_core::hashing::keccak_256(hash.as_bytes())),
+            ethereum_transaction_hashes: vec![],
+        };
+        backend.mapping().write_hashes(commitment).unwrap();
+    }
+
+    #[test]
+    fn reads_the_last_block_indexed_by_the_key_value_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = DatabaseSource::RocksDb {
+            path: dir.path().join("db"),
+            cache_size: 0,
+        };
+        let (client, hashes) = imported_chain(3);
+
+        let backend =
+            fc_db::kv::Backend::<Block, _>::open(client.clone(), &source, dir.path()).unwrap();
+        let sync = |number: usize| write_hashes(&backend, hashes[number]);
+        let indexed = || kv_last_indexed_block(&*client, &backend).unwrap();
+
+        assert_eq!(indexed(), 0);
+        sync(0);
+        sync(1);
+        assert_eq!(indexed(), 1, "the top of the chain is not indexed yet");
+
+        sync(3);
+        backend
+            .meta()
+            .write_current_syncing_tips(vec![hashes[2]])
+            .unwrap();
+        assert_eq!(indexed(), 1, "the worker walks down from #2");
+
+        sync(2);
+        assert_eq!(indexed(), 3);
+    }
+
+    #[tokio::test]
+    async fn tracks_the_last_block_indexed_by_the_key_value_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = DatabaseSource::RocksDb {
+            path: dir.path().join("db"),
+            cache_size: 0,
+        };
+        let (client, hashes) = imported_chain(3);
+        let backend =
+            fc_db::kv::Backend::<Block, _>::open(client.clone(), &source, dir.path()).unwrap();
+        hashes[..2]
+            .iter()
+            .for_each(|hash| write_hashes(&backend, *hash));
+        // What the health checks and the finality tags read before.
+        assert_eq!(backend.latest_block_hash().await.unwrap(), hashes[3]);
+
+        let last_indexed_block = Arc::new(AtomicU64::new(u64::MAX));
+        let tracking = track_last_indexed_block(
+            client,
+            Arc::new(FrontierBackend::KeyValue(Arc::new(backend))),
+            last_indexed_block.clone(),
+        );
+        // The first read is done right away, the task then waits for the next one.
+        let _ = tokio::time::timeout(INDEXED_BLOCK_INTERVAL / 2, tracking).await;
+        assert_eq!(last_indexed_block.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn revert_deletes_the_key_value_mappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = DatabaseSource::RocksDb {
+            path: dir.path().join("db"),
+            cache_size: 0,
+        };
+        let client = Arc::new(substrate_test_runtime_client::new());
+        let open = || fc_db::kv::Backend::<Block, _>::open(client.clone(), &source, dir.path());
+
+        let (kept, reverted) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        let (kept_block, reverted_block) = (H256::repeat_byte(0x11), H256::repeat_byte(0x12));
+        let (shared, reverted_only) = (H256::repeat_byte(0x21), H256::repeat_byte(0x22));
+        let backend = open().unwrap();
+        for (block_hash, ethereum_block_hash, ethereum_transaction_hashes) in [
+            (kept, kept_block, vec![shared]),
+            (reverted, reverted_block, vec![shared, reverted_only]),
+        ] {
+            let commitment = MappingCommitment {
+                block_hash,
+                ethereum_block_hash,
+                ethereum_transaction_hashes,
+            };
+            backend.mapping().write_hashes(commitment).unwrap();
+        }
+        drop(backend);
+
+        let db = KvDatabase::open(&source, dir.path()).unwrap();
+        let logged = Some((reverted_block, vec![shared, reverted_only]));
+        delete_kv_mappings::<Block>(&db, &[(reverted, logged)]).unwrap();
+        drop(db);
+
+        let backend = open().unwrap();
+        let mapping = backend.mapping();
+        assert_eq!(mapping.block_hash(&kept_block).unwrap(), Some(vec![kept]));
+        assert_eq!(mapping.block_hash(&reverted_block).unwrap(), None);
+        assert!(mapping.is_synced(&kept).unwrap());
+        assert!(!mapping.is_synced(&reverted).unwrap());
+        let shared = mapping.transaction_metadata(&shared).unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].substrate_block_hash, kept);
+        assert!(mapping
+            .transaction_metadata(&reverted_only)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn revert_deletes_the_sql_rows() {
+        // A single connection, as every connection to `:memory:` has its own database.
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let (kept, reverted) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        for table in SQL_BLOCK_TABLES {
+            sqlx::query(&format!(
+                "CREATE TABLE {table} (substrate_block_hash BLOB NOT NULL)"
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+            for hash in [kept, reverted] {
+                sqlx::query(&format!(
+                    "INSERT INTO {table} (substrate_block_hash) VALUES (?)"
+                ))
+                .bind(hash.as_bytes())
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        }
+
+        delete_sql_blocks(&pool, &[reverted]).await.unwrap();
+
+        for table in SQL_BLOCK_TABLES {
+            let rows: Vec<Vec<u8>> =
+                sqlx::query_scalar(&format!("SELECT substrate_block_hash FROM {table}"))
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(rows, vec![kept.as_bytes().to_vec()], "{table}");
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! The `frontier-db` subcommand.
+//!
+//! Canonical blocks are walked from `--from` to `--to` and their Ethereum block is read through
+//! [`StorageOverrideHandler`], so only blocks whose state is still available can be indexed or
+//! checked; the others are reported as skipped. The last processed block is checkpointed next to
+//! the mapping database every [`CHECKPOINT_INTERVAL`] blocks, and an interrupted `reindex`,
+//! `verify` or `backfill` continues after it unless `--from` is given.
+
+use crate::{
+    client::FullBackend,
+    eth::{self, FrontierBackend, StorageOverride, StorageOverrideHandler},
+    service::FullClient,
+    FrontierDbCmd, FrontierDbOperation,
+};
+use common_runtime::opaque::Block;
+use fc_api::Backend as _;
+use polkadot_sdk::*;
+use sc_cli::{CliConfiguration, DatabaseParams, Result, SharedParams};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::Block as BlockT;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Number of blocks processed between two checkpoints.
+const CHECKPOINT_INTERVAL: u32 = 1_000;
+
+impl CliConfiguration for FrontierDbCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+}
+
+/// The Ethereum block stored in the state of a Substrate block.
+struct EthereumBlock {
+    hash: H256,
+    transactions: Vec<H256>,
+}
+
+/// Counters reported at the end of a run.
+#[derive(Default)]
+struct Summary {
+    processed: u32,
+    indexed: u32,
+    inconsistent: u32,
+    skipped: u32,
+}
+
+impl FrontierDbOperation {
+    fn name(&self) -> &'static str {
+        match self {
+            FrontierDbOperation::Reindex => "reindex",
+            FrontierDbOperation::Verify => "verify",
+            FrontierDbOperation::Backfill => "backfill",
+            FrontierDbOperation::Stats => "stats",
+        }
+    }
+}
+
+/// Directory of the mapping database of `frontier_backend`, under the chain's config directory.
+fn backend_dir(
+    config_dir: &Path,
+    frontier_backend: &FrontierBackend<Block, FullClient>,
+) -> PathBuf {
+    match frontier_backend {
+        FrontierBackend::KeyValue(_) => config_dir.join("frontier"),
+        FrontierBackend::Sql(_) => config_dir.join("sql"),
+        FrontierBackend::Postgres(_) => config_dir.join("postgres"),
+    }
+}
+
+fn checkpoint_path(dir: &Path, operation: FrontierDbOperation) -> PathBuf {
+    dir.join(format!("{}.checkpoint", operation.name()))
+}
+
+fn read_checkpoint(path: &Path) -> Result<Option<u32>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let number = std::fs::read_to_string(path)?;
+    let number = number
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid checkpoint {}: {e}", path.display()))?;
+    Ok(Some(number))
+}
+
+fn ethereum_block(
+    storage_override: &dyn StorageOverride<Block>,
+    hash: H256,
+) -> Option<EthereumBlock> {
+    let block = storage_override.current_block(hash)?;
+    Some(EthereumBlock {
+        hash: block.header.hash(),
+        transactions: block
+            .transactions
+            .iter()
+            .map(|transaction| transaction.hash())
+            .collect(),
+    })
+}
+
+/// Ways in which the mappings of the Substrate block `hash` are wrong or missing.
+async fn problems<B: BlockT<Hash = H256>>(
+    backend: &dyn fc_api::Backend<B>,
+    hash: H256,
+    block: &EthereumBlock,
+) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let mapped = backend.block_hash(&block.hash).await?.unwrap_or_default();
+    if !mapped.contains(&hash) {
+        problems.push(format!(
+            "Ethereum block {:?} is not mapped to it",
+            block.hash
+        ));
+    }
+    for (index, transaction) in block.transactions.iter().enumerate() {
+        let metadata = backend.transaction_metadata(transaction).await?;
+        let found = metadata.iter().any(|metadata| {
+            metadata.substrate_block_hash == hash
+                && metadata.ethereum_block_hash == block.hash
+                && metadata.ethereum_index as usize == index
+        });
+        if !found {
+            problems.push(format!(
+                "transaction {transaction:?} is not mapped to it at index {index}"
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// The key-value mappings of the Substrate block `hash`.
+fn kv_commitment<B: BlockT<Hash = H256>>(
+    hash: H256,
+    block: EthereumBlock,
+) -> fc_db::kv::MappingCommitment<B> {
+    fc_db::kv::MappingCommitment {
+        block_hash: hash,
+        ethereum_block_hash: block.hash,
+        ethereum_transaction_hashes: block.transactions,
+    }
+}
+
+/// Write the mappings of the Substrate block `hash`, replacing those already present.
+async fn index(
+    client: &Arc<FullClient>,
+    storage_override: &dyn StorageOverride<Block>,
+    frontier_backend: &FrontierBackend<Block, FullClient>,
+    hash: H256,
+    block: EthereumBlock,
+) -> Result<()> {
+    match frontier_backend {
+        FrontierBackend::KeyValue(b) => b.mapping().write_hashes(kv_commitment(hash, block))?,
+        FrontierBackend::Sql(b) => {
+            let sql_error = |e: sqlx::Error| format!("Frontier SQL db error: {e}");
+            eth::delete_sql_blocks(b.pool(), &[hash])
+                .await
+                .map_err(sql_error)?;
+            b.insert_block_metadata(client.clone(), hash)
+                .await
+                .map_err(sql_error)?;
+            b.index_block_logs(hash).await;
+        }
+        FrontierBackend::Postgres(b) => {
+            b.delete_blocks(&[hash])
+                .await
+                .map_err(|e| format!("Frontier PostgreSQL error: {e}"))?;
+            b.index_block::<_, FullBackend<Block>>(&**client, storage_override, hash)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+impl FrontierDbCmd {
+    /// Run the selected operation on `frontier_backend`.
+    ///
+    /// `config_dir` is the config directory of the chain, which holds the mapping databases.
+    pub async fn run(
+        &self,
+        client: Arc<FullClient>,
+        frontier_backend: FrontierBackend<Block, FullClient>,
+        config_dir: PathBuf,
+    ) -> Result<()> {
+        let dir = backend_dir(&config_dir, &frontier_backend);
+        if self.operation == FrontierDbOperation::Stats {
+            return stats(&client, &frontier_backend, &dir).await;
+        }
+
+        std::fs::create_dir_all(&dir)?;
+        let checkpoint = checkpoint_path(&dir, self.operation);
+        let from = match (self.from, read_checkpoint(&checkpoint)?) {
+            (Some(from), _) => from,
+            (None, Some(done)) => {
+                log::info!("Resuming `{}` after block #{done}", self.operation.name());
+                done + 1
+            }
+            (None, None) => 0,
+        };
+        let to = self.to.unwrap_or(client.info().best_number);
+
+        let storage_override =
+            StorageOverrideHandler::<Block, FullClient, FullBackend<Block>>::new(client.clone());
+        let backend = frontier_backend.as_api();
+        let backend = backend.as_ref();
+
+        let mut summary = Summary::default();
+        for number in from..=to {
+            let Some(hash) = client.hash(number)? else {
+                return Err(format!("Block #{number} is not in the canonical chain").into());
+            };
+            summary.processed += 1;
+
+            match ethereum_block(&storage_override, hash) {
+                None => summary.skipped += 1,
+                Some(block) => match self.operation {
+                    FrontierDbOperation::Reindex => {
+                        index(&client, &storage_override, &frontier_backend, hash, block).await?;
+                        summary.indexed += 1;
+                    }
+                    FrontierDbOperation::Verify => {
+                        let problems = problems(backend, hash, &block).await?;
+                        if !problems.is_empty() {
+                            summary.inconsistent += 1;
+                        }
+                        for problem in problems {
+                            println!("#{number} {hash:?}: {problem}");
+                        }
+                    }
+                    FrontierDbOperation::Backfill => {
+                        if !problems(backend, hash, &block).await?.is_empty() {
+                            index(&client, &storage_override, &frontier_backend, hash, block)
+                                .await?;
+                            summary.indexed += 1;
+                        }
+                    }
+                    FrontierDbOperation::Stats => unreachable!("handled above; qed"),
+                },
+            }
+
+            if number % CHECKPOINT_INTERVAL == 0 {
+                std::fs::write(&checkpoint, number.to_string())?;
+                log::info!("{}: #{number} / #{to}", self.operation.name());
+            }
+        }
+        if checkpoint.exists() {
+            std::fs::remove_file(&checkpoint)?;
+        }
+
+        println!(
+            "{}: {} blocks processed from #{from} to #{to}, {} indexed, {} inconsistent, {} \
+             without an available Ethereum block",
+            self.operation.name(),
+            summary.processed,
+            summary.indexed,
+            summary.inconsistent,
+            summary.skipped,
+        );
+        if summary.inconsistent > 0 {
+            return Err(format!(
+                "{} blocks have inconsistent mappings, run `frontier-db backfill` to repair them",
+                summary.inconsistent
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+async fn stats(
+    client: &Arc<FullClient>,
+    frontier_backend: &FrontierBackend<Block, FullClient>,
+    dir: &Path,
+) -> Result<()> {
+    let info = client.info();
+    println!("Best block:      #{}", info.best_number);
+    println!("Finalized block: #{}", info.finalized_number);
+
+    match frontier_backend {
+        FrontierBackend::KeyValue(b) => {
+            println!("Backend:         key-value");
+            let tips = b.meta().current_syncing_tips()?;
+            if tips.is_empty() {
+                println!("Syncing tips:    none, indexed up to the best block");
+            }
+            for tip in tips {
+                match client.number(tip)? {
+                    Some(number) => println!("Syncing tip:     #{number} {tip:?}"),
+                    None => println!("Syncing tip:     {tip:?} (unknown block)"),
+                }
+            }
+        }
+        FrontierBackend::Sql(b) => {
+            println!("Backend:         sql");
+            let latest = b.latest_block_hash().await?;
+            match client.number(latest)? {
+                Some(number) => println!("Latest indexed:  #{number} {latest:?}"),
+                None => println!("Latest indexed:  {latest:?} (unknown block)"),
+            }
+            for (label, query) in [
+                ("Blocks:          ", "SELECT COUNT(*) FROM blocks"),
+                ("Transactions:    ", "SELECT COUNT(*) FROM transactions"),
+                ("Logs:            ", "SELECT COUNT(*) FROM logs"),
+                (
+                    "Pending logs:    ",
+                    "SELECT COUNT(*) FROM sync_status WHERE status = 0",
+                ),
+            ] {
+                let count: i64 = sqlx::query_scalar(query)
+                    .fetch_one(b.pool())
+                    .await
+                    .map_err(|e| format!("Frontier SQL db error: {e}"))?;
+                println!("{label}{count}");
+            }
+        }
+        FrontierBackend::Postgres(b) => {
+            println!("Backend:         postgres");
+            let postgres_error = |e: sqlx::Error| format!("Frontier PostgreSQL error: {e}");
+            match b.last_indexed_canon_block().await.map_err(postgres_error)? {
+                Some((number, hash)) => println!("Latest indexed:  #{number} {hash:?}"),
+                None => println!("Latest indexed:  none"),
+            }
+            for (table, count) in b.counts().await.map_err(postgres_error)? {
+                println!("{:<17}{count}", format!("Rows of {table}:"));
+            }
+        }
+    }
+
+    for operation in [
+        FrontierDbOperation::Reindex,
+        FrontierDbOperation::Verify,
+        FrontierDbOperation::Backfill,
+    ] {
+        if let Some(done) = read_checkpoint(&checkpoint_path(dir, operation))? {
+            println!(
+                "Interrupted `{}` run, resumes after #{done}",
+                operation.name()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use substrate_test_runtime_client::runtime::Block;
+
+    #[tokio::test]
+    async fn verify_finds_what_reindex_writes_to_the_key_value_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = sc_service::DatabaseSource::RocksDb {
+            path: dir.path().join("db"),
+            cache_size: 0,
+        };
+        let client = Arc::new(substrate_test_runtime_client::new());
+        let backend = fc_db::kv::Backend::<Block, _>::open(client, &source, dir.path()).unwrap();
+
+        let block = || EthereumBlock {
+            hash: H256::repeat_byte(0x11),
+            transactions: vec![H256::repeat_byte(0x21), H256::repeat_byte(0x22)],
+        };
+        let (indexed, missing) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        backend
+            .mapping()
+            .write_hashes(kv_commitment(indexed, block()))
+            .unwrap();
+
+        assert!(problems(&backend, indexed, &block())
+            .await
+            .unwrap()
+            .is_empty());
+        let problems = problems(&backend, missing, &block()).await.unwrap();
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("is not mapped to it"));
+        assert!(problems[2].contains("at index 1"));
+    }
+}
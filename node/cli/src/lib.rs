@@ -40,8 +40,12 @@ mod command;
 #[cfg(feature = "cli")]
 pub mod eth;
 #[cfg(feature = "cli")]
+mod frontier_db;
+#[cfg(feature = "cli")]
 pub mod service;
 #[cfg(feature = "cli")]
+mod session_keys;
+#[cfg(feature = "cli")]
 pub use eth::EthConfiguration;
 #[cfg(feature = "cli")]
 pub mod client;
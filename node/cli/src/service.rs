@@ -56,14 +56,21 @@ use sc_network::{
     event::Event, service::traits::NetworkService, NetworkBackend, NetworkEventStream,
 };
 use sc_network_sync::{strategy::warp::WarpSyncParams, SyncingService};
-use sc_service::{config::Configuration, error::Error as ServiceError, RpcHandlers, TaskManager};
+use sc_service::{
+    config::{Configuration, KeystoreConfig},
+    error::Error as ServiceError,
+    RpcHandlers, TaskManager,
+};
 use sc_statement_store::Store as StatementStore;
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_api::ProvideRuntimeApi;
-use sp_core::crypto::Pair;
+use sp_core::crypto::{ExposeSecret, Pair};
 use sp_runtime::{generic, traits::Block as BlockT, SaturatedConversion};
-use std::{path::Path, sync::Arc};
+use std::{
+    path::Path,
+    sync::{atomic::AtomicU64, Arc},
+};
 // use crate::client::{FullBackend, FullClient};
 
 // pub type Backend = FullBackend<Block>;
@@ -190,6 +197,65 @@ pub fn fetch_nonce(client: &FullClient, account: sp_core::ecdsa::Pair) -> u32 {
 // 	)
 // }
 
+/// Open the Frontier mapping database selected by `eth_config`.
+pub fn open_frontier_backend(
+    client: Arc<FullClient>,
+    config: &Configuration,
+    eth_config: &EthConfiguration,
+) -> Result<FrontierBackend<Block, FullClient>, ServiceError> {
+    Ok(match eth_config.frontier_backend_type {
+        BackendType::KeyValue => FrontierBackend::KeyValue(Arc::new(fc_db::kv::Backend::open(
+            Arc::clone(&client),
+            &config.database,
+            &db_config_dir(config),
+        )?)),
+        BackendType::Sql if eth_config.frontier_sql_url.is_some() => {
+            let url = eth_config.frontier_sql_url.as_deref().unwrap_or_default();
+            if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+                return Err(ServiceError::Other(format!(
+                    "--frontier-sql-url must be a postgres:// URL, got `{url}`"
+                )));
+            }
+            let backend = futures::executor::block_on(frontier_postgres::Backend::new(
+                frontier_postgres::BackendConfig {
+                    url,
+                    pool_size: eth_config.frontier_sql_backend_pool_size,
+                    acquire_timeout: std::time::Duration::from_secs(30),
+                },
+            ))
+            .map_err(|err| {
+                ServiceError::Other(format!("failed creating postgres backend: {err}"))
+            })?;
+            FrontierBackend::Postgres(Arc::new(backend))
+        }
+        BackendType::Sql => {
+            let db_path = db_config_dir(config).join("sql");
+            std::fs::create_dir_all(&db_path).map_err(|e| {
+                ServiceError::Other(format!("failed creating sql db directory: {e}"))
+            })?;
+            let storage_override =
+                Arc::new(StorageOverrideHandler::<Block, FullClient, FullBackend>::new(client));
+            let backend = futures::executor::block_on(fc_db::sql::Backend::new(
+                fc_db::sql::BackendConfig::Sqlite(fc_db::sql::SqliteBackendConfig {
+                    path: Path::new("sqlite:///")
+                        .join(db_path)
+                        .join("frontier.db3")
+                        .to_str()
+                        .unwrap(),
+                    create_if_missing: true,
+                    thread_count: eth_config.frontier_sql_backend_thread_count,
+                    cache_size: eth_config.frontier_sql_backend_cache_size,
+                }),
+                eth_config.frontier_sql_backend_pool_size,
+                std::num::NonZeroU32::new(eth_config.frontier_sql_backend_num_ops_timeout),
+                storage_override,
+            ))
+            .map_err(|err| ServiceError::Other(format!("failed creating sql backend: {err:?}")))?;
+            FrontierBackend::Sql(Arc::new(backend))
+        }
+    })
+}
+
 /// Creates a new partial node.
 pub fn new_partial<NB>(
     config: &Configuration,
@@ -419,6 +485,9 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
         transaction_pool,
         other: (import_setup, mut telemetry, statement_store, babe_worker_handle, beefy_rpc_links),
     } = new_partial::<N>(&config, &eth_config, mixnet_config.as_ref())?;
+    // Statements reach the store from the network, the RPC and the offchain workers: all of them
+    // go through the wrapper so that the RPC subscriptions see every new statement.
+    let statement_store = Arc::new(node_rpc::NotifyingStatementStore::new(statement_store));
 
     let metrics = N::register_notification_metrics(
         config.prometheus_config.as_ref().map(|cfg| &cfg.registry),
@@ -541,8 +610,16 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 
     let eth_backend = backend.clone();
     let eth_storage_override = storage_override.clone();
-
-    let (rpc_extensions_builder, rpc_setup, frontier_backend, pubsub_notification_sinks) = {
+    // Kept up to date by the Frontier tasks, read by the finality tags and the health checks.
+    let last_indexed_block = Arc::new(AtomicU64::new(0));
+
+    let (
+        rpc_extensions_builder,
+        eth_rpc_builder,
+        rpc_setup,
+        frontier_backend,
+        pubsub_notification_sinks,
+    ) = {
         let (_, grandpa_link, _, _) = &import_setup;
 
         let justification_stream = grandpa_link.justification_stream();
@@ -560,6 +637,16 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
         let select_chain = select_chain.clone();
         let keystore = keystore_container.keystore();
         let chain_spec = config.chain_spec.cloned_box();
+        let keystore_signer = eth_config.enable_keystore_signer.then(|| {
+            let password = match &config.keystore {
+                KeystoreConfig::Path { password, .. } => password.as_ref(),
+                _ => None,
+            };
+            node_rpc::EthKeystoreSigner::new(
+                keystore.clone(),
+                password.map(|password| password.expose_secret().as_str()),
+            )
+        });
 
         let mut net_config = sc_network::config::FullNetworkConfiguration::<
             Block,
@@ -567,36 +654,10 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
             Litep2pNetworkBackend,
         >::new(&config.network);
 
-        let frontier_backend = match eth_config.frontier_backend_type {
-            BackendType::KeyValue => FrontierBackend::KeyValue(Arc::new(fc_db::kv::Backend::open(
-                Arc::clone(&client),
-                &config.database,
-                &db_config_dir(&config),
-            )?)),
-            BackendType::Sql => {
-                let db_path = db_config_dir(&config).join("sql");
-                std::fs::create_dir_all(&db_path).expect("failed creating sql db directory");
-                let backend = futures::executor::block_on(fc_db::sql::Backend::new(
-                    fc_db::sql::BackendConfig::Sqlite(fc_db::sql::SqliteBackendConfig {
-                        path: Path::new("sqlite:///")
-                            .join(db_path)
-                            .join("frontier.db3")
-                            .to_str()
-                            .unwrap(),
-                        create_if_missing: true,
-                        thread_count: eth_config.frontier_sql_backend_thread_count,
-                        cache_size: eth_config.frontier_sql_backend_cache_size,
-                    }),
-                    eth_config.frontier_sql_backend_pool_size,
-                    std::num::NonZeroU32::new(eth_config.frontier_sql_backend_num_ops_timeout),
-                    storage_override.clone(),
-                ))
-                .unwrap_or_else(|err| panic!("failed creating sql backend: {:?}", err));
-                FrontierBackend::Sql(Arc::new(backend))
-            }
-        };
+        let frontier_backend = open_frontier_backend(client.clone(), &config, &eth_config)?;
 
         let frontier_backend1 = Arc::new(frontier_backend);
+        let last_indexed_block1 = last_indexed_block.clone();
         let frontier_backend2 = frontier_backend1.clone();
         // todo warp_sync_params
 
@@ -655,35 +716,58 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
         let network0 = network.clone();
         let role1 = config.role.clone().is_authority().clone();
         let sync_service0 = sync_service.clone();
+        // The Ethereum dependencies are shared by the node RPC server and the Ethereum one.
+        let eth_deps = {
+            let client = client.clone();
+            let pool = pool.clone();
+            let enable_dev_signer = eth_config.enable_dev_signer;
+            let max_past_logs = eth_config.max_past_logs;
+            let max_stored_filters = eth_config.max_stored_filters;
+            let execute_gas_limit_multiplier = eth_config.execute_gas_limit_multiplier;
+            move || node_rpc::EthDeps {
+                client: client.clone(),
+                pool: pool.clone(),
+                graph: pool.pool().clone(),
+                converter: Some(TransactionConverter::<Block>::default()),
+                is_authority: role1.into(),
+                enable_dev_signer,
+                keystore_signer: keystore_signer.clone(),
+                network: network0.clone(),
+                sync: sync_service0.clone(),
+                frontier_backend: frontier_backend1.as_api(),
+                last_indexed_block: last_indexed_block1.clone(),
+                storage_override: storage_override.clone(),
+                block_data_cache: block_data_cache.clone(),
+                filter_pool: filter_pool1.clone(),
+                max_past_logs,
+                max_stored_filters,
+                fee_history_cache: fee_history_cache1.clone(),
+                fee_history_cache_limit,
+                execute_gas_limit_multiplier,
+                forced_parent_hashes: None,
+                pending_create_inherent_data_providers,
+            }
+        };
+
+        let eth_rpc_builder = {
+            let eth_deps = eth_deps.clone();
+            let client = client.clone();
+            let keystore = keystore.clone();
+            let justification_stream = justification_stream.clone();
+            let pubsub_notification_sinks = pubsub_notification_sinks1.clone();
+            move |subscription_executor: node_rpc::SubscriptionTaskExecutor| {
+                node_rpc::create_eth_rpc::<_, _, FullBackend, _, _, _>(
+                    eth_deps(),
+                    subscription_executor,
+                    pubsub_notification_sinks,
+                    Box::new(BabeConsensusDataProvider::new(client, keystore)),
+                    justification_stream,
+                )
+            }
+        };
+
         let rpc_extensions_builder =
             move |deny_unsafe, subscription_executor: node_rpc::SubscriptionTaskExecutor| {
-                let enable_dev_signer = eth_config.enable_dev_signer;
-                let max_past_logs = eth_config.max_past_logs;
-                let execute_gas_limit_multiplier = eth_config.execute_gas_limit_multiplier;
-                let eth_deps = node_rpc::EthDeps {
-                    client: client.clone(),
-                    pool: pool.clone(),
-                    graph: pool.pool().clone(),
-                    converter: Some(TransactionConverter::<Block>::default()),
-                    is_authority: role1.into(),
-                    enable_dev_signer,
-                    network: network0.clone(),
-                    sync: sync_service0.clone(),
-                    frontier_backend: match &*frontier_backend1.clone() {
-                        fc_db::Backend::KeyValue(b) => b.clone(),
-                        fc_db::Backend::Sql(b) => b.clone(),
-                    },
-                    storage_override: storage_override.clone(),
-                    block_data_cache: block_data_cache.clone(),
-                    filter_pool: filter_pool1.clone(),
-                    max_past_logs,
-                    fee_history_cache: fee_history_cache1.clone(),
-                    fee_history_cache_limit,
-                    execute_gas_limit_multiplier,
-                    forced_parent_hashes: None,
-                    pending_create_inherent_data_providers,
-                };
-
                 let deps = node_rpc::FullDeps {
                     client: client.clone(),
                     pool: pool.clone(),
@@ -713,7 +797,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
                     statement_store: rpc_statement_store.clone(),
                     backend: rpc_backend.clone(),
                     // mixnet_api: mixnet_api.as_ref().cloned(),
-                    eth: eth_deps,
+                    eth: eth_deps(),
                 };
                 let pending_consenus_data_provider = Box::new(BabeConsensusDataProvider::new(client.clone(), keystore.clone()));
                 node_rpc::create_full(
@@ -727,6 +811,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 
         (
             rpc_extensions_builder,
+            eth_rpc_builder,
             shared_voter_state2,
             frontier_backend2,
             pubsub_notification_sinks2,
@@ -762,8 +847,85 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
         fee_history_cache_limit,
         sync_service.clone(),
         pubsub_notification_sinks,
+        last_indexed_block.clone(),
+        &eth_config,
+        prometheus_registry.as_ref(),
     );
 
+    if let Some(addr) = eth_config.eth_rpc_addr {
+        let module = eth_rpc_builder(Arc::new(task_manager.spawn_handle())).map_err(|err| {
+            ServiceError::Other(format!("failed building the Ethereum RPC: {err}"))
+        })?;
+        let module = node_rpc::allow_methods(module, &eth_config.eth_rpc_methods);
+        let mut budgets = node_rpc::RpcBudgets::new(eth_config.eth_rpc_max_heavy_calls);
+        for budget in &eth_config.eth_rpc_budget {
+            budgets
+                .set(budget)
+                .map_err(|err| ServiceError::Other(format!("invalid --eth-rpc-budget: {err}")))?;
+        }
+        if let Some(registry) = &prometheus_registry {
+            budgets.register(registry).map_err(|err| {
+                ServiceError::Other(format!("failed registering RPC budget metrics: {err}"))
+            })?;
+        }
+        let budgets = node_rpc::BudgetLayer::new(budgets, client.clone());
+        let cors = &eth_config.eth_rpc_cors;
+        let server_config = node_rpc::EthServerConfig {
+            addr,
+            cors: (!cors.iter().any(|origin| origin == "all")).then(|| cors.clone()),
+            max_connections: eth_config.eth_rpc_max_connections,
+        };
+        let server = node_rpc::start_eth_server(server_config, module, budgets).map_err(|err| {
+            ServiceError::Other(format!(
+                "failed to start the Ethereum JSON-RPC server: {err}"
+            ))
+        })?;
+        task_manager
+            .spawn_handle()
+            .spawn("eth-rpc-server", None, server.stopped());
+    }
+
+    if eth_config.ethgraphql {
+        let chain = eth_graphql::ClientChain::new(
+            client.clone(),
+            frontier_backend.as_api(),
+            eth_storage_override.clone(),
+        );
+        let schema = eth_graphql::schema(Arc::new(chain), eth_config.ethgraphql_max_cost);
+        let server = eth_graphql::bind(eth_config.ethgraphql_addr, schema).map_err(|err| {
+            ServiceError::Other(format!(
+                "failed to start the Ethereum GraphQL server: {err}"
+            ))
+        })?;
+        task_manager
+            .spawn_handle()
+            .spawn("eth-graphql", None, async move {
+                if let Err(err) = server.await {
+                    log::error!("Ethereum GraphQL server failed: {err}");
+                }
+            });
+    }
+
+    if let Some(addr) = eth_config.health_addr {
+        let source = node_health::NodeHealth::new(
+            client.clone(),
+            sync_service.clone(),
+            last_indexed_block.clone(),
+        );
+        let thresholds = node_health::Thresholds {
+            max_finality_lag: eth_config.ready_max_finality_lag,
+            max_frontier_lag: eth_config.ready_max_frontier_lag,
+            min_peers: eth_config.ready_min_peers,
+        };
+        task_manager
+            .spawn_handle()
+            .spawn("health", None, async move {
+                if let Err(err) = node_health::serve(addr, Arc::new(source), thresholds).await {
+                    log::error!("Health server failed: {err}");
+                }
+            });
+    }
+
     if let Some(hwbench) = hwbench {
         sc_sysinfo::print_hwbench(&hwbench);
         match SUBSTRATE_REFERENCE_HARDWARE.check_hardware(&hwbench) {
@@ -1003,7 +1165,11 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
                 is_validator: role.is_authority(),
                 enable_http_requests: true,
                 custom_extensions: move |_| {
-                    vec![Box::new(statement_store.clone().as_statement_store_ext()) as Box<_>]
+                    vec![
+                        Box::new(sp_statement_store::runtime_api::StatementStoreExt::new(
+                            statement_store.clone(),
+                        )) as Box<_>,
+                    ]
                 },
             })
             .run(client.clone(), task_manager.spawn_handle())
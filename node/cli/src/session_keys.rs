@@ -0,0 +1,348 @@
+//! The `session-keys` subcommands.
+//!
+//! The keys are derived for every key type of the runtime's `SessionKeys`, in declaration order,
+//! so that a key added to the runtime cannot be forgotten here. Each key type gets its own path:
+//! `<SURI>//<key type>` from `--suri`, `//<PASSWORD>//<key type>//<INDEX>` from `--password` and
+//! `--index`.
+//!
+//! Validators set up with the former `validator_node_init.sh` keep their keys with
+//! `--legacy-paths`, which derives them at the script's paths:
+//!
+//! - ed25519: `//<PASSWORD>//fir//ed//<INDEX>`
+//! - sr25519: `//<PASSWORD>/fir/sr/<INDEX>`
+//! - ecdsa: `//<PASSWORD>//fir//ecdsa//<INDEX>`
+//!
+//! These share one sr25519 secret between BABE, ImOnline, authority discovery and mixnet.
+
+use crate::{
+    Cli, SessionKeysCmd, SessionKeysGenerateCmd, SessionKeysInsertCmd, SessionKeysInspectCmd,
+    SessionKeysSecret, SessionKeysVerifyCmd,
+};
+use codec::{Decode, Encode};
+use common_runtime::AccountId;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use polkadot_sdk::*;
+use sc_cli::{Error, KeystoreParams, Result, SharedParams, SubstrateCli};
+use sc_keystore::LocalKeystore;
+use sc_service::{config::KeystoreConfig, BasePath};
+use sp_application_crypto::AppCrypto;
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_consensus_beefy::ecdsa_crypto::AuthorityId as BeefyId;
+use sp_consensus_grandpa::AuthorityId as GrandpaId;
+use sp_core::{
+    crypto::{ByteArray, CryptoTypeId, KeyTypeId},
+    ecdsa, ed25519, sr25519, Bytes, Pair, H160,
+};
+use sp_keystore::Keystore;
+use sp_mixnet::types::AuthorityId as MixnetId;
+use sp_runtime::traits::OpaqueKeys;
+use std::str::FromStr;
+
+#[cfg(feature = "scs")]
+use kitchensink_mainnet_runtime::{Runtime, RuntimeCall, SessionKeys};
+#[cfg(feature = "tscs")]
+use kitchensink_testnet_runtime::{Runtime, RuntimeCall, SessionKeys};
+
+/// A derived session key.
+struct DerivedKey {
+    key_type: KeyTypeId,
+    suri: String,
+    public: Vec<u8>,
+}
+
+/// The crypto scheme of a session key type.
+fn crypto_of(key_type: KeyTypeId) -> Option<CryptoTypeId> {
+    [
+        (GrandpaId::ID, GrandpaId::CRYPTO_ID),
+        (BabeId::ID, BabeId::CRYPTO_ID),
+        (ImOnlineId::ID, ImOnlineId::CRYPTO_ID),
+        (AuthorityDiscoveryId::ID, AuthorityDiscoveryId::CRYPTO_ID),
+        (MixnetId::ID, MixnetId::CRYPTO_ID),
+        (BeefyId::ID, BeefyId::CRYPTO_ID),
+    ]
+    .into_iter()
+    .find(|(id, _)| *id == key_type)
+    .map(|(_, crypto)| crypto)
+}
+
+fn key_type_name(key_type: KeyTypeId) -> String {
+    String::from_utf8_lossy(&key_type.0).into_owned()
+}
+
+impl SessionKeysSecret {
+    fn suri(&self, key_type: KeyTypeId, crypto: CryptoTypeId) -> String {
+        let name = key_type_name(key_type);
+        match (&self.suri, &self.password, self.index) {
+            (Some(suri), _, _) => format!("{suri}//{name}"),
+            (None, Some(password), Some(index)) if !self.legacy_paths => {
+                format!("//{password}//{name}//{index}")
+            }
+            (None, Some(password), Some(index)) if crypto == ed25519::CRYPTO_ID => {
+                format!("//{password}//fir//ed//{index}")
+            }
+            (None, Some(password), Some(index)) if crypto == sr25519::CRYPTO_ID => {
+                format!("//{password}/fir/sr/{index}")
+            }
+            (None, Some(password), Some(index)) => format!("//{password}//fir//ecdsa//{index}"),
+            _ => unreachable!("clap requires --suri or --password with --index; qed"),
+        }
+    }
+
+    /// Derive every session key of the runtime.
+    fn derive(&self) -> Result<Vec<DerivedKey>> {
+        SessionKeys::key_ids()
+            .iter()
+            .map(|&key_type| {
+                let crypto = crypto_of(key_type).ok_or_else(|| {
+                    Error::Input(format!(
+                        "The runtime declares the session key type `{}`, which this command \
+                         cannot derive",
+                        key_type_name(key_type)
+                    ))
+                })?;
+                let suri = self.suri(key_type, crypto);
+                let public = if crypto == ed25519::CRYPTO_ID {
+                    ed25519::Pair::from_string(&suri, None).map(|p| p.public().to_raw_vec())
+                } else if crypto == sr25519::CRYPTO_ID {
+                    sr25519::Pair::from_string(&suri, None).map(|p| p.public().to_raw_vec())
+                } else {
+                    ecdsa::Pair::from_string(&suri, None).map(|p| p.public().to_raw_vec())
+                }
+                .map_err(|e| Error::Input(format!("Invalid secret: {e:?}")))?;
+                Ok(DerivedKey {
+                    key_type,
+                    suri,
+                    public,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The runtime `SessionKeys` made of `keys`.
+fn session_keys(keys: &[DerivedKey]) -> Result<SessionKeys> {
+    let encoded: Vec<u8> = keys
+        .iter()
+        .flat_map(|key| key.public.iter().copied())
+        .collect();
+    SessionKeys::decode(&mut &encoded[..])
+        .map_err(|e| Error::Input(format!("Derived keys do not form `SessionKeys`: {e}")))
+}
+
+fn print_keys(keys: &[DerivedKey]) -> Result<()> {
+    let session_keys = session_keys(keys)?;
+    for key in keys {
+        println!(
+            "{}: {}",
+            key_type_name(key.key_type),
+            array_bytes::bytes2hex("0x", &key.public)
+        );
+    }
+    println!(
+        "Session keys:  {}",
+        array_bytes::bytes2hex("0x", session_keys.encode())
+    );
+
+    let call = RuntimeCall::Session(pallet_session::Call::set_keys {
+        keys: session_keys,
+        proof: Vec::new(),
+    });
+    println!(
+        "set_keys call: {}",
+        array_bytes::bytes2hex("0x", call.encode())
+    );
+    Ok(())
+}
+
+/// Open the keystore of the chain selected by `shared`, as `key insert` does.
+fn open_keystore(
+    cli: &Cli,
+    shared: &SharedParams,
+    params: &KeystoreParams,
+) -> Result<LocalKeystore> {
+    let base_path = shared
+        .base_path()?
+        .unwrap_or_else(|| BasePath::from_project("", "", &Cli::executable_name()));
+    let chain_spec = cli.load_spec(&shared.chain_id(shared.is_dev()))?;
+    let config_dir = base_path.config_dir(chain_spec.id());
+
+    match params.keystore_config(&config_dir)? {
+        KeystoreConfig::Path { path, password } => Ok(LocalKeystore::open(path, password)?),
+        _ => unreachable!("keystore_config always returns path and password; qed"),
+    }
+}
+
+impl SessionKeysCmd {
+    /// Run the selected subcommand.
+    pub fn run(&self, cli: &Cli) -> Result<()> {
+        match self {
+            SessionKeysCmd::Generate(cmd) => cmd.run(),
+            SessionKeysCmd::Insert(cmd) => cmd.run(cli),
+            SessionKeysCmd::Inspect(cmd) => cmd.run(),
+            SessionKeysCmd::Verify(cmd) => cmd.run(cli),
+        }
+    }
+}
+
+impl SessionKeysGenerateCmd {
+    /// Generate a secret phrase and print the keys derived from it.
+    pub fn run(&self) -> Result<()> {
+        let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+        println!("Secret phrase: {phrase}");
+        println!("Keep it safe, the session keys are derived from it with `--suri`.");
+        let secret = SessionKeysSecret {
+            suri: Some(phrase),
+            password: None,
+            index: None,
+            legacy_paths: false,
+        };
+        print_keys(&secret.derive()?)
+    }
+}
+
+impl SessionKeysInspectCmd {
+    /// Print the keys derived from the secret.
+    pub fn run(&self) -> Result<()> {
+        print_keys(&self.secret.derive()?)
+    }
+}
+
+impl SessionKeysInsertCmd {
+    /// Write every session key to the keystore and print the `set_keys` payload.
+    pub fn run(&self, cli: &Cli) -> Result<()> {
+        let keys = self.secret.derive()?;
+        // Fail before touching the keystore if the keys do not form `SessionKeys`.
+        session_keys(&keys)?;
+
+        let keystore = open_keystore(cli, &self.shared_params, &self.keystore_params)?;
+        for key in &keys {
+            keystore
+                .insert(key.key_type, &key.suri, &key.public)
+                .map_err(|_| Error::KeystoreOperation)?;
+        }
+        print_keys(&keys)
+    }
+}
+
+impl SessionKeysVerifyCmd {
+    /// Compare the keystore with `Session::NextKeys` of the stash.
+    pub fn run(&self, cli: &Cli) -> Result<()> {
+        let stash: AccountId = H160::from_str(&self.stash)
+            .map_err(|e| Error::Input(format!("Invalid stash `{}`: {e}", self.stash)))?
+            .into();
+        let storage_key = pallet_session::NextKeys::<Runtime>::hashed_key_for(&stash);
+
+        let next_keys: Option<Bytes> = sc_cli::build_runtime()?
+            .block_on(async {
+                let client = WsClientBuilder::default().build(&self.rpc_url).await?;
+                client
+                    .request("state_getStorage", rpc_params![Bytes(storage_key)])
+                    .await
+            })
+            .map_err(|e| Error::Application(Box::new(e)))?;
+        let Some(next_keys) = next_keys else {
+            return Err(Error::Input(format!(
+                "No session keys are registered for {}",
+                self.stash
+            )));
+        };
+        let next_keys = SessionKeys::decode(&mut &next_keys[..])
+            .map_err(|e| Error::Input(format!("Failed to decode `Session::NextKeys`: {e}")))?;
+
+        let keystore = open_keystore(cli, &self.shared_params, &self.keystore_params)?;
+        let mut missing = 0;
+        for (public, key_type) in next_keys.into_raw_public_keys() {
+            let present = keystore.has_keys(&[(public.clone(), key_type)]);
+            if !present {
+                missing += 1;
+            }
+            println!(
+                "{} {}: {}",
+                if present { "✅" } else { "❌" },
+                key_type_name(key_type),
+                array_bytes::bytes2hex("0x", &public),
+            );
+        }
+
+        if missing > 0 {
+            return Err(Error::Input(format!(
+                "{missing} session keys registered for {} are not in the keystore",
+                self.stash
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password_secret(legacy_paths: bool) -> SessionKeysSecret {
+        SessionKeysSecret {
+            suri: None,
+            password: Some("secret".into()),
+            index: Some(3),
+            legacy_paths,
+        }
+    }
+
+    fn suris(keys: &[DerivedKey]) -> Vec<(String, String)> {
+        keys.iter()
+            .map(|key| (key_type_name(key.key_type), key.suri.clone()))
+            .collect()
+    }
+
+    fn assert_distinct_publics(keys: &[DerivedKey]) {
+        assert_eq!(keys.len(), SessionKeys::key_ids().len());
+        let mut publics: Vec<_> = keys.iter().map(|key| key.public.clone()).collect();
+        publics.sort();
+        publics.dedup();
+        assert_eq!(publics.len(), keys.len());
+    }
+
+    #[test]
+    fn password_paths_match_the_former_init_script() {
+        let keys = password_secret(true).derive().unwrap();
+        let expected = [
+            ("gran", "//secret//fir//ed//3"),
+            ("babe", "//secret/fir/sr/3"),
+            ("imon", "//secret/fir/sr/3"),
+            ("auth", "//secret/fir/sr/3"),
+            ("mixn", "//secret/fir/sr/3"),
+            ("beef", "//secret//fir//ecdsa//3"),
+        ];
+        let mut actual = suris(&keys);
+        actual.sort();
+        let mut expected: Vec<_> = expected
+            .iter()
+            .map(|(key_type, suri)| (key_type.to_string(), suri.to_string()))
+            .collect();
+        expected.sort();
+        assert_eq!(actual, expected);
+        session_keys(&keys).unwrap();
+    }
+
+    #[test]
+    fn password_keys_are_distinct_per_role() {
+        let keys = password_secret(false).derive().unwrap();
+        for (key_type, suri) in suris(&keys) {
+            assert_eq!(suri, format!("//secret//{key_type}//3"));
+        }
+        assert_distinct_publics(&keys);
+        session_keys(&keys).unwrap();
+    }
+
+    #[test]
+    fn suri_keys_are_distinct_per_role() {
+        let secret = SessionKeysSecret {
+            suri: Some("//Alice".into()),
+            password: None,
+            index: None,
+            legacy_paths: false,
+        };
+        assert_distinct_publics(&secret.derive().unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+//! Post an order to the statement store of a node, then print the orders of the book as they
+//! arrive.
+//!
+//! ```text
+//! cargo run -p node-rpc --example statement_order_book -- \
+//!     ws://127.0.0.1:9944 <Ethereum secret key, hex> "buy 10 SCS at 1.5"
+//! ```
+//!
+//! The order is a statement with the topic `keccak256("orders/SCS-USDT")`, signed with the
+//! Ethereum key. The node accepts it if the account of the key holds at least one `StatementCost`
+//! of native balance.
+
+use codec::{Decode, Encode};
+use fp_account::EthereumSigner;
+use jsonrpsee::{
+    core::client::{ClientT, SubscriptionClientT},
+    rpc_params,
+    ws_client::WsClientBuilder,
+};
+use sp_core::{ecdsa, hashing::keccak_256, Bytes, Pair};
+use sp_runtime::traits::IdentifyAccount;
+use sp_statement_store::Statement;
+
+const MARKET: &[u8] = b"orders/SCS-USDT";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(url), Some(secret), Some(order)) = (args.next(), args.next(), args.next()) else {
+        return Err("usage: statement_order_book <ws url> <secret key> <order>".into());
+    };
+    let secret = sp_core::bytes::from_hex(&secret)?;
+    let pair = ecdsa::Pair::from_seed_slice(&secret).map_err(|_| "invalid secret key")?;
+    let account = EthereumSigner::from(pair.public()).into_account();
+    let topic = keccak_256(MARKET);
+
+    let client = WsClientBuilder::default().build(&url).await?;
+    let mut orders = client
+        .subscribe::<Bytes, _>(
+            "statement_subscribeStatement",
+            rpc_params![vec![topic]],
+            "statement_unsubscribeStatement",
+        )
+        .await?;
+
+    let mut statement = Statement::new();
+    statement.set_topic(0, topic);
+    statement.set_plain_data(order.into_bytes());
+    statement_validation::sign(&mut statement, &pair);
+    client
+        .request::<(), _>("statement_submit", rpc_params![Bytes(statement.encode())])
+        .await?;
+    println!(
+        "{account:?} posted {:?}",
+        sp_core::hexdisplay::HexDisplay::from(&statement.hash())
+    );
+
+    while let Some(order) = orders.next().await {
+        let statement = Statement::decode(&mut &order?[..])?;
+        let signer = statement_validation::ethereum_signer(&statement)
+            .map_err(|err| format!("badly signed order: {err:?}"))?;
+        let data = statement
+            .data()
+            .map(|data| String::from_utf8_lossy(data).into_owned());
+        println!("{signer:?}: {}", data.unwrap_or_default());
+    }
+    Ok(())
+}
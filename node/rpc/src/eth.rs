@@ -1,4 +1,7 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    sync::{atomic::AtomicU64, Arc},
+};
 
 use jsonrpsee::RpcModule;
 // Substrate
@@ -6,12 +9,14 @@ use common_runtime::opaque::Block;
 use sc_client_api::{
     backend::{Backend, StorageProvider},
     client::BlockchainEvents,
-    AuxStore, UsageProvider,
+    AuxStore, ProofProvider, UsageProvider,
 };
 use fc_rpc::pending::ConsensusDataProvider;
+use sc_consensus_grandpa::GrandpaJustificationStream;
 use sc_network::service::traits::NetworkService;
 use sc_network_sync::SyncingService;
 use sc_rpc::SubscriptionTaskExecutor;
+use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool::{ChainApi, Pool};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::{CallApiAt, ProvideRuntimeApi};
@@ -27,6 +32,16 @@ pub use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
 use fc_storage::StorageOverride;
 use fp_rpc::{ConvertTransaction, ConvertTransactionRuntimeApi, EthereumRuntimeRPCApi};
 
+use crate::{
+    eth_estimate::{self, EthAccessListApiServer, EthEstimate},
+    eth_finality::{self, EthFinality},
+    eth_proof::{EthProof, EthProofApiServer},
+    eth_signer::{EthKeystoreSigner, EthPersonal, EthPersonalApiServer},
+    eth_simulate::{self, EthSimulate, EthSimulateApiServer},
+    eth_submit,
+};
+use evm_simulation::EthereumSimulationApi;
+
 /// Extra dependencies for Ethereum compatibility.
 pub struct EthDeps<C, P, A: ChainApi, CT, CIDP> {
     /// The client instance to use.
@@ -41,12 +56,16 @@ pub struct EthDeps<C, P, A: ChainApi, CT, CIDP> {
     pub is_authority: bool,
     /// Whether to enable dev signer
     pub enable_dev_signer: bool,
+    /// Signer of the Ethereum accounts of the keystore, served with unsafe RPC methods only.
+    pub keystore_signer: Option<EthKeystoreSigner>,
     /// Network service
     pub network: Arc<dyn NetworkService>,
     /// Chain syncing service
     pub sync: Arc<SyncingService<Block>>,
     /// Frontier Backend.
     pub frontier_backend: Arc<dyn fc_api::Backend<Block>>,
+    /// Number of the last block indexed by the Frontier mapping worker.
+    pub last_indexed_block: Arc<AtomicU64>,
     /// Ethereum data access overrides.
     pub storage_override: Arc<dyn StorageOverride<Block>>,
     /// Cache for Ethereum block data.
@@ -55,6 +74,8 @@ pub struct EthDeps<C, P, A: ChainApi, CT, CIDP> {
     pub filter_pool: Option<FilterPool>,
     /// Maximum number of logs in a query.
     pub max_past_logs: u32,
+    /// Maximum number of filters stored at once.
+    pub max_stored_filters: usize,
     /// Fee history cache.
     pub fee_history_cache: FeeHistoryCache,
     /// Maximum fee history cache size.
@@ -79,17 +100,22 @@ pub fn create_eth<C, BE, P, A, CT, CIDP, EC>(
         >,
     >,
     pending_consenus_data_provider: Box<dyn ConsensusDataProvider<Block>>,
+    justification_stream: GrandpaJustificationStream<Block>,
+    deny_unsafe: DenyUnsafe,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
     // B: BlockT,
     C: CallApiAt<Block> + ProvideRuntimeApi<Block>,
-    C::Api:
-        BlockBuilderApi<Block> + ConvertTransactionRuntimeApi<Block> + EthereumRuntimeRPCApi<Block>,
+    C::Api: BlockBuilderApi<Block>
+        + ConvertTransactionRuntimeApi<Block>
+        + EthereumRuntimeRPCApi<Block>
+        + EthereumSimulationApi<Block>,
     C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError>,
     C: BlockchainEvents<Block>
         + AuxStore
         + UsageProvider<Block>
         + StorageProvider<Block, BE>
+        + ProofProvider<Block>
         + 'static,
     BE: Backend<Block> + 'static,
     P: TransactionPool<Block = Block> + 'static,
@@ -100,7 +126,7 @@ where
 {
     use fc_rpc::{
         Debug, DebugApiServer, Eth, EthApiServer, EthDevSigner, EthFilter, EthFilterApiServer,
-        EthPubSub, EthPubSubApiServer, EthSigner, Net, NetApiServer, Web3, Web3ApiServer,
+        EthPubSub, EthSigner, Net, NetApiServer, Web3, Web3ApiServer,
     };
 
     let EthDeps {
@@ -110,13 +136,16 @@ where
         converter,
         is_authority,
         enable_dev_signer,
+        keystore_signer,
         network,
         sync,
         frontier_backend,
+        last_indexed_block,
         storage_override,
         block_data_cache,
         filter_pool,
         max_past_logs,
+        max_stored_filters,
         fee_history_cache,
         fee_history_cache_limit,
         execute_gas_limit_multiplier,
@@ -124,12 +153,19 @@ where
         pending_create_inherent_data_providers,
     } = deps;
 
+    // The Ethereum methods are collected apart so that the finality tags can wrap them.
+    let mut eth_io = RpcModule::new(());
+
     let mut signers = Vec::new();
     if enable_dev_signer {
         signers.push(Box::new(EthDevSigner::new()) as Box<dyn EthSigner>);
     }
+    let keystore_signer = keystore_signer.filter(|_| deny_unsafe.check_if_safe().is_ok());
+    if let Some(signer) = &keystore_signer {
+        signers.push(Box::new(signer.clone()));
+    }
 
-    io.merge(
+    eth_io.merge(
         Eth::<_, C, P, CT, BE, A, CIDP, EC>::new(
             client.clone(),
             pool.clone(),
@@ -153,13 +189,13 @@ where
     )?;
 
     if let Some(filter_pool) = filter_pool {
-        io.merge(
+        eth_io.merge(
             EthFilter::new(
                 client.clone(),
                 frontier_backend.clone(),
                 graph.clone(),
                 filter_pool,
-                500_usize, // max stored filters
+                max_stored_filters,
                 max_past_logs,
                 block_data_cache.clone(),
             )
@@ -167,7 +203,14 @@ where
         )?;
     }
 
-    io.merge(
+    let finality = Arc::new(EthFinality::new(
+        client.clone(),
+        last_indexed_block,
+        storage_override.clone(),
+        justification_stream,
+    ));
+    eth_io.merge(eth_finality::with_finalized_heads(
+        finality.clone(),
         EthPubSub::new(
             pool,
             client.clone(),
@@ -175,11 +218,10 @@ where
             subscription_task_executor,
             storage_override.clone(),
             pubsub_notification_sinks,
-        )
-        .into_rpc(),
-    )?;
+        ),
+    )?)?;
 
-    io.merge(
+    eth_io.merge(
         Net::new(
             client.clone(),
             network,
@@ -189,23 +231,48 @@ where
         .into_rpc(),
     )?;
 
-    io.merge(Web3::new(client.clone()).into_rpc())?;
+    eth_io.merge(Web3::new(client.clone()).into_rpc())?;
+
+    if let Some(signer) = keystore_signer {
+        eth_io.merge(EthPersonal::new(signer, deny_unsafe).into_rpc())?;
+    }
+
+    eth_io.merge(EthProof::new(client.clone(), frontier_backend.clone()).into_rpc())?;
 
-    io.merge(
+    let simulate = || {
+        EthSimulate::new(
+            client.clone(),
+            frontier_backend.clone(),
+            execute_gas_limit_multiplier,
+        )
+    };
+    eth_io.merge(simulate().into_rpc())?;
+    eth_io = eth_simulate::with_call_overrides(eth_io, simulate())?;
+
+    let estimate = || EthEstimate::new(client.clone(), frontier_backend.clone());
+    eth_io.merge(estimate().into_rpc())?;
+    eth_io = eth_estimate::with_runtime_estimates(eth_io, estimate())?;
+    eth_io = eth_submit::with_readable_rejections(eth_io)?;
+
+    eth_io.merge(
         Debug::new(
             client.clone(),
-            frontier_backend,
+            frontier_backend.clone(),
             storage_override,
             block_data_cache,
         )
         .into_rpc(),
     )?;
 
+    eth_io = eth_finality::finality_aware(eth_io, finality)?;
+
     #[cfg(feature = "txpool")]
-    io.merge(TxPool::new(client, graph).into_rpc())?;
+    eth_io.merge(TxPool::new(client.clone(), graph).into_rpc())?;
 
     #[cfg(feature = "txpool")]
     use fc_rpc::{TxPool, TxPoolApiServer};
 
+    io.merge(eth_io)?;
+
     Ok(io)
 }
@@ -0,0 +1,750 @@
+//! Budgets of the Ethereum RPC methods.
+//!
+//! Each method may be given a budget, checked before and while it runs:
+//!
+//! - `range`: blocks a filter of `eth_getLogs` or `eth_newFilter` spans, or the block count of
+//!   `eth_feeHistory`; block tags are resolved against the best and finalized blocks;
+//! - `gas`: gas limit of the transaction of `eth_call` and the like, filled in when missing;
+//! - `size`: bytes of the JSON response;
+//! - `time`: seconds the node waits for the response of a call before answering with an error;
+//! - `rate`: calls of the method the node answers per minute, from all clients together;
+//! - `heavy`: whether a call holds one of the `--eth-rpc-max-heavy-calls` slots shared by the
+//!   heavy methods while it runs.
+//!
+//! The budgets are a JSON-RPC middleware, [`BudgetLayer`], of the Ethereum RPC server: they apply
+//! to the calls it receives, whatever module serves them, before the call reaches the method. The
+//! time budget is a deadline for the response, checked whenever the call awaits: Frontier executes
+//! a call in the runtime without awaiting, so such a call is answered when the runtime returns. A
+//! heavy call holds its slot until it is answered.
+//!
+//! A call over a budget is rejected with the EIP-1474 code `-32005` (limit exceeded), or
+//! `-32002` (resource unavailable) when it timed out or no heavy call slot is free. The `data` of
+//! the error names the budget and its limit, e.g. `{"budget":"range","limit":1024}`.
+//!
+//! Budgets are set per method with `--eth-rpc-budget`, over the defaults of [`DEFAULT_BUDGETS`],
+//! and exported to Prometheus with the number of calls each of them rejected.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use common_runtime::opaque::Block;
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use jsonrpsee::{
+    server::{middleware::rpc::RpcServiceT, MethodResponse},
+    types::{ErrorObject, ErrorObjectOwned, Id, Request},
+};
+use serde_json::{json, Value};
+use sp_blockchain::HeaderBackend;
+use substrate_prometheus_endpoint::{
+    register, CounterVec, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64,
+};
+use tokio::sync::Semaphore;
+
+use crate::eth_methods::{
+    positional, Param, INTERNAL_ERROR, LIMIT_EXCEEDED, METHODS, RESOURCE_UNAVAILABLE,
+};
+
+/// Budget of a method; `None` is no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Most blocks a query spans.
+    pub range: Option<u64>,
+    /// Most gas a call is given.
+    pub gas: Option<u64>,
+    /// Most bytes of a result.
+    pub size: Option<usize>,
+    /// Most time spent on a call.
+    pub time: Option<Duration>,
+    /// Most calls per minute.
+    pub rate: Option<u64>,
+    /// Whether a call takes one of the heavy call slots.
+    pub heavy: bool,
+}
+
+const MIB: usize = 1024 * 1024;
+
+/// Period over which the `rate` of a method is counted.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Budgets of the methods that read many blocks or run the EVM, unless configured otherwise.
+pub const DEFAULT_BUDGETS: &[(&str, Budget)] = &[
+    ("eth_getLogs", logs(Some(1024))),
+    ("eth_getFilterLogs", logs(None)),
+    ("eth_getFilterChanges", logs(None)),
+    (
+        "eth_newFilter",
+        Budget {
+            range: Some(1024),
+            ..NONE
+        },
+    ),
+    (
+        "eth_feeHistory",
+        Budget {
+            range: Some(1024),
+            ..NONE
+        },
+    ),
+    ("eth_call", evm()),
+    ("eth_estimateGas", evm()),
+    ("eth_createAccessList", evm()),
+    ("eth_simulateV1", evm()),
+    ("eth_getProof", evm()),
+];
+
+const NONE: Budget = Budget {
+    range: None,
+    gas: None,
+    size: None,
+    time: None,
+    rate: None,
+    heavy: false,
+};
+
+const fn logs(range: Option<u64>) -> Budget {
+    Budget {
+        range,
+        size: Some(10 * MIB),
+        time: Some(Duration::from_secs(30)),
+        heavy: true,
+        ..NONE
+    }
+}
+
+const fn evm() -> Budget {
+    Budget {
+        time: Some(Duration::from_secs(10)),
+        heavy: true,
+        ..NONE
+    }
+}
+
+/// The budgets of the methods, and the heavy call slots and call rates they share.
+///
+/// Clones share the slots, the rates and the metrics.
+#[derive(Clone)]
+pub struct RpcBudgets {
+    methods: HashMap<String, Budget>,
+    heavy_calls: Arc<Semaphore>,
+    max_heavy_calls: usize,
+    /// Start of the current minute of each method with a rate, and the calls answered since.
+    calls: Arc<Mutex<HashMap<String, (Instant, u64)>>>,
+    metrics: Option<BudgetMetrics>,
+}
+
+impl RpcBudgets {
+    /// The default budgets, with `max_heavy_calls` heavy calls running at once.
+    pub fn new(max_heavy_calls: usize) -> Self {
+        Self {
+            methods: DEFAULT_BUDGETS
+                .iter()
+                .map(|(name, budget)| (name.to_string(), *budget))
+                .collect(),
+            heavy_calls: Arc::new(Semaphore::new(max_heavy_calls)),
+            max_heavy_calls,
+            calls: Default::default(),
+            metrics: None,
+        }
+    }
+
+    /// Change the budget of a method, as given to `--eth-rpc-budget`:
+    /// `<method>=<key>:<value>,...`, e.g. `eth_getLogs=range:2000,time:60`. `<value>` is a number,
+    /// `none` to lift the limit, or `true`/`false` for `heavy`; keys not given keep their value.
+    /// `rate` is in calls per minute.
+    pub fn set(&mut self, spec: &str) -> Result<(), String> {
+        let (method, limits) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected <method>=<budget>, got `{spec}`"))?;
+        let budget = self.methods.entry(method.trim().to_string()).or_default();
+        for limit in limits
+            .split(',')
+            .map(str::trim)
+            .filter(|limit| !limit.is_empty())
+        {
+            let (key, value) = limit
+                .split_once(':')
+                .ok_or_else(|| format!("expected <key>:<value>, got `{limit}`"))?;
+            let number = || match value {
+                "none" => Ok(None),
+                value => value
+                    .parse::<u64>()
+                    .map(Some)
+                    .map_err(|e| format!("`{limit}`: {e}")),
+            };
+            match key {
+                "range" => budget.range = number()?,
+                "gas" => budget.gas = number()?,
+                "size" => budget.size = number()?.map(|size| size as usize),
+                "time" => budget.time = number()?.map(Duration::from_secs),
+                "rate" => budget.rate = number()?,
+                "heavy" => budget.heavy = value.parse().map_err(|e| format!("`{limit}`: {e}"))?,
+                key => return Err(format!("unknown budget `{key}`")),
+            }
+        }
+        Ok(())
+    }
+
+    /// The budget of `method`.
+    pub fn budget(&self, method: &str) -> Option<&Budget> {
+        self.methods.get(method)
+    }
+
+    /// Export the budgets, the heavy calls and the rejections to `registry`.
+    pub fn register(&mut self, registry: &Registry) -> Result<(), PrometheusError> {
+        let metrics = BudgetMetrics::register(registry)?;
+        metrics.max_heavy_calls.set(self.max_heavy_calls as u64);
+        for (method, budget) in &self.methods {
+            let limits = [
+                ("range", budget.range),
+                ("gas", budget.gas),
+                ("size", budget.size.map(|size| size as u64)),
+                ("time", budget.time.map(|time| time.as_secs())),
+                ("rate", budget.rate),
+            ];
+            for (name, limit) in limits {
+                if let Some(limit) = limit {
+                    metrics
+                        .limits
+                        .with_label_values(&[method.as_str(), name])
+                        .set(limit);
+                }
+            }
+        }
+        self.metrics = Some(metrics);
+        Ok(())
+    }
+
+    /// Count a call to `method`, unless `rate` calls were already answered this minute.
+    fn admit(&self, method: &str, rate: u64) -> bool {
+        let mut calls = self.calls.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let (start, count) = calls.entry(method.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            (*start, *count) = (now, 0);
+        }
+        if *count >= rate {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// The error of a call to `method` over `budget`, counted in the metrics.
+    fn reject(
+        &self,
+        method: &str,
+        budget: &str,
+        code: i32,
+        message: String,
+        limit: u64,
+    ) -> ErrorObjectOwned {
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .rejections
+                .with_label_values(&[method, budget])
+                .inc();
+        }
+        ErrorObject::owned(
+            code,
+            message,
+            Some(json!({ "budget": budget, "limit": limit })),
+        )
+    }
+}
+
+/// Prometheus metrics of the budgets.
+#[derive(Clone)]
+struct BudgetMetrics {
+    limits: GaugeVec<U64>,
+    max_heavy_calls: Gauge<U64>,
+    heavy_calls: Gauge<U64>,
+    rejections: CounterVec<U64>,
+}
+
+impl BudgetMetrics {
+    fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            limits: register(
+                GaugeVec::new(
+                    Opts::new(
+                        "substrate_eth_rpc_budget",
+                        "Limit of a budget of an Ethereum RPC method, in blocks, gas, bytes, \
+                         seconds or calls per minute",
+                    ),
+                    &["method", "budget"],
+                )?,
+                registry,
+            )?,
+            max_heavy_calls: register(
+                Gauge::new(
+                    "substrate_eth_rpc_max_heavy_calls",
+                    "Number of heavy Ethereum RPC calls allowed to run at once",
+                )?,
+                registry,
+            )?,
+            heavy_calls: register(
+                Gauge::new(
+                    "substrate_eth_rpc_heavy_calls",
+                    "Number of heavy Ethereum RPC calls running",
+                )?,
+                registry,
+            )?,
+            rejections: register(
+                CounterVec::new(
+                    Opts::new(
+                        "substrate_eth_rpc_budget_rejections_total",
+                        "Number of Ethereum RPC calls rejected for going over a budget",
+                    ),
+                    &["method", "budget"],
+                )?,
+                registry,
+            )?,
+        })
+    }
+}
+
+/// Numbers of the best and finalized blocks, to which the block tags resolve.
+type Head = Arc<dyn Fn() -> (u64, u64) + Send + Sync>;
+
+/// The middleware enforcing budgets on the calls of a server, set with
+/// [`RpcServiceBuilder::layer`](jsonrpsee::server::RpcServiceBuilder::layer).
+#[derive(Clone)]
+pub struct BudgetLayer {
+    budgets: Arc<RpcBudgets>,
+    head: Head,
+}
+
+impl BudgetLayer {
+    /// Enforce `budgets`, resolving block tags with `client`.
+    pub fn new<C>(budgets: RpcBudgets, client: Arc<C>) -> Self
+    where
+        C: HeaderBackend<Block> + Send + Sync + 'static,
+    {
+        let head: Head = Arc::new(move || {
+            let info = client.info();
+            (info.best_number.into(), info.finalized_number.into())
+        });
+        Self {
+            budgets: Arc::new(budgets),
+            head,
+        }
+    }
+}
+
+#[cfg(test)]
+impl BudgetLayer {
+    /// The default budgets, with the block tags resolved at the best and finalized `head`.
+    pub(crate) fn at(head: (u64, u64)) -> Self {
+        Self {
+            budgets: Arc::new(RpcBudgets::new(1)),
+            head: Arc::new(move || head),
+        }
+    }
+}
+
+impl<S> tower::Layer<S> for BudgetLayer {
+    type Service = Budgeted<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Budgeted {
+            inner,
+            budgets: self.budgets.clone(),
+            head: self.head.clone(),
+        }
+    }
+}
+
+/// The calls of `S` within their budgets.
+#[derive(Clone)]
+pub struct Budgeted<S> {
+    inner: S,
+    budgets: Arc<RpcBudgets>,
+    head: Head,
+}
+
+impl<'a, S> RpcServiceT<'a> for Budgeted<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, mut request: Request<'a>) -> Self::Future {
+        let Some(budget) = self.budgets.budget(&request.method).copied() else {
+            return self.inner.call(request).boxed();
+        };
+        let budgets = self.budgets.clone();
+        let name = request.method.to_string();
+
+        if let Some(rate) = budget.rate {
+            if !budgets.admit(&name, rate) {
+                let message = format!("more than {rate} calls per minute");
+                let error = budgets.reject(&name, "rate", LIMIT_EXCEEDED, message, rate);
+                return rejected(&request.id, error);
+            }
+        }
+
+        if budget.range.is_some() || budget.gas.is_some() {
+            let checked = positional(&request.params()).and_then(|mut params| {
+                check_params(
+                    &budgets,
+                    &name,
+                    &budget,
+                    schema(&name),
+                    &mut params,
+                    &*self.head,
+                )?;
+                serde_json::value::to_raw_value(&params)
+                    .map_err(|e| ErrorObject::owned(INTERNAL_ERROR, e.to_string(), None::<()>))
+            });
+            match checked {
+                Ok(params) => request.params = Some(Cow::Owned(params)),
+                Err(error) => return rejected(&request.id, error),
+            }
+        }
+
+        let slot = match budget.heavy {
+            true => match budgets.heavy_calls.clone().try_acquire_owned() {
+                Ok(slot) => {
+                    let heavy_calls = budgets.metrics.as_ref().map(|m| m.heavy_calls.clone());
+                    Some((slot, HeavyCall::start(heavy_calls)))
+                }
+                Err(_) => {
+                    let limit = budgets.max_heavy_calls as u64;
+                    let message = "too many heavy requests, retry later".to_string();
+                    let error =
+                        budgets.reject(&name, "heavy", RESOURCE_UNAVAILABLE, message, limit);
+                    return rejected(&request.id, error);
+                }
+            },
+            false => None,
+        };
+
+        let id = request.id.clone();
+        let call = self.inner.call(request);
+        async move {
+            let response = match budget.time {
+                Some(time) => match tokio::time::timeout(time, call).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        let (limit, message) = (time.as_secs(), "request timed out".into());
+                        let error =
+                            budgets.reject(&name, "time", RESOURCE_UNAVAILABLE, message, limit);
+                        return MethodResponse::error(id, error);
+                    }
+                },
+                None => call.await,
+            };
+            drop(slot);
+
+            match budget.size {
+                Some(size) if response.as_result().len() > size => {
+                    let bytes = response.as_result().len();
+                    let message = format!("response of {bytes} bytes exceeds {size} bytes");
+                    let error = budgets.reject(&name, "size", LIMIT_EXCEEDED, message, size as u64);
+                    MethodResponse::error(id, error)
+                }
+                _ => response,
+            }
+        }
+        .boxed()
+    }
+}
+
+/// The answer to the call `id` rejected with `error`.
+fn rejected(id: &Id, error: ErrorObjectOwned) -> BoxFuture<'static, MethodResponse> {
+    future::ready(MethodResponse::error(id.clone(), error)).boxed()
+}
+
+/// The parameters of `method`, if known.
+fn schema(method: &str) -> &'static [Param] {
+    METHODS
+        .iter()
+        .find(|(name, ..)| *name == method)
+        .map_or(&[][..], |m| m.1)
+}
+
+/// Counts a running heavy call in the metrics while alive.
+struct HeavyCall(Option<Gauge<U64>>);
+
+impl HeavyCall {
+    fn start(gauge: Option<Gauge<U64>>) -> Self {
+        gauge.iter().for_each(|gauge| gauge.inc());
+        Self(gauge)
+    }
+}
+
+impl Drop for HeavyCall {
+    fn drop(&mut self) {
+        self.0.iter().for_each(|gauge| gauge.dec());
+    }
+}
+
+/// Check the range and the gas of `params`, laid out as `schema`, filling in a missing gas limit.
+fn check_params(
+    budgets: &RpcBudgets,
+    name: &str,
+    budget: &Budget,
+    schema: &[Param],
+    params: &mut [Value],
+    head: &dyn Fn() -> (u64, u64),
+) -> Result<(), ErrorObjectOwned> {
+    if let Some(limit) = budget.range {
+        let range = match name {
+            "eth_feeHistory" => params.first().and_then(quantity),
+            _ => schema
+                .iter()
+                .zip(params.iter())
+                .find(|(param, _)| matches!(param, Param::Filter))
+                .and_then(|(_, filter)| filter_range(filter, head())),
+        };
+        if let Some(range) = range.filter(|range| *range > limit) {
+            let message = format!("query spans {range} blocks, more than {limit}");
+            return Err(budgets.reject(name, "range", LIMIT_EXCEEDED, message, limit));
+        }
+    }
+
+    if let Some(limit) = budget.gas {
+        let transaction = schema
+            .iter()
+            .zip(params.iter_mut())
+            .find(|(param, _)| matches!(param, Param::Transaction))
+            .and_then(|(_, transaction)| transaction.as_object_mut());
+        if let Some(transaction) = transaction {
+            match transaction.get("gas").and_then(quantity) {
+                Some(gas) if gas > limit => {
+                    let message = format!("gas {gas} exceeds the limit of {limit}");
+                    return Err(budgets.reject(name, "gas", LIMIT_EXCEEDED, message, limit));
+                }
+                Some(_) => {}
+                None => {
+                    transaction.insert("gas".into(), json!(format!("{limit:#x}")));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Number of blocks spanned by `filter`, if known before the call.
+fn filter_range(filter: &Value, (best, finalized): (u64, u64)) -> Option<u64> {
+    if filter.get("blockHash").is_some_and(|hash| !hash.is_null()) {
+        return Some(1);
+    }
+    let block = |key: &str| match filter.get(key) {
+        None | Some(Value::Null) => Some(best),
+        Some(Value::String(tag)) => match tag.as_str() {
+            "earliest" => Some(0),
+            "latest" | "pending" => Some(best),
+            "safe" | "finalized" => Some(finalized),
+            _ => quantity(&filter[key]),
+        },
+        Some(value) => quantity(value),
+    };
+    let (from, to) = (block("fromBlock")?, block("toBlock")?);
+    Some(to.saturating_sub(from).saturating_add(1))
+}
+
+/// A quantity given as a hex string or a number.
+fn quantity(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(hex) => u64::from_str_radix(hex.strip_prefix("0x")?, 16).ok(),
+        value => value.as_u64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth_methods::INVALID_PARAMS;
+    use jsonrpsee::types::ResponsePayload;
+
+    const HEAD: (u64, u64) = (1_000, 900);
+
+    fn budgets(specs: &[&str]) -> RpcBudgets {
+        let mut budgets = RpcBudgets::new(1);
+        specs.iter().for_each(|spec| budgets.set(spec).unwrap());
+        budgets
+    }
+
+    /// Methods answering 701 bytes of logs, the gas of the call and an estimate after 200ms.
+    #[derive(Clone)]
+    struct Methods;
+
+    impl<'a> RpcServiceT<'a> for Methods {
+        type Future = BoxFuture<'a, MethodResponse>;
+
+        fn call(&self, request: Request<'a>) -> Self::Future {
+            async move {
+                let result = match request.method_name() {
+                    "eth_getLogs" => json!(vec!["0x00"; 100]),
+                    "eth_call" => positional(&request.params()).unwrap()[0]["gas"].clone(),
+                    "eth_estimateGas" => {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        json!("0x5208")
+                    }
+                    method => panic!("unexpected call to {method}"),
+                };
+                MethodResponse::response(request.id, ResponsePayload::success(result), usize::MAX)
+            }
+            .boxed()
+        }
+    }
+
+    fn budgeted(budgets: RpcBudgets) -> Budgeted<Methods> {
+        Budgeted {
+            inner: Methods,
+            budgets: Arc::new(budgets),
+            head: Arc::new(|| HEAD),
+        }
+    }
+
+    async fn call(
+        service: &Budgeted<Methods>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, (i32, Value)> {
+        let params = serde_json::value::to_raw_value(&params).unwrap();
+        let request = Request::new(method.into(), Some(&params), Id::Number(1));
+        let response = service.call(request).await;
+        let mut response: Value = serde_json::from_str(response.as_result()).unwrap();
+        match response.get_mut("error") {
+            None => Ok(response["result"].take()),
+            Some(error) => Err((
+                error["code"].as_i64().unwrap() as i32,
+                error.get_mut("data").map(Value::take).unwrap_or_default(),
+            )),
+        }
+    }
+
+    #[test]
+    fn parses_budgets() {
+        let budgets = budgets(&["eth_getLogs=range:2000,time:none", "eth_call=gas:30000000"]);
+        assert_eq!(
+            budgets.budget("eth_getLogs"),
+            Some(&Budget {
+                range: Some(2000),
+                time: None,
+                ..logs(None)
+            })
+        );
+        assert_eq!(
+            budgets.budget("eth_call"),
+            Some(&Budget {
+                gas: Some(30_000_000),
+                ..evm()
+            })
+        );
+
+        let mut budgets = RpcBudgets::new(1);
+        assert!(budgets.set("eth_call").is_err());
+        assert!(budgets.set("eth_call=gas").is_err());
+        assert!(budgets.set("eth_call=speed:1").is_err());
+        assert!(budgets.set("eth_call=gas:lots").is_err());
+    }
+
+    #[test]
+    fn resolves_filter_ranges() {
+        let range = |filter| filter_range(&filter, HEAD);
+        assert_eq!(range(json!({})), Some(1));
+        assert_eq!(range(json!({ "fromBlock": "earliest" })), Some(1_001));
+        assert_eq!(
+            range(json!({ "fromBlock": "0x64", "toBlock": "safe" })),
+            Some(801)
+        );
+        assert_eq!(
+            range(json!({ "fromBlock": "0x0", "blockHash": "0x01" })),
+            Some(1)
+        );
+        assert_eq!(
+            range(json!({ "fromBlock": "0x64", "toBlock": "0x10" })),
+            Some(1)
+        );
+        assert_eq!(range(json!({ "fromBlock": "soon" })), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_logs_over_budget() {
+        let service = budgeted(budgets(&["eth_getLogs=range:100,size:1000"]));
+        let logs = |filter| call(&service, "eth_getLogs", json!([filter]));
+        assert!(logs(json!({ "fromBlock": "0x385" })).await.is_ok());
+        assert_eq!(
+            logs(json!({ "fromBlock": "0x384" })).await,
+            Err((LIMIT_EXCEEDED, json!({ "budget": "range", "limit": 100 })))
+        );
+
+        let service = budgeted(budgets(&["eth_getLogs=size:700"]));
+        assert_eq!(
+            call(&service, "eth_getLogs", json!([{}])).await,
+            Err((LIMIT_EXCEEDED, json!({ "budget": "size", "limit": 700 })))
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_parameters_that_are_not_positional() {
+        let service = budgeted(budgets(&[]));
+        let (code, _) = call(&service, "eth_call", json!({ "gas": "0x5208" }))
+            .await
+            .unwrap_err();
+        assert_eq!(code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn limits_the_rate_of_calls() {
+        let service = budgeted(budgets(&["eth_call=rate:2"]));
+        let eth_call = || call(&service, "eth_call", json!([{ "gas": "0x5208" }]));
+        assert!(eth_call().await.is_ok());
+        assert!(eth_call().await.is_ok());
+        assert_eq!(
+            eth_call().await,
+            Err((LIMIT_EXCEEDED, json!({ "budget": "rate", "limit": 2 })))
+        );
+        // Other methods have their own rate.
+        assert!(call(&service, "eth_getLogs", json!([{}])).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn caps_the_gas_of_calls() {
+        let service = budgeted(budgets(&["eth_call=gas:50000"]));
+        let eth_call = |gas: Value| call(&service, "eth_call", json!([{ "gas": gas }, "latest"]));
+        assert_eq!(eth_call(json!("0x5208")).await, Ok(json!("0x5208")));
+        assert_eq!(eth_call(Value::Null).await, Ok(json!("0xc350")));
+        assert_eq!(
+            eth_call(json!("0xc351")).await,
+            Err((LIMIT_EXCEEDED, json!({ "budget": "gas", "limit": 50_000 })))
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_time_and_heavy_calls() {
+        let service = budgeted(budgets(&[]));
+        let estimate = || call(&service, "eth_estimateGas", json!([{}]));
+        // A single heavy call runs at a time.
+        let (first, second) = futures::join!(estimate(), estimate());
+        assert_eq!(first, Ok(json!("0x5208")));
+        assert_eq!(
+            second,
+            Err((
+                RESOURCE_UNAVAILABLE,
+                json!({ "budget": "heavy", "limit": 1 })
+            ))
+        );
+
+        let mut budgets = budgets(&[]);
+        budgets.methods.get_mut("eth_estimateGas").unwrap().time = Some(Duration::from_millis(50));
+        let service = budgeted(budgets);
+        let (code, data) = call(&service, "eth_estimateGas", json!([{}]))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            (code, &data["budget"]),
+            (RESOURCE_UNAVAILABLE, &json!("time"))
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Gas estimation that covers the proof size of a call, and `eth_createAccessList`.
+//!
+//! With `GasLimitPovSizeRatio` a transaction pays for the larger of its EVM gas and the gas of the
+//! proof size of the storage it reads, so calls touching many cold slots need more gas than the
+//! EVM charges. `eth_estimateGas` is answered by `EthereumSimulationApi::estimate_gas`: the runtime
+//! searches the smallest gas limit with which the call succeeds while charged as a transaction.
+//! Runtimes without the API are still estimated by Frontier, with [`ScsEstimateGasAdapter`].
+//!
+//! `eth_createAccessList` executes the call with a proof recorder, then turns the keys of the
+//! recorded proof and of the storage written back into accounts and slots, with the `evm-proof`
+//! crate. As with Geth, the call runs again with the access list found until it stops changing.
+//! Slots read while empty and never written leave no key in the proof, so they are not listed.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    sync::Arc,
+};
+
+use common_runtime::opaque::Block;
+use evm_proof::{decode_key, proven_keys, EvmKey};
+use evm_simulation::{CallOutcome, EthereumSimulationApi, SimulatedCall};
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::{BlockNumberOrHash, TransactionRequest};
+use fp_evm::{ExitError, ExitReason};
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    RpcModule,
+};
+use serde::Serialize;
+use serde_json::Value;
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::Header as HeaderT;
+
+use crate::{
+    eth_methods::{forward, positional},
+    eth_simulate::{check_exit, revert_message, AccessListItem, CallRequest},
+};
+
+/// Most executions of a call by `eth_createAccessList`.
+const MAX_ROUNDS: usize = 8;
+/// Precompiles are below this address; they are warm without being listed.
+const LAST_PRECOMPILE: u64 = 0xffff;
+
+/// A call executed with a proof recorder.
+struct Trace {
+    reason: ExitReason,
+    output: Vec<u8>,
+    /// Gas used, proof size included.
+    used_gas: U256,
+    /// Address of the contract created.
+    created: Option<H160>,
+    /// EVM storage keys read or written.
+    keys: BTreeSet<EvmKey>,
+}
+
+/// `EstimateGasAdapter` of Frontier's estimation, used for runtimes without `estimate_gas`.
+///
+/// A request without an access list gets an empty one, so that the runtime charges the proof size
+/// of the envelope the transaction will be sent in.
+pub struct ScsEstimateGasAdapter;
+
+impl fc_rpc::EstimateGasAdapter for ScsEstimateGasAdapter {
+    fn adapt_request(mut request: TransactionRequest) -> TransactionRequest {
+        request.access_list.get_or_insert_with(Vec::new);
+        request
+    }
+}
+
+/// The response of `eth_createAccessList`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListResult {
+    /// Accounts and slots accessed by the call.
+    pub access_list: Vec<AccessListItem>,
+    /// Gas used with the access list, proof size included.
+    pub gas_used: U256,
+    /// Why the call failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// EIP-2930 access lists.
+#[rpc(server)]
+pub trait EthAccessListApi {
+    /// The access list of `request` executed on top of `block`, the latest by default.
+    #[method(name = "eth_createAccessList")]
+    async fn create_access_list(
+        &self,
+        request: CallRequest,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<AccessListResult>;
+}
+
+/// Implementation of [`EthAccessListApiServer`] and of `eth_estimateGas` through the runtime.
+pub struct EthEstimate<C> {
+    client: Arc<C>,
+    frontier_backend: Arc<dyn fc_api::Backend<Block>>,
+}
+
+impl<C> EthEstimate<C> {
+    /// Create the RPC handler.
+    pub fn new(client: Arc<C>, frontier_backend: Arc<dyn fc_api::Backend<Block>>) -> Self {
+        Self {
+            client,
+            frontier_backend,
+        }
+    }
+}
+
+impl<C> EthEstimate<C>
+where
+    C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    /// Substrate block of `block`, the latest by default.
+    async fn block_hash(&self, block: Option<BlockNumberOrHash>) -> RpcResult<H256> {
+        let id = frontier_backend_client::native_block_id::<Block, C>(
+            &*self.client,
+            &*self.frontier_backend,
+            block,
+        )
+        .await?
+        .ok_or_else(|| internal_err("header not found"))?;
+        self.client
+            .expect_block_hash_from_id(&id)
+            .map_err(internal_err)
+    }
+
+    /// Gas limit of the Ethereum block at `hash`, the most a transaction can use.
+    fn block_gas_limit(&self, hash: H256) -> RpcResult<U256> {
+        let block = self
+            .client
+            .runtime_api()
+            .current_block(hash)
+            .map_err(internal_err)?
+            .ok_or_else(|| internal_err("Ethereum block not found"))?;
+        Ok(block.header.gas_limit)
+    }
+
+    /// Whether the runtime at `hash` estimates gas itself.
+    fn estimates(&self, hash: H256) -> RpcResult<bool> {
+        self.client
+            .runtime_api()
+            .has_api::<dyn EthereumSimulationApi<Block>>(hash)
+            .map_err(internal_err)
+    }
+
+    /// `eth_estimateGas` answered by the runtime at `hash`.
+    fn estimate_gas(&self, request: CallRequest, hash: H256) -> RpcResult<U256> {
+        let call = request.into_runtime(self.block_gas_limit(hash)?)?;
+        let estimate = self
+            .client
+            .runtime_api()
+            .estimate_gas(hash, call)
+            .map_err(internal_err)?
+            .map_err(|e| internal_err(format!("runtime error: {e:?}")))?;
+
+        let (reason, value) = match estimate.outcome {
+            CallOutcome::Call(info) => (info.exit_reason, info.value),
+            CallOutcome::Deployed(info, _) => (info.exit_reason, Vec::new()),
+            CallOutcome::Failed(e) => return Err(internal_err(format!("runtime error: {e:?}"))),
+        };
+        match reason {
+            ExitReason::Error(ExitError::OutOfGas) => Err(internal_err(format!(
+                "gas required exceeds allowance {}",
+                estimate.gas_limit
+            ))),
+            reason => check_exit(&reason, &value).map(|()| estimate.gas_limit),
+        }
+    }
+
+    /// Execute `call` at `hash`, whose state root is `state_root`, recording what it accesses.
+    fn trace(&self, call: &SimulatedCall, hash: H256, state_root: H256) -> RpcResult<Trace> {
+        let mut api = self.client.runtime_api();
+        api.record_proof();
+
+        let SimulatedCall {
+            from,
+            to,
+            data,
+            value,
+            gas_limit,
+            nonce,
+            ..
+        } = call.clone();
+        let access_list = Some(call.access_list.clone());
+        let outcome = match to {
+            Some(to) => api
+                .call(
+                    hash,
+                    from,
+                    to,
+                    data,
+                    value,
+                    gas_limit,
+                    None,
+                    None,
+                    nonce,
+                    false,
+                    access_list,
+                )
+                .map_err(internal_err)?
+                .map(|info| (info.exit_reason, info.value, info.used_gas.effective, None)),
+            None => api
+                .create(
+                    hash,
+                    from,
+                    data,
+                    value,
+                    gas_limit,
+                    None,
+                    None,
+                    nonce,
+                    false,
+                    access_list,
+                )
+                .map_err(internal_err)?
+                .map(|info| {
+                    (
+                        info.exit_reason,
+                        Vec::new(),
+                        info.used_gas.effective,
+                        Some(info.value),
+                    )
+                }),
+        };
+        let (reason, output, used_gas, created) =
+            outcome.map_err(|e| internal_err(format!("runtime error: {e:?}")))?;
+
+        let proof = api
+            .extract_proof()
+            .ok_or_else(|| internal_err("no proof was recorded"))?;
+        let state = self.client.state_at(hash).map_err(internal_err)?;
+        let changes = api
+            .into_storage_changes(&state, hash)
+            .map_err(internal_err)?;
+        let keys = proven_keys(state_root, proof.into_iter_nodes())
+            .into_iter()
+            .chain(changes.main_storage_changes.into_iter().map(|(key, _)| key))
+            .filter_map(|key| decode_key(&key))
+            .collect();
+        Ok(Trace {
+            reason,
+            output,
+            used_gas,
+            created,
+            keys,
+        })
+    }
+}
+
+#[async_trait]
+impl<C> EthAccessListApiServer for EthEstimate<C>
+where
+    C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    async fn create_access_list(
+        &self,
+        request: CallRequest,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<AccessListResult> {
+        let hash = self.block_hash(block).await?;
+        let state_root = *self
+            .client
+            .expect_header(hash)
+            .map_err(internal_err)?
+            .state_root();
+        // Fees are left out: they read accounts the EVM does not access.
+        let mut call = request.into_runtime(self.block_gas_limit(hash)?)?;
+
+        let mut rounds = 0;
+        loop {
+            let trace = self.trace(&call, hash, state_root)?;
+            let warm: Vec<_> = [Some(call.from), call.to, trace.created]
+                .into_iter()
+                .flatten()
+                .collect();
+            let access_list = access_list(trace.keys, &warm);
+            rounds += 1;
+            if access_list == call.access_list || rounds == MAX_ROUNDS {
+                let error = match trace.reason {
+                    ExitReason::Succeed(_) => None,
+                    ExitReason::Revert(_) => Some(revert_message(&trace.output)),
+                    ExitReason::Error(e) => Some(format!("{e:?}")),
+                    ExitReason::Fatal(e) => Some(format!("{e:?}")),
+                };
+                return Ok(AccessListResult {
+                    access_list: access_list
+                        .into_iter()
+                        .map(|(address, storage_keys)| AccessListItem {
+                            address,
+                            storage_keys,
+                        })
+                        .collect(),
+                    gas_used: trace.used_gas,
+                    error,
+                });
+            }
+            call.access_list = access_list;
+        }
+    }
+}
+
+/// The access list of a call that accessed `keys`.
+///
+/// As with EIP-2929, the accounts in `warm`, the sender, the callee or the contract created, and
+/// the precompiles are warm already, so they are listed only for their slots.
+fn access_list(keys: BTreeSet<EvmKey>, warm: &[H160]) -> Vec<(H160, Vec<H256>)> {
+    let cold = |address: &H160| {
+        !warm.contains(address) && *address > H160::from_low_u64_be(LAST_PRECOMPILE)
+    };
+
+    let mut list = BTreeMap::<H160, Vec<H256>>::new();
+    for key in keys {
+        match key {
+            EvmKey::Account(address) | EvmKey::Code(address) if cold(&address) => {
+                list.entry(address).or_default();
+            }
+            EvmKey::Storage(address, slot) => list.entry(address).or_default().push(slot),
+            _ => {}
+        }
+    }
+    list.into_iter().collect()
+}
+
+/// Serve `eth_estimateGas` of `io` through `estimate` when the runtime supports it, through
+/// Frontier otherwise.
+pub fn with_runtime_estimates<C>(
+    io: RpcModule<()>,
+    estimate: EthEstimate<C>,
+) -> Result<RpcModule<()>, Box<dyn Error + Send + Sync>>
+where
+    C: ProvideRuntimeApi<Block> + CallApiAt<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    let inner = Arc::new(io.clone());
+    let estimate = Arc::new(estimate);
+    let mut module = io;
+    if module.remove_method("eth_estimateGas").is_none() {
+        return Ok(module);
+    }
+    module.register_async_method("eth_estimateGas", move |request, _| {
+        let (inner, estimate) = (inner.clone(), estimate.clone());
+        async move {
+            let params = positional(&request)?;
+            let mut sequence = request.sequence();
+            let call: CallRequest = sequence.next()?;
+            let block = sequence.optional_next()?;
+            // State overrides are Frontier's.
+            let hash = estimate.block_hash(block).await?;
+            if params.iter().skip(2).any(|param| !param.is_null()) || !estimate.estimates(hash)? {
+                return forward(&inner, "eth_estimateGas", params).await;
+            }
+            serde_json::to_value(estimate.estimate_gas(call, hash)?).map_err(internal_err)
+        }
+    })?;
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(n: u64) -> H160 {
+        H160::from_low_u64_be(n)
+    }
+
+    fn slot(n: u64) -> H256 {
+        H256::from_low_u64_be(n)
+    }
+
+    #[test]
+    fn lists_the_cold_accounts_and_every_slot() {
+        let (from, to, other) = (addr(0x10_0001), addr(0x10_0002), addr(0x10_0003));
+        let mut keys = BTreeSet::from([
+            EvmKey::Account(from),
+            EvmKey::Account(to),
+            EvmKey::Code(to),
+            EvmKey::Code(addr(1)),
+            EvmKey::Account(other),
+            EvmKey::Code(other),
+            EvmKey::Storage(other, slot(7)),
+        ]);
+        // The 200 cold slots of the callee, read in any order.
+        keys.extend((0..200).rev().map(|n| EvmKey::Storage(to, slot(n))));
+
+        let list = access_list(keys, &[from, to]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].0, to);
+        assert_eq!(list[0].1, (0..200).map(slot).collect::<Vec<_>>());
+        assert_eq!(list[1], (other, vec![slot(7)]));
+    }
+
+    #[test]
+    fn adapted_requests_have_an_access_list() {
+        use fc_rpc::EstimateGasAdapter;
+
+        let request = ScsEstimateGasAdapter::adapt_request(TransactionRequest::default());
+        assert_eq!(request.access_list, Some(Vec::new()));
+    }
+}
@@ -0,0 +1,406 @@
+//! GRANDPA finality in the Ethereum RPC.
+//!
+//! The `finalized` and `safe` block tags both stand for the last block finalized by GRANDPA: its
+//! finality is deterministic, so there is no weaker "safe" head. Frontier resolves the tags when it
+//! looks up a single block, but treats them as the best block in the range of `eth_getLogs`. The
+//! methods taking a block tag are therefore wrapped so that the tags reach Frontier as the number
+//! of the finalized block, capped by the last block the Frontier mapping worker indexed, so that
+//! `finalized` is never ahead of `latest`. Filters of `eth_newFilter` are left as they are: their
+//! range follows new blocks, which a number would not.
+//!
+//! `eth_subscribe("newFinalizedHeads")` streams the header of every Ethereum block as GRANDPA
+//! finalizes it, in the format of `newHeads`, from the justifications of the GRANDPA voter. When a
+//! justification finalizes several blocks at once, each of them is sent, in order. Only that kind
+//! is served here: the other kinds are handed to Frontier as they are, with the subscription of
+//! the caller, so that Frontier keeps serving them as it does without this module.
+
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use common_runtime::{opaque::Block, BlockNumber};
+use fc_rpc::EthPubSubApiServer;
+use fc_rpc_core::types::pubsub::{Kind, Params as SubscriptionParams, PubSubResult};
+use fc_storage::StorageOverride;
+use futures::{stream, Stream, StreamExt};
+use jsonrpsee::{
+    types::{ErrorObjectOwned, Params},
+    RpcModule,
+};
+use sc_consensus_grandpa::GrandpaJustificationStream;
+use sc_rpc::utils::pipe_from_stream;
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::SaturatedConversion;
+
+use crate::eth_methods::{forward, positional, Param, METHODS};
+
+/// Subscription kind of the finalized heads.
+const FINALIZED_HEADS: &str = "newFinalizedHeads";
+/// Justifications queued for a subscriber before a warning is logged.
+const QUEUE_SIZE_WARNING: usize = 1_000;
+
+/// The finalized block and its headers, as seen by the Ethereum RPC.
+pub struct EthFinality<C> {
+    client: Arc<C>,
+    last_indexed_block: Arc<AtomicU64>,
+    storage_override: Arc<dyn StorageOverride<Block>>,
+    justification_stream: GrandpaJustificationStream<Block>,
+}
+
+impl<C> EthFinality<C> {
+    /// Create the handler of the finality tags and of `newFinalizedHeads`. `last_indexed_block`
+    /// is the number of the last block indexed by the Frontier mapping worker, as the node keeps
+    /// it up to date.
+    pub fn new(
+        client: Arc<C>,
+        last_indexed_block: Arc<AtomicU64>,
+        storage_override: Arc<dyn StorageOverride<Block>>,
+        justification_stream: GrandpaJustificationStream<Block>,
+    ) -> Self {
+        Self {
+            client,
+            last_indexed_block,
+            storage_override,
+            justification_stream,
+        }
+    }
+}
+
+impl<C> EthFinality<C>
+where
+    C: HeaderBackend<Block> + Send + Sync + 'static,
+{
+    /// Number of the finalized block, or of the last indexed one when indexing is behind.
+    fn finalized_number(&self) -> BlockNumber {
+        let indexed = self
+            .last_indexed_block
+            .load(Ordering::Relaxed)
+            .saturated_into();
+        self.client.info().finalized_number.min(indexed)
+    }
+
+    /// Ethereum headers of the blocks finalized from now on.
+    fn finalized_heads(&self) -> impl Stream<Item = PubSubResult> + Send + Unpin + 'static {
+        let client = self.client.clone();
+        let storage_override = self.storage_override.clone();
+        let mut next: Option<BlockNumber> = None;
+
+        self.justification_stream
+            .subscribe(QUEUE_SIZE_WARNING)
+            .flat_map(move |justification| {
+                let target = justification.justification.commit.target_number;
+                let heads = (next.unwrap_or(target)..=target)
+                    .filter_map(|number| {
+                        let hash = client.hash(number).ok().flatten()?;
+                        storage_override
+                            .current_block(hash)
+                            .map(PubSubResult::header)
+                    })
+                    .collect::<Vec<_>>();
+                next = next.max(Some(target + 1));
+                stream::iter(heads)
+            })
+    }
+}
+
+/// Serve the `finalized` and `safe` tags of `io` as the GRANDPA-finalized block.
+pub fn finality_aware<C>(
+    io: RpcModule<()>,
+    finality: Arc<EthFinality<C>>,
+) -> Result<RpcModule<()>, Box<dyn Error + Send + Sync>>
+where
+    C: HeaderBackend<Block> + Send + Sync + 'static,
+{
+    let inner = Arc::new(io.clone());
+    let mut module = io;
+
+    for &(name, schema, _) in METHODS {
+        let tagged = schema.iter().any(|param| {
+            matches!(param, Param::Block | Param::BlockOrHash)
+                || matches!(param, Param::Filter) && name != "eth_newFilter"
+        });
+        if !tagged || module.remove_method(name).is_none() {
+            continue;
+        }
+        let (inner, finality) = (inner.clone(), finality.clone());
+        module.register_async_method(name, move |request, _| {
+            let (inner, finality) = (inner.clone(), finality.clone());
+            async move {
+                let mut params = positional(&request)?;
+                let tags = finality_tags(&mut params, schema);
+                if !tags.is_empty() {
+                    let finalized = format!("{:#x}", finality.finalized_number());
+                    tags.into_iter()
+                        .for_each(|tag| *tag = Value::String(finalized.clone()));
+                }
+                forward(&inner, name, params).await
+            }
+        })?;
+    }
+    Ok(module)
+}
+
+/// The `eth_subscribe` of Frontier's `pubsub`, with the `newFinalizedHeads` kind served from
+/// `finality`.
+pub fn with_finalized_heads<C, S>(
+    finality: Arc<EthFinality<C>>,
+    pubsub: S,
+) -> Result<RpcModule<()>, Box<dyn Error + Send + Sync>>
+where
+    C: HeaderBackend<Block> + Send + Sync + 'static,
+    S: EthPubSubApiServer + Send + Sync + 'static,
+{
+    let pubsub = Arc::new(pubsub);
+    let mut module = RpcModule::new(());
+    module.register_subscription(
+        "eth_subscribe",
+        "eth_subscription",
+        "eth_unsubscribe",
+        move |request, pending, _| {
+            let (pubsub, finality) = (pubsub.clone(), finality.clone());
+            async move {
+                if request.sequence().next::<String>().ok().as_deref() == Some(FINALIZED_HEADS) {
+                    return pipe_from_stream(pending, finality.finalized_heads()).await;
+                }
+                match subscription_params(&request) {
+                    Ok((kind, params)) => pubsub.subscribe(pending, kind, params),
+                    Err(e) => pending.reject(e).await,
+                }
+            }
+        },
+    )?;
+    Ok(module)
+}
+
+/// The parameters of a Frontier `eth_subscribe`, read as Frontier reads them.
+fn subscription_params(
+    request: &Params,
+) -> Result<(Kind, Option<SubscriptionParams>), ErrorObjectOwned> {
+    let mut seq = request.sequence();
+    Ok((seq.next()?, seq.optional_next()?))
+}
+
+/// The `finalized` and `safe` tags among `params`, laid out as `schema`.
+fn finality_tags<'a>(params: &'a mut [Value], schema: &[Param]) -> Vec<&'a mut Value> {
+    let is_tag = |value: &Value| matches!(value.as_str(), Some("finalized" | "safe"));
+
+    let mut tags = Vec::new();
+    for (param, value) in schema.iter().zip(params.iter_mut()) {
+        match (param, value) {
+            (Param::Block | Param::BlockOrHash, value) if is_tag(value) => tags.push(value),
+            (Param::Filter, Value::Object(filter)) => tags.extend(
+                filter
+                    .iter_mut()
+                    .filter(|(key, _)| *key == "fromBlock" || *key == "toBlock")
+                    .filter_map(|(_, value)| is_tag(value).then_some(value)),
+            ),
+            _ => {}
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_runtime::opaque::Header;
+    use fp_rpc::TransactionStatus;
+    use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+    use sc_consensus_grandpa::{GrandpaJustification, GrandpaJustificationSender};
+    use serde_json::json;
+    use sp_blockchain::{BlockStatus, Info};
+    use sp_core::{H160, H256, U256};
+    use sp_runtime::{traits::Header as _, Permill};
+
+    /// A chain whose block `n` has the hash `n`, with an Ethereum block of the same number.
+    struct Chain;
+
+    impl HeaderBackend<Block> for Chain {
+        fn header(&self, _: H256) -> sp_blockchain::Result<Option<Header>> {
+            Ok(None)
+        }
+        fn info(&self) -> Info<Block> {
+            unimplemented!("not read by newFinalizedHeads")
+        }
+        fn status(&self, _: H256) -> sp_blockchain::Result<BlockStatus> {
+            Ok(BlockStatus::InChain)
+        }
+        fn number(&self, hash: H256) -> sp_blockchain::Result<Option<BlockNumber>> {
+            Ok(Some(hash.to_low_u64_be().saturated_into()))
+        }
+        fn hash(&self, number: BlockNumber) -> sp_blockchain::Result<Option<H256>> {
+            Ok(Some(H256::from_low_u64_be(number.into())))
+        }
+    }
+
+    impl StorageOverride<Block> for Chain {
+        fn account_code_at(&self, _: H256, _: H160) -> Option<Vec<u8>> {
+            None
+        }
+        fn account_storage_at(&self, _: H256, _: H160, _: U256) -> Option<H256> {
+            None
+        }
+        fn current_block(&self, at: H256) -> Option<ethereum::BlockV2> {
+            let header = ethereum::PartialHeader {
+                parent_hash: Default::default(),
+                beneficiary: Default::default(),
+                state_root: Default::default(),
+                receipts_root: Default::default(),
+                logs_bloom: Default::default(),
+                difficulty: Default::default(),
+                number: at.to_low_u64_be().into(),
+                gas_limit: Default::default(),
+                gas_used: Default::default(),
+                timestamp: Default::default(),
+                extra_data: Default::default(),
+                mix_hash: Default::default(),
+                nonce: Default::default(),
+            };
+            Some(ethereum::BlockV2::new(header, Vec::new(), Vec::new()))
+        }
+        fn current_receipts(&self, _: H256) -> Option<Vec<ethereum::ReceiptV3>> {
+            None
+        }
+        fn current_transaction_statuses(&self, _: H256) -> Option<Vec<TransactionStatus>> {
+            None
+        }
+        fn elasticity(&self, _: H256) -> Option<Permill> {
+            None
+        }
+        fn is_eip1559(&self, _: H256) -> bool {
+            true
+        }
+    }
+
+    /// Frontier's `eth_subscribe`, sending the kind it was called with.
+    struct PubSub;
+
+    impl EthPubSubApiServer for PubSub {
+        fn subscribe(
+            &self,
+            pending: PendingSubscriptionSink,
+            kind: Kind,
+            _: Option<SubscriptionParams>,
+        ) {
+            tokio::spawn(async move {
+                let Ok(sink) = pending.accept().await else {
+                    return;
+                };
+                let message = SubscriptionMessage::from_json(&format!("{kind:?}")).unwrap();
+                let _ = sink.send(message).await;
+            });
+        }
+    }
+
+    fn subscriptions() -> (RpcModule<()>, GrandpaJustificationSender<Block>) {
+        let (sender, justification_stream) = GrandpaJustificationStream::channel();
+        let finality = EthFinality::new(
+            Arc::new(Chain),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Chain),
+            justification_stream,
+        );
+        let module = with_finalized_heads(Arc::new(finality), PubSub).unwrap();
+        (module, sender)
+    }
+
+    fn finalize(sender: &GrandpaJustificationSender<Block>, number: BlockNumber) {
+        let commit = sp_consensus_grandpa::Commit::<Header> {
+            target_hash: H256::from_low_u64_be(number.into()),
+            target_number: number,
+            precommits: Vec::new(),
+        };
+        let justification = sp_consensus_grandpa::GrandpaJustification::<Header> {
+            round: 1,
+            commit,
+            votes_ancestries: Vec::new(),
+        };
+        sender
+            .notify(|| Ok::<_, ()>(GrandpaJustification::from(justification)))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_every_block_of_a_justification_in_order() {
+        let (module, sender) = subscriptions();
+        let mut subscription = module
+            .subscribe("eth_subscribe", [FINALIZED_HEADS], 16)
+            .await
+            .unwrap();
+
+        // The first justification starts the stream, the next one finalizes three blocks.
+        finalize(&sender, 2);
+        finalize(&sender, 5);
+        for expected in ["0x2", "0x3", "0x4", "0x5"] {
+            let (head, _) = subscription.next::<Value>().await.unwrap().unwrap();
+            assert_eq!(head["number"], json!(expected));
+        }
+
+        // Blocks finalized again are not sent twice.
+        finalize(&sender, 5);
+        finalize(&sender, 6);
+        let (head, _) = subscription.next::<Value>().await.unwrap().unwrap();
+        assert_eq!(head["number"], json!("0x6"));
+    }
+
+    #[tokio::test]
+    async fn other_kinds_are_served_by_frontier() {
+        let (module, _sender) = subscriptions();
+        let mut subscription = module
+            .subscribe("eth_subscribe", ["newHeads"], 16)
+            .await
+            .unwrap();
+        let (kind, _) = subscription.next::<String>().await.unwrap().unwrap();
+        assert_eq!(kind, "NewHeads");
+
+        let unknown = module
+            .subscribe("eth_subscribe", ["newSafeHeads"], 16)
+            .await;
+        assert!(unknown.is_err());
+    }
+
+    fn pin(mut params: Vec<Value>, schema: &[Param]) -> Vec<Value> {
+        let tags = finality_tags(&mut params, schema);
+        tags.into_iter().for_each(|tag| *tag = json!("0x2a"));
+        params
+    }
+
+    #[test]
+    fn pins_the_finality_tags() {
+        let balance = [Param::Address, Param::BlockOrHash];
+        let address = json!("0x5fbdb2315678afecb367f032d93f642f64180aa3");
+        assert_eq!(
+            pin(vec![address.clone(), json!("finalized")], &balance)[1],
+            json!("0x2a")
+        );
+        assert_eq!(
+            pin(vec![address.clone(), json!("safe")], &balance)[1],
+            json!("0x2a")
+        );
+        assert_eq!(
+            pin(vec![address.clone(), json!("latest")], &balance)[1],
+            json!("latest")
+        );
+        // Only block parameters are tags.
+        assert_eq!(pin(vec![json!("safe")], &[Param::Bytes])[0], json!("safe"));
+
+        let filter = json!({ "fromBlock": "safe", "toBlock": "finalized", "address": "safe" });
+        assert_eq!(
+            pin(vec![filter], &[Param::Filter]),
+            vec![json!({ "fromBlock": "0x2a", "toBlock": "0x2a", "address": "safe" })]
+        );
+    }
+
+    #[test]
+    fn leaves_requests_without_tags_alone() {
+        let mut params = vec![json!("0x1"), json!(true)];
+        assert!(finality_tags(&mut params, &[Param::Block, Param::Bool]).is_empty());
+        let mut params = vec![json!({ "blockHash": "0x00" })];
+        assert!(finality_tags(&mut params, &[Param::Filter]).is_empty());
+    }
+}
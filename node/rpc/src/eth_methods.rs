@@ -0,0 +1,158 @@
+//! The Ethereum RPC methods of the `ethereum/execution-apis` specification, and the plumbing the
+//! layers over Frontier's modules use to call them.
+//!
+//! [`METHODS`] gives the parameters of each method, so that a layer can find e.g. the block
+//! parameters of a call. [`forward`] calls a method of the wrapped module as a client would.
+
+use jsonrpsee::{
+    types::{ErrorObject, ErrorObjectOwned, Params},
+    RpcModule,
+};
+use serde_json::{json, Value};
+
+/// The request is not valid JSON-RPC or its parameters do not match the method.
+pub(crate) const INVALID_PARAMS: i32 = -32602;
+/// The implementation failed for a reason that is not known.
+pub(crate) const INTERNAL_ERROR: i32 = -32603;
+/// The call reverted, the data is the revert reason.
+pub(crate) const EXECUTION_REVERTED: i32 = 3;
+/// The state of the block is not available anymore.
+pub(crate) const RESOURCE_UNAVAILABLE: i32 = -32002;
+/// The transaction pool refused the transaction.
+pub(crate) const TRANSACTION_REJECTED: i32 = -32003;
+/// The node does not implement the method or option.
+pub(crate) const METHOD_NOT_SUPPORTED: i32 = -32004;
+/// The request exceeds a limit of the node.
+pub(crate) const LIMIT_EXCEEDED: i32 = -32005;
+
+/// Schema of a parameter, as named by the specification.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Param {
+    /// `uint`: `0x` followed by hex digits, without leading zeros.
+    Quantity,
+    /// `address`: 20 bytes.
+    Address,
+    /// `hash32`: 32 bytes.
+    Hash,
+    /// `bytes`: any number of bytes.
+    Bytes,
+    /// `bytesMax32` or `uint256`: a storage slot.
+    Slot,
+    /// An array of 32 byte storage slots, as in `eth_getProof`.
+    Slots,
+    /// `boolean`.
+    Bool,
+    /// `BlockNumberOrTag`.
+    Block,
+    /// `BlockNumberOrTagOrHash`, including the EIP-1898 object.
+    BlockOrHash,
+    /// `GenericTransaction`.
+    Transaction,
+    /// `Filter`.
+    Filter,
+    /// The reward percentiles of `eth_feeHistory`.
+    Percentiles,
+    /// Account overrides by address, as in `eth_call`.
+    StateOverrides,
+    /// `BlockOverrides`.
+    BlockOverrides,
+    /// The payload of `eth_simulateV1`.
+    Simulation,
+}
+
+use Param::*;
+
+/// Methods of the specification: name, parameters and number of required parameters.
+pub(crate) const METHODS: &[(&str, &[Param], usize)] = &[
+    ("eth_accounts", &[], 0),
+    ("eth_blockNumber", &[], 0),
+    (
+        "eth_call",
+        &[Transaction, BlockOrHash, StateOverrides, BlockOverrides],
+        1,
+    ),
+    ("eth_chainId", &[], 0),
+    ("eth_coinbase", &[], 0),
+    ("eth_createAccessList", &[Transaction, BlockOrHash], 1),
+    ("eth_estimateGas", &[Transaction, BlockOrHash], 1),
+    ("eth_feeHistory", &[Quantity, Block, Percentiles], 2),
+    ("eth_gasPrice", &[], 0),
+    ("eth_getBalance", &[Address, BlockOrHash], 2),
+    ("eth_getBlockByHash", &[Hash, Bool], 2),
+    ("eth_getBlockByNumber", &[Block, Bool], 2),
+    ("eth_getBlockReceipts", &[BlockOrHash], 1),
+    ("eth_getBlockTransactionCountByHash", &[Hash], 1),
+    ("eth_getBlockTransactionCountByNumber", &[Block], 1),
+    ("eth_getCode", &[Address, BlockOrHash], 2),
+    ("eth_getFilterChanges", &[Quantity], 1),
+    ("eth_getFilterLogs", &[Quantity], 1),
+    ("eth_getLogs", &[Filter], 1),
+    ("eth_getProof", &[Address, Slots, BlockOrHash], 3),
+    ("eth_getStorageAt", &[Address, Slot, BlockOrHash], 3),
+    (
+        "eth_getTransactionByBlockHashAndIndex",
+        &[Hash, Quantity],
+        2,
+    ),
+    (
+        "eth_getTransactionByBlockNumberAndIndex",
+        &[Block, Quantity],
+        2,
+    ),
+    ("eth_getTransactionByHash", &[Hash], 1),
+    ("eth_getTransactionCount", &[Address, BlockOrHash], 2),
+    ("eth_getTransactionReceipt", &[Hash], 1),
+    ("eth_getUncleByBlockHashAndIndex", &[Hash, Quantity], 2),
+    ("eth_getUncleByBlockNumberAndIndex", &[Block, Quantity], 2),
+    ("eth_getUncleCountByBlockHash", &[Hash], 1),
+    ("eth_getUncleCountByBlockNumber", &[Block], 1),
+    ("eth_maxPriorityFeePerGas", &[], 0),
+    ("eth_newBlockFilter", &[], 0),
+    ("eth_newFilter", &[Filter], 1),
+    ("eth_newPendingTransactionFilter", &[], 0),
+    ("eth_protocolVersion", &[], 0),
+    ("eth_sendRawTransaction", &[Bytes], 1),
+    ("eth_sendTransaction", &[Transaction], 1),
+    ("eth_sign", &[Address, Bytes], 2),
+    ("eth_signTransaction", &[Transaction], 1),
+    ("eth_simulateV1", &[Simulation, BlockOrHash], 1),
+    ("eth_syncing", &[], 0),
+    ("eth_uninstallFilter", &[Quantity], 1),
+    ("net_listening", &[], 0),
+    ("net_peerCount", &[], 0),
+    ("net_version", &[], 0),
+    ("web3_clientVersion", &[], 0),
+    ("web3_sha3", &[Bytes], 1),
+];
+
+/// The positional parameters of `request`, none if it has none.
+pub(crate) fn positional(request: &Params) -> Result<Vec<Value>, ErrorObjectOwned> {
+    Ok(request.parse::<Option<Vec<Value>>>()?.unwrap_or_default())
+}
+
+/// Call `method` of `inner` as a client would and return its result or error unchanged.
+pub(crate) async fn forward(
+    inner: &RpcModule<()>,
+    method: &str,
+    params: Vec<Value>,
+) -> Result<Value, ErrorObjectOwned> {
+    let internal = |e: serde_json::Error| {
+        ErrorObject::owned(INTERNAL_ERROR, format!("Invalid response: {e}"), None::<()>)
+    };
+    let request = json!({ "jsonrpc": "2.0", "id": 0, "method": method, "params": params });
+    let (response, _) = inner
+        .raw_json_request(&request.to_string(), 1)
+        .await
+        .map_err(internal)?;
+    let mut response: Value = serde_json::from_str(&response).map_err(internal)?;
+
+    match response.get_mut("error") {
+        None => Ok(response["result"].take()),
+        Some(error) => {
+            let code = error["code"].as_i64().unwrap_or(INTERNAL_ERROR.into()) as i32;
+            let message = error["message"].as_str().unwrap_or_default().to_string();
+            let data = error.get_mut("data").map(Value::take);
+            Err(ErrorObject::owned(code, message, data))
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! `eth_getProof`, answered with Substrate read proofs.
+//!
+//! The format of the response and its verifier are in the `evm-proof` crate.
+
+use std::sync::Arc;
+
+use common_runtime::opaque::Block;
+use evm_proof::{account_key, code_key, storage_key, EvmProof, StorageSlotProof};
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::BlockNumberOrHash;
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+};
+use sc_client_api::ProofProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::keccak_256, Bytes, H160, H256, U256};
+use sp_runtime::traits::Header as HeaderT;
+
+/// EIP-1186 proofs of EVM accounts.
+#[rpc(server)]
+pub trait EthProofApi {
+    /// Proof of the account `address` and of its `storage_keys` at `block`.
+    #[method(name = "eth_getProof")]
+    async fn proof(
+        &self,
+        address: H160,
+        storage_keys: Vec<H256>,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<EvmProof>;
+}
+
+/// Implementation of [`EthProofApiServer`].
+pub struct EthProof<C> {
+    client: Arc<C>,
+    frontier_backend: Arc<dyn fc_api::Backend<Block>>,
+}
+
+impl<C> EthProof<C> {
+    /// Create the RPC handler.
+    pub fn new(client: Arc<C>, frontier_backend: Arc<dyn fc_api::Backend<Block>>) -> Self {
+        Self {
+            client,
+            frontier_backend,
+        }
+    }
+}
+
+#[async_trait]
+impl<C> EthProofApiServer for EthProof<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + ProofProvider<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block>,
+{
+    async fn proof(
+        &self,
+        address: H160,
+        storage_keys: Vec<H256>,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<EvmProof> {
+        let id = frontier_backend_client::native_block_id::<Block, C>(
+            &*self.client,
+            &*self.frontier_backend,
+            Some(block),
+        )
+        .await?
+        .ok_or_else(|| internal_err("header not found"))?;
+        let hash = self
+            .client
+            .expect_block_hash_from_id(&id)
+            .map_err(internal_err)?;
+        let header = self.client.expect_header(hash).map_err(internal_err)?;
+
+        let read_proof = |keys: &[Vec<u8>]| -> RpcResult<Vec<Bytes>> {
+            let proof = self
+                .client
+                .read_proof(hash, &mut keys.iter().map(|key| &key[..]))
+                .map_err(internal_err)?;
+            Ok(proof.into_iter_nodes().map(Bytes).collect())
+        };
+
+        // The claimed values come from the runtime, as for `eth_getBalance` and friends, so that
+        // a verifier also checks that they match the state.
+        let api = self.client.runtime_api();
+        let account = api.account_basic(hash, address).map_err(internal_err)?;
+        let code = api.account_code_at(hash, address).map_err(internal_err)?;
+        let account_proof = read_proof(&[account_key(address), code_key(address)])?;
+
+        let storage_proof = storage_keys
+            .into_iter()
+            .map(|key| {
+                let index = U256::from_big_endian(key.as_bytes());
+                let value = api.storage_at(hash, address, index).map_err(internal_err)?;
+                Ok(StorageSlotProof {
+                    key,
+                    value: U256::from_big_endian(value.as_bytes()),
+                    proof: read_proof(&[storage_key(address, key)])?,
+                })
+            })
+            .collect::<RpcResult<_>>()?;
+
+        Ok(EvmProof {
+            address,
+            block_hash: hash,
+            state_root: *header.state_root(),
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: H256(keccak_256(&code)),
+            account_proof,
+            storage_proof,
+        })
+    }
+}
@@ -0,0 +1,192 @@
+//! A JSON-RPC server serving only the Ethereum methods.
+//!
+//! The node RPC server merges the Substrate and the Ethereum methods under a single `DenyUnsafe`.
+//! This server is started apart, on its own address and with its own CORS origins, with the
+//! module of [`create_eth_rpc`](crate::create_eth_rpc) cut down to a method allowlist, so that
+//! Ethereum wallets can be given an endpoint without the Substrate methods. Its calls are bounded
+//! by the budgets of [`BudgetLayer`].
+
+use std::{
+    error::Error,
+    net::{SocketAddr, TcpListener},
+};
+
+use http::{header, HeaderValue, Method};
+use jsonrpsee::{
+    server::{RpcServiceBuilder, Server, ServerHandle},
+    RpcModule,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::eth_budget::BudgetLayer;
+
+/// Methods served when no allowlist is given.
+pub const DEFAULT_ETH_METHODS: &[&str] = &["eth_*", "net_*", "web3_*"];
+
+/// Configuration of the Ethereum JSON-RPC server.
+#[derive(Clone, Debug)]
+pub struct EthServerConfig {
+    /// Address to listen on.
+    pub addr: SocketAddr,
+    /// Origins allowed to call the server from a browser, any origin if `None`.
+    pub cors: Option<Vec<String>>,
+    /// Maximum number of concurrent connections.
+    pub max_connections: u32,
+}
+
+/// Whether `method` is matched by `pattern`: either the method name or a prefix followed by `*`.
+fn matches(pattern: &str, method: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => method.starts_with(prefix),
+        None => pattern == method,
+    }
+}
+
+/// Remove from `module` the methods matched by none of `allowlist`.
+pub fn allow_methods<P: AsRef<str>>(mut module: RpcModule<()>, allowlist: &[P]) -> RpcModule<()> {
+    let denied: Vec<_> = module
+        .method_names()
+        .filter(|method| {
+            !allowlist
+                .iter()
+                .any(|pattern| matches(pattern.as_ref(), method))
+        })
+        .collect();
+    for method in denied {
+        module.remove_method(method);
+    }
+    module
+}
+
+fn cors_layer(origins: &Option<Vec<String>>) -> Result<CorsLayer, Box<dyn Error + Send + Sync>> {
+    let allow_origin = match origins {
+        None => AllowOrigin::any(),
+        Some(origins) => AllowOrigin::list(
+            origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+    };
+    Ok(CorsLayer::new()
+        .allow_methods([Method::POST])
+        .allow_origin(allow_origin)
+        .allow_headers([header::CONTENT_TYPE]))
+}
+
+/// Start serving `module` over HTTP and WebSocket as configured by `config`, within `budgets`.
+///
+/// The address is bound before returning, so that the node fails to start when it is taken. The
+/// server runs until the returned handle is stopped or dropped.
+pub fn start_eth_server(
+    config: EthServerConfig,
+    module: RpcModule<()>,
+    budgets: BudgetLayer,
+) -> Result<ServerHandle, Box<dyn Error + Send + Sync>> {
+    let middleware = tower::ServiceBuilder::new().layer(cors_layer(&config.cors)?);
+    let listener = TcpListener::bind(config.addr)?;
+    listener.set_nonblocking(true)?;
+    let server = Server::builder()
+        .max_connections(config.max_connections)
+        .set_http_middleware(middleware)
+        .set_rpc_middleware(RpcServiceBuilder::new().layer(budgets))
+        .set_id_provider(fc_rpc::EthereumSubIdProvider)
+        .build_from_tcp(listener)?;
+    log::info!(
+        "Ethereum JSON-RPC server started at {}",
+        server.local_addr()?
+    );
+    Ok(server.start(module))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::ErrorObjectOwned;
+
+    fn module() -> RpcModule<()> {
+        let mut module = RpcModule::new(());
+        let methods = [
+            "eth_call",
+            "eth_sign",
+            "net_version",
+            "system_health",
+            "dev_getBlockStats",
+        ];
+        for method in methods {
+            module
+                .register_method(method, |_, _| Ok::<_, ErrorObjectOwned>("ok"))
+                .unwrap();
+        }
+        module
+            .register_subscription(
+                "eth_subscribe",
+                "eth_subscription",
+                "eth_unsubscribe",
+                |_, _, _| async {},
+            )
+            .unwrap();
+        module
+    }
+
+    fn names(module: &RpcModule<()>) -> Vec<&'static str> {
+        let mut names: Vec<_> = module.method_names().collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn default_allowlist_keeps_ethereum_methods_only() {
+        let module = allow_methods(module(), DEFAULT_ETH_METHODS);
+        assert_eq!(
+            names(&module),
+            [
+                "eth_call",
+                "eth_sign",
+                "eth_subscribe",
+                "eth_unsubscribe",
+                "net_version"
+            ]
+        );
+    }
+
+    #[test]
+    fn allowlist_takes_exact_names_and_prefixes() {
+        let module = allow_methods(module(), &["eth_call", "net_*"]);
+        assert_eq!(names(&module), ["eth_call", "net_version"]);
+
+        let module = allow_methods(module, &["eth_"]);
+        assert!(names(&module).is_empty());
+    }
+
+    #[tokio::test]
+    async fn binds_before_serving() {
+        let config = |addr| EthServerConfig {
+            addr,
+            cors: None,
+            max_connections: 1,
+        };
+        let server = start_eth_server(
+            config("127.0.0.1:0".parse().unwrap()),
+            module(),
+            BudgetLayer::at((0, 0)),
+        )
+        .unwrap();
+        server.stop().unwrap();
+
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let busy = start_eth_server(
+            config(taken.local_addr().unwrap()),
+            module(),
+            BudgetLayer::at((0, 0)),
+        );
+        assert!(busy.is_err());
+    }
+
+    #[test]
+    fn cors_origins_must_be_header_values() {
+        assert!(cors_layer(&None).is_ok());
+        assert!(cors_layer(&Some(vec!["https://app.example".into()])).is_ok());
+        assert!(cors_layer(&Some(vec!["bad\norigin".into()])).is_err());
+    }
+}
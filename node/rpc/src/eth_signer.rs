@@ -0,0 +1,469 @@
+//! Ethereum accounts of the node keystore, for `eth_sendTransaction` and `eth_sign`.
+//!
+//! The accounts are the ECDSA keys of the keystore under [`ETH_KEY_TYPE`], inserted with
+//! `author_insertKey`, `key insert --scheme ecdsa --key-type eths` or the `personal_*` methods
+//! below. They are locked when the node starts: `personal_unlockAccount` unlocks one for a while,
+//! given the passphrase of the keystore (`--password`, empty without one), and only unlocked
+//! accounts sign. Both the signer and the `personal_*` methods are served with
+//! `--rpc-methods unsafe` only.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use ethereum::{
+    EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionSignature,
+    TransactionV2 as EthereumTransaction,
+};
+use fc_rpc::{internal_err, EthSigner};
+use fc_rpc_core::types::TransactionMessage;
+use fp_account::AccountId20;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::ErrorObjectOwned};
+use sc_rpc_api::DenyUnsafe;
+use sp_core::{
+    bytes::from_hex,
+    crypto::{KeyTypeId, Pair as _},
+    ecdsa,
+    hashing::{blake2_256, keccak_256},
+    Bytes, H160, H256,
+};
+use sp_keystore::KeystorePtr;
+
+/// Key type of the Ethereum accounts in the keystore.
+pub const ETH_KEY_TYPE: KeyTypeId = KeyTypeId(*b"eths");
+
+/// How long `personal_unlockAccount` unlocks an account for without a duration, as in geth.
+const DEFAULT_UNLOCK_DURATION: Duration = Duration::from_secs(300);
+
+/// Signer of the Ethereum accounts of the keystore.
+///
+/// Clones share the unlocked accounts, so that the signer given to `Eth` signs with the accounts
+/// unlocked by [`EthPersonal`].
+#[derive(Clone)]
+pub struct EthKeystoreSigner {
+    keystore: KeystorePtr,
+    passphrase: [u8; 32],
+    /// Unlocked accounts and when they lock again, `None` for never.
+    unlocked: Arc<Mutex<HashMap<H160, Option<Instant>>>>,
+}
+
+impl EthKeystoreSigner {
+    /// Sign with the accounts of `keystore`, unlocked with `password`, the password of the
+    /// keystore.
+    pub fn new(keystore: KeystorePtr, password: Option<&str>) -> Self {
+        Self {
+            keystore,
+            passphrase: blake2_256(password.unwrap_or_default().as_bytes()),
+            unlocked: Default::default(),
+        }
+    }
+
+    fn check_passphrase(&self, passphrase: &str) -> RpcResult<()> {
+        match blake2_256(passphrase.as_bytes()) == self.passphrase {
+            true => Ok(()),
+            false => Err(internal_err("invalid passphrase")),
+        }
+    }
+
+    /// The key of `address`, if unlocked.
+    fn unlocked_key(&self, address: &H160) -> RpcResult<ecdsa::Public> {
+        let key = self
+            .keystore
+            .ecdsa_public_keys(ETH_KEY_TYPE)
+            .into_iter()
+            .find(|key| address_of(key) == *address)
+            .ok_or_else(|| internal_err("unknown account"))?;
+
+        let mut unlocked = self.unlocked.lock().unwrap_or_else(PoisonError::into_inner);
+        match unlocked.get(address) {
+            Some(None) => Ok(key),
+            Some(Some(until)) if Instant::now() < *until => Ok(key),
+            Some(Some(_)) => {
+                unlocked.remove(address);
+                Err(internal_err("authentication needed: password or unlock"))
+            }
+            None => Err(internal_err("authentication needed: password or unlock")),
+        }
+    }
+
+    /// Signature of `hash` by `key`: `r`, `s` and the recovery id.
+    fn sign_hash(&self, key: &ecdsa::Public, hash: H256) -> RpcResult<(H256, H256, u8)> {
+        let signature = self
+            .keystore
+            .ecdsa_sign_prehashed(ETH_KEY_TYPE, key, hash.as_fixed_bytes())
+            .map_err(internal_err)?
+            .ok_or_else(|| internal_err("key not found in the keystore"))?;
+        let bytes: &[u8] = signature.as_ref();
+        Ok((
+            H256::from_slice(&bytes[..32]),
+            H256::from_slice(&bytes[32..64]),
+            bytes[64],
+        ))
+    }
+}
+
+/// Ethereum address of `key`.
+fn address_of(key: &ecdsa::Public) -> H160 {
+    AccountId20::from(*key).into()
+}
+
+impl EthSigner for EthKeystoreSigner {
+    fn accounts(&self) -> Vec<H160> {
+        self.keystore
+            .ecdsa_public_keys(ETH_KEY_TYPE)
+            .iter()
+            .map(address_of)
+            .collect()
+    }
+
+    fn sign(
+        &self,
+        message: TransactionMessage,
+        address: &H160,
+    ) -> Result<EthereumTransaction, ErrorObjectOwned> {
+        let key = self.unlocked_key(address)?;
+        Ok(match message {
+            TransactionMessage::Legacy(m) => {
+                let (r, s, recovery_id) = self.sign_hash(&key, m.hash())?;
+                let v = match m.chain_id {
+                    None => 27 + u64::from(recovery_id),
+                    Some(chain_id) => 2 * chain_id + 35 + u64::from(recovery_id),
+                };
+                EthereumTransaction::Legacy(LegacyTransaction {
+                    nonce: m.nonce,
+                    gas_price: m.gas_price,
+                    gas_limit: m.gas_limit,
+                    action: m.action,
+                    value: m.value,
+                    input: m.input,
+                    signature: TransactionSignature::new(v, r, s)
+                        .ok_or_else(|| internal_err("signer generated invalid signature"))?,
+                })
+            }
+            TransactionMessage::EIP2930(m) => {
+                let (r, s, recovery_id) = self.sign_hash(&key, m.hash())?;
+                EthereumTransaction::EIP2930(EIP2930Transaction {
+                    chain_id: m.chain_id,
+                    nonce: m.nonce,
+                    gas_price: m.gas_price,
+                    gas_limit: m.gas_limit,
+                    action: m.action,
+                    value: m.value,
+                    input: m.input,
+                    access_list: m.access_list,
+                    odd_y_parity: recovery_id != 0,
+                    r,
+                    s,
+                })
+            }
+            TransactionMessage::EIP1559(m) => {
+                let (r, s, recovery_id) = self.sign_hash(&key, m.hash())?;
+                EthereumTransaction::EIP1559(EIP1559Transaction {
+                    chain_id: m.chain_id,
+                    nonce: m.nonce,
+                    max_priority_fee_per_gas: m.max_priority_fee_per_gas,
+                    max_fee_per_gas: m.max_fee_per_gas,
+                    gas_limit: m.gas_limit,
+                    action: m.action,
+                    value: m.value,
+                    input: m.input,
+                    access_list: m.access_list,
+                    odd_y_parity: recovery_id != 0,
+                    r,
+                    s,
+                })
+            }
+        })
+    }
+}
+
+/// Management of the keystore accounts, and `eth_sign`.
+#[rpc(server)]
+pub trait EthPersonalApi {
+    /// Addresses of the accounts.
+    #[method(name = "personal_listAccounts")]
+    fn list_accounts(&self) -> RpcResult<Vec<H160>>;
+
+    /// Generate an account in the keystore.
+    #[method(name = "personal_newAccount")]
+    fn new_account(&self, passphrase: String) -> RpcResult<H160>;
+
+    /// Insert the account of the hex-encoded secret key `key` in the keystore.
+    #[method(name = "personal_importRawKey")]
+    fn import_raw_key(&self, key: String, passphrase: String) -> RpcResult<H160>;
+
+    /// Unlock `address` for `duration` seconds, 300 by default, or until locked again when 0.
+    #[method(name = "personal_unlockAccount")]
+    fn unlock_account(
+        &self,
+        address: H160,
+        passphrase: String,
+        duration: Option<u64>,
+    ) -> RpcResult<bool>;
+
+    /// Lock `address` again.
+    #[method(name = "personal_lockAccount")]
+    fn lock_account(&self, address: H160) -> RpcResult<bool>;
+
+    /// EIP-191 signature of `data` by `address`, given its passphrase.
+    #[method(name = "personal_sign")]
+    fn personal_sign(&self, data: Bytes, address: H160, passphrase: String) -> RpcResult<Bytes>;
+
+    /// EIP-191 signature of `data` by the unlocked account `address`.
+    #[method(name = "eth_sign")]
+    fn eth_sign(&self, address: H160, data: Bytes) -> RpcResult<Bytes>;
+}
+
+/// Implementation of [`EthPersonalApiServer`].
+pub struct EthPersonal {
+    signer: EthKeystoreSigner,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl EthPersonal {
+    /// Create the RPC handler of the accounts of `signer`.
+    pub fn new(signer: EthKeystoreSigner, deny_unsafe: DenyUnsafe) -> Self {
+        Self {
+            signer,
+            deny_unsafe,
+        }
+    }
+
+    /// EIP-191 signature of `data` by `key`, with `v` as 27 or 28.
+    fn sign_message(&self, key: &ecdsa::Public, data: &[u8]) -> RpcResult<Bytes> {
+        let mut message = format!("\x19Ethereum Signed Message:\n{}", data.len()).into_bytes();
+        message.extend_from_slice(data);
+        let (r, s, recovery_id) = self.signer.sign_hash(key, keccak_256(&message).into())?;
+        let mut signature = [r.as_bytes(), s.as_bytes()].concat();
+        signature.push(27 + recovery_id);
+        Ok(signature.into())
+    }
+}
+
+impl EthPersonalApiServer for EthPersonal {
+    fn list_accounts(&self) -> RpcResult<Vec<H160>> {
+        self.deny_unsafe.check_if_safe()?;
+        Ok(self.signer.accounts())
+    }
+
+    fn new_account(&self, passphrase: String) -> RpcResult<H160> {
+        self.deny_unsafe.check_if_safe()?;
+        self.signer.check_passphrase(&passphrase)?;
+        let keystore = &self.signer.keystore;
+        let key = keystore
+            .ecdsa_generate_new(ETH_KEY_TYPE, None)
+            .map_err(internal_err)?;
+        Ok(address_of(&key))
+    }
+
+    fn import_raw_key(&self, key: String, passphrase: String) -> RpcResult<H160> {
+        self.deny_unsafe.check_if_safe()?;
+        self.signer.check_passphrase(&passphrase)?;
+        let seed = from_hex(&key).map_err(|_| internal_err("invalid hex"))?;
+        let pair =
+            ecdsa::Pair::from_seed_slice(&seed).map_err(|_| internal_err("invalid secret key"))?;
+        let suri = sp_core::bytes::to_hex(&seed, false);
+        self.signer
+            .keystore
+            .insert(ETH_KEY_TYPE, &suri, pair.public().as_ref())
+            .map_err(|()| internal_err("failed to insert the key in the keystore"))?;
+        Ok(address_of(&pair.public()))
+    }
+
+    fn unlock_account(
+        &self,
+        address: H160,
+        passphrase: String,
+        duration: Option<u64>,
+    ) -> RpcResult<bool> {
+        self.deny_unsafe.check_if_safe()?;
+        self.signer.check_passphrase(&passphrase)?;
+        if !self.signer.accounts().contains(&address) {
+            return Err(internal_err("unknown account"));
+        }
+        let until = match duration.map(Duration::from_secs) {
+            Some(Duration::ZERO) => None,
+            duration => Some(Instant::now() + duration.unwrap_or(DEFAULT_UNLOCK_DURATION)),
+        };
+        let mut unlocked = self
+            .signer
+            .unlocked
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        unlocked.insert(address, until);
+        Ok(true)
+    }
+
+    fn lock_account(&self, address: H160) -> RpcResult<bool> {
+        self.deny_unsafe.check_if_safe()?;
+        let mut unlocked = self
+            .signer
+            .unlocked
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(unlocked.remove(&address).is_some())
+    }
+
+    fn personal_sign(&self, data: Bytes, address: H160, passphrase: String) -> RpcResult<Bytes> {
+        self.deny_unsafe.check_if_safe()?;
+        self.signer.check_passphrase(&passphrase)?;
+        let key = self
+            .signer
+            .keystore
+            .ecdsa_public_keys(ETH_KEY_TYPE)
+            .into_iter()
+            .find(|key| address_of(key) == address)
+            .ok_or_else(|| internal_err("unknown account"))?;
+        self.sign_message(&key, &data)
+    }
+
+    fn eth_sign(&self, address: H160, data: Bytes) -> RpcResult<Bytes> {
+        self.deny_unsafe.check_if_safe()?;
+        let key = self.signer.unlocked_key(&address)?;
+        self.sign_message(&key, &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum::{
+        EIP1559TransactionMessage, EnvelopedEncodable, LegacyTransactionMessage, TransactionAction,
+    };
+    use sp_core::U256;
+    use sp_keystore::testing::MemoryKeystore;
+
+    /// Secret key of the example of EIP-155.
+    const SECRET: &str = "0x4646464646464646464646464646464646464646464646464646464646464646";
+
+    fn signer() -> (EthKeystoreSigner, EthPersonal, H160) {
+        let signer = EthKeystoreSigner::new(Arc::new(MemoryKeystore::new()), Some("secret"));
+        let personal = EthPersonal::new(signer.clone(), DenyUnsafe::No);
+        let address = personal
+            .import_raw_key(SECRET.into(), "secret".into())
+            .unwrap();
+        (signer, personal, address)
+    }
+
+    fn recover(hash: H256, r: H256, s: H256, recovery_id: u8) -> H160 {
+        let mut signature = [0; 65];
+        signature[..32].copy_from_slice(r.as_bytes());
+        signature[32..64].copy_from_slice(s.as_bytes());
+        signature[64] = recovery_id;
+        let key = ecdsa::Signature::from_raw(signature).recover_prehashed(hash.as_fixed_bytes());
+        address_of(&key.expect("signature is valid"))
+    }
+
+    #[test]
+    fn signs_the_eip155_example() {
+        let (signer, personal, address) = signer();
+        let message = LegacyTransactionMessage {
+            nonce: 9.into(),
+            gas_price: U256::from(20) * U256::exp10(9),
+            gas_limit: 21_000.into(),
+            action: TransactionAction::Call(H160::repeat_byte(0x35)),
+            value: U256::exp10(18),
+            input: vec![],
+            chain_id: Some(1),
+        };
+        personal
+            .unlock_account(address, "secret".into(), None)
+            .unwrap();
+
+        let transaction = signer
+            .sign(TransactionMessage::Legacy(message), &address)
+            .unwrap();
+        assert_eq!(
+            sp_core::bytes::to_hex(&EnvelopedEncodable::encode(&transaction), false),
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400\
+             008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d899\
+             7f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+        );
+    }
+
+    #[test]
+    fn signs_eip1559_transactions() {
+        let (signer, personal, address) = signer();
+        let message = EIP1559TransactionMessage {
+            chain_id: 42,
+            nonce: 0.into(),
+            max_priority_fee_per_gas: 1_000.into(),
+            max_fee_per_gas: 2_000.into(),
+            gas_limit: 21_000.into(),
+            action: TransactionAction::Call(H160::repeat_byte(0x35)),
+            value: 1.into(),
+            input: vec![],
+            access_list: vec![],
+        };
+        personal
+            .unlock_account(address, "secret".into(), Some(0))
+            .unwrap();
+
+        let message = TransactionMessage::EIP1559(message);
+        let EthereumTransaction::EIP1559(transaction) = signer.sign(message, &address).unwrap()
+        else {
+            panic!("not an EIP-1559 transaction")
+        };
+        let hash = EIP1559TransactionMessage::from(transaction.clone()).hash();
+        let recovery_id = transaction.odd_y_parity as u8;
+        assert_eq!(
+            recover(hash, transaction.r, transaction.s, recovery_id),
+            address
+        );
+    }
+
+    #[test]
+    fn signs_with_unlocked_accounts_only() {
+        let (signer, personal, address) = signer();
+        let message = || {
+            TransactionMessage::Legacy(LegacyTransactionMessage {
+                nonce: 0.into(),
+                gas_price: 1.into(),
+                gas_limit: 21_000.into(),
+                action: TransactionAction::Create,
+                value: 0.into(),
+                input: vec![],
+                chain_id: Some(42),
+            })
+        };
+        assert_eq!(signer.accounts(), vec![address]);
+        assert!(signer.sign(message(), &address).is_err());
+        assert!(personal.eth_sign(address, vec![1].into()).is_err());
+
+        assert!(personal
+            .unlock_account(address, "wrong".into(), None)
+            .is_err());
+        assert!(personal
+            .unlock_account(H160::zero(), "secret".into(), None)
+            .is_err());
+        assert!(personal
+            .unlock_account(address, "secret".into(), None)
+            .unwrap());
+        assert!(signer.sign(message(), &address).is_ok());
+
+        let signature = personal
+            .eth_sign(address, b"hello".to_vec().into())
+            .unwrap();
+        let signed = personal.personal_sign(b"hello".to_vec().into(), address, "secret".into());
+        assert_eq!(signature, signed.unwrap());
+        let hash = keccak_256(b"\x19Ethereum Signed Message:\n5hello").into();
+        let (r, s) = (
+            H256::from_slice(&signature[..32]),
+            H256::from_slice(&signature[32..64]),
+        );
+        assert_eq!(recover(hash, r, s, signature[64] - 27), address);
+
+        assert!(personal.lock_account(address).unwrap());
+        assert!(signer.sign(message(), &address).is_err());
+    }
+
+    #[test]
+    fn is_unsafe() {
+        let signer = EthKeystoreSigner::new(Arc::new(MemoryKeystore::new()), None);
+        let personal = EthPersonal::new(signer, DenyUnsafe::Yes);
+        assert!(personal.list_accounts().is_err());
+        assert!(personal.new_account(String::new()).is_err());
+    }
+}
@@ -0,0 +1,778 @@
+//! `eth_simulateV1`, and the state and block overrides of `eth_call`.
+//!
+//! Both are executed by the runtime through `EthereumSimulationApi` of the `evm-simulation` crate,
+//! which writes the overrides and runs the calls in a storage transaction that is rolled back, on
+//! top of the state of the requested block.
+//!
+//! The state overrides follow Geth: `balance`, `nonce`, `code`, and either `state` or `stateDiff`.
+//! The block overrides supported are `number`, `time` and `baseFeePerGas`; the others are refused
+//! with `-32004`, as the chain has no use for them or derives them from its consensus.
+//!
+//! Simulated blocks are not sealed: they have no hash, no transaction list and, when numbers are
+//! skipped, the blocks in between are not filled.
+
+use std::{collections::BTreeMap, error::Error, sync::Arc};
+
+use common_runtime::opaque::Block;
+use evm_simulation::{
+    AccountOverride, BlockOverrides, CallOutcome, EthereumSimulationApi, SimulatedBlock,
+    SimulatedBlockResult, SimulatedCall,
+};
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::BlockNumberOrHash;
+use fp_evm::ExitReason;
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::{ErrorObject, ErrorObjectOwned},
+    RpcModule,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H160, H256, U256};
+
+use crate::eth_methods::{
+    forward, positional, EXECUTION_REVERTED, INVALID_PARAMS, LIMIT_EXCEEDED, METHOD_NOT_SUPPORTED,
+    TRANSACTION_REJECTED,
+};
+
+/// Most blocks in a simulation.
+const MAX_BLOCKS: usize = 256;
+/// Most calls in a simulation, over all its blocks.
+const MAX_CALLS: usize = 1000;
+/// A simulated call failed in the EVM for another reason than a revert.
+const VM_ERROR: i32 = -32015;
+
+/// A call, as the `GenericTransaction` of the specification.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+    /// Sender, the zero address if not given.
+    pub from: Option<H160>,
+    /// Callee, a contract creation if not given.
+    pub to: Option<H160>,
+    /// Gas limit, the most allowed if not given.
+    pub gas: Option<U256>,
+    /// Legacy gas price, used as the max fee per gas.
+    pub gas_price: Option<U256>,
+    /// EIP-1559 max fee per gas.
+    pub max_fee_per_gas: Option<U256>,
+    /// EIP-1559 max priority fee per gas.
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Value transferred.
+    pub value: Option<U256>,
+    /// Call data.
+    pub input: Option<Bytes>,
+    /// Call data, the older name of `input`.
+    pub data: Option<Bytes>,
+    /// Nonce, the sender's if not given.
+    pub nonce: Option<U256>,
+    /// EIP-2930 access list.
+    pub access_list: Option<Vec<AccessListItem>>,
+}
+
+/// An entry of an access list.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    /// Account accessed.
+    pub address: H160,
+    /// Storage slots of the account accessed.
+    pub storage_keys: Vec<H256>,
+}
+
+/// Overrides of an account.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateOverride {
+    /// Free balance.
+    pub balance: Option<U256>,
+    /// Nonce.
+    pub nonce: Option<U256>,
+    /// Contract code.
+    pub code: Option<Bytes>,
+    /// Storage replacing the whole storage of the account.
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// Storage slots to set.
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+    /// Not supported.
+    pub move_precompile_to_address: Option<H160>,
+}
+
+/// Overrides of accounts, by address.
+pub type StateOverrides = BTreeMap<H160, StateOverride>;
+
+/// Overrides of the block environment.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverride {
+    /// Block number.
+    pub number: Option<U256>,
+    /// Timestamp in seconds.
+    pub time: Option<U256>,
+    /// Base fee per gas.
+    pub base_fee_per_gas: Option<U256>,
+    /// Not supported.
+    pub gas_limit: Option<U256>,
+    /// Not supported.
+    #[serde(alias = "coinbase")]
+    pub fee_recipient: Option<H160>,
+    /// Not supported.
+    #[serde(alias = "random")]
+    pub prev_randao: Option<H256>,
+    /// Not supported.
+    pub blob_base_fee: Option<U256>,
+}
+
+/// A block of `eth_simulateV1`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateCalls {
+    /// Environment of the block.
+    pub block_overrides: Option<BlockOverride>,
+    /// Accounts changed before the calls.
+    pub state_overrides: Option<StateOverrides>,
+    /// Calls, executed in order.
+    #[serde(default)]
+    pub calls: Vec<CallRequest>,
+}
+
+/// The request of `eth_simulateV1`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationPayload {
+    /// Blocks, executed in order.
+    pub block_state_calls: Vec<BlockStateCalls>,
+    /// Not supported.
+    #[serde(default)]
+    pub trace_transfers: bool,
+    /// Check nonces, balances and fees as for transactions.
+    #[serde(default)]
+    pub validation: bool,
+    /// Not supported.
+    #[serde(default)]
+    pub return_full_transactions: bool,
+}
+
+/// A block simulated by `eth_simulateV1`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedBlockResponse {
+    /// Block number.
+    pub number: U256,
+    /// Timestamp in seconds.
+    pub timestamp: U256,
+    /// Gas limit of the block the simulation is based on.
+    pub gas_limit: U256,
+    /// Gas used by the calls.
+    pub gas_used: U256,
+    /// Base fee per gas.
+    pub base_fee_per_gas: U256,
+    /// Results of the calls.
+    pub calls: Vec<SimulatedCallResponse>,
+}
+
+/// A call simulated by `eth_simulateV1`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedCallResponse {
+    /// `1` for success, `0` for failure.
+    pub status: U256,
+    /// Returned data, or the revert data.
+    pub return_data: Bytes,
+    /// Gas used.
+    pub gas_used: U256,
+    /// Logs emitted.
+    pub logs: Vec<SimulatedLog>,
+    /// Address of the contract created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<H160>,
+    /// Why the call failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CallError>,
+}
+
+/// A log emitted by a simulated call.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedLog {
+    /// Emitting contract.
+    pub address: H160,
+    /// Topics.
+    pub topics: Vec<H256>,
+    /// Data.
+    pub data: Bytes,
+    /// Number of the simulated block.
+    pub block_number: U256,
+    /// Index of the call in the block.
+    pub transaction_index: U256,
+    /// Index of the log in the block.
+    pub log_index: U256,
+}
+
+/// The failure of a simulated call.
+#[derive(Clone, Debug, Serialize)]
+pub struct CallError {
+    /// `3` for a revert, `-32015` for other EVM errors.
+    pub code: i32,
+    /// Description, with the reason of a revert.
+    pub message: String,
+    /// Revert data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Bytes>,
+}
+
+/// Simulation of blocks of calls.
+#[rpc(server)]
+pub trait EthSimulateApi {
+    /// Execute `payload` on top of `block`, the latest by default.
+    #[method(name = "eth_simulateV1")]
+    async fn simulate_v1(
+        &self,
+        payload: SimulationPayload,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<Vec<SimulatedBlockResponse>>;
+}
+
+/// Implementation of [`EthSimulateApiServer`] and of the overrides of `eth_call`.
+pub struct EthSimulate<C> {
+    client: Arc<C>,
+    frontier_backend: Arc<dyn fc_api::Backend<Block>>,
+    execute_gas_limit_multiplier: u64,
+}
+
+impl<C> EthSimulate<C> {
+    /// Create the RPC handler. Calls may use up to `execute_gas_limit_multiplier` times the block
+    /// gas limit, as with `eth_call`.
+    pub fn new(
+        client: Arc<C>,
+        frontier_backend: Arc<dyn fc_api::Backend<Block>>,
+        execute_gas_limit_multiplier: u64,
+    ) -> Self {
+        Self {
+            client,
+            frontier_backend,
+            execute_gas_limit_multiplier,
+        }
+    }
+}
+
+impl<C> EthSimulate<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    /// Run `blocks` on top of `at`, each one following the previous. `in_place`, as for
+    /// `eth_call`, the blocks keep the number and time of `at` unless overridden.
+    async fn run(
+        &self,
+        at: Option<BlockNumberOrHash>,
+        blocks: Vec<BlockStateCalls>,
+        validation: bool,
+        in_place: bool,
+    ) -> RpcResult<(U256, Vec<SimulatedBlockResult>)> {
+        let id = frontier_backend_client::native_block_id::<Block, C>(
+            &*self.client,
+            &*self.frontier_backend,
+            at,
+        )
+        .await?
+        .ok_or_else(|| internal_err("header not found"))?;
+        let hash = self
+            .client
+            .expect_block_hash_from_id(&id)
+            .map_err(internal_err)?;
+
+        let api = self.client.runtime_api();
+        if !api
+            .has_api::<dyn EthereumSimulationApi<Block>>(hash)
+            .map_err(internal_err)?
+        {
+            return Err(not_supported(
+                "overrides are not supported by the runtime at this block",
+            ));
+        }
+        let parent = api
+            .current_block(hash)
+            .map_err(internal_err)?
+            .ok_or_else(|| internal_err("Ethereum block not found"))?;
+        let gas_limit = parent.header.gas_limit;
+        let max_gas = gas_limit.saturating_mul(self.execute_gas_limit_multiplier.into());
+
+        let mut number = parent.header.number.low_u64();
+        let mut time = parent.header.timestamp / 1000;
+        let mut simulated = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let mut overrides = block.block_overrides.unwrap_or_default().into_runtime()?;
+            if in_place {
+                overrides.number.get_or_insert(number);
+                overrides.time.get_or_insert(time);
+            } else {
+                match overrides.number {
+                    Some(next) if next <= number => {
+                        return Err(invalid(format!(
+                            "block number {next} is not above {number}"
+                        )))
+                    }
+                    Some(next) => number = next,
+                    None => number += 1,
+                }
+                if let Some(next) = overrides.time {
+                    if next <= time {
+                        return Err(invalid(format!("block time {next} is not above {time}")));
+                    }
+                    time = next;
+                }
+            }
+
+            let state_overrides = block
+                .state_overrides
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(address, account)| account.into_runtime(address))
+                .collect::<RpcResult<_>>()?;
+            let calls = block
+                .calls
+                .into_iter()
+                .map(|call| call.into_runtime(max_gas))
+                .collect::<RpcResult<_>>()?;
+            simulated.push(SimulatedBlock {
+                overrides,
+                state_overrides,
+                calls,
+            });
+        }
+
+        let results = api
+            .simulate(hash, simulated, validation)
+            .map_err(internal_err)?
+            .map_err(|e| internal_err(format!("runtime error: {e:?}")))?;
+        Ok((gas_limit, results))
+    }
+
+    /// `eth_call` with overrides, answered as Frontier answers a plain one.
+    async fn call(
+        &self,
+        request: CallRequest,
+        at: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverrides>,
+        block_overrides: Option<BlockOverride>,
+    ) -> RpcResult<Bytes> {
+        let block = BlockStateCalls {
+            block_overrides,
+            state_overrides,
+            calls: vec![request],
+        };
+        let (_, results) = self.run(at, vec![block], false, true).await?;
+
+        match results.into_iter().flat_map(|block| block.calls).next() {
+            Some(CallOutcome::Call(info)) => {
+                check_exit(&info.exit_reason, &info.value)?;
+                Ok(Bytes(info.value))
+            }
+            Some(CallOutcome::Deployed(info, code)) => {
+                check_exit(&info.exit_reason, &[])?;
+                Ok(Bytes(code))
+            }
+            Some(CallOutcome::Failed(e)) => Err(internal_err(format!("runtime error: {e:?}"))),
+            None => Err(internal_err("the runtime returned no result")),
+        }
+    }
+}
+
+#[async_trait]
+impl<C> EthSimulateApiServer for EthSimulate<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    async fn simulate_v1(
+        &self,
+        payload: SimulationPayload,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<Vec<SimulatedBlockResponse>> {
+        if payload.trace_transfers {
+            return Err(not_supported("traceTransfers is not supported"));
+        }
+        if payload.return_full_transactions {
+            return Err(not_supported("returnFullTransactions is not supported"));
+        }
+        let blocks = payload.block_state_calls;
+        let calls: usize = blocks.iter().map(|block| block.calls.len()).sum();
+        if blocks.len() > MAX_BLOCKS || calls > MAX_CALLS {
+            return Err(ErrorObject::owned(
+                LIMIT_EXCEEDED,
+                format!("too many blocks or calls, at most {MAX_BLOCKS} and {MAX_CALLS}"),
+                None::<()>,
+            ));
+        }
+
+        let (gas_limit, results) = self.run(block, blocks, payload.validation, false).await?;
+        results
+            .into_iter()
+            .map(|block| respond(block, gas_limit))
+            .collect()
+    }
+}
+
+/// Serve `eth_call` of `io` with state and block overrides: calls with overrides run through
+/// `simulate`, the others still reach Frontier.
+pub fn with_call_overrides<C>(
+    io: RpcModule<()>,
+    simulate: EthSimulate<C>,
+) -> Result<RpcModule<()>, Box<dyn Error + Send + Sync>>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C: Send + Sync + 'static,
+    C::Api: EthereumRuntimeRPCApi<Block> + EthereumSimulationApi<Block>,
+{
+    let inner = Arc::new(io.clone());
+    let simulate = Arc::new(simulate);
+    let mut module = io;
+    if module.remove_method("eth_call").is_none() {
+        return Ok(module);
+    }
+    module.register_async_method("eth_call", move |request, _| {
+        let (inner, simulate) = (inner.clone(), simulate.clone());
+        async move {
+            let params = positional(&request)?;
+            if params.iter().skip(2).all(Value::is_null) {
+                return forward(&inner, "eth_call", params).await;
+            }
+            let mut params = request.sequence();
+            let call = params.next()?;
+            let block = params.optional_next()?;
+            let state_overrides = params.optional_next()?;
+            let block_overrides = params.optional_next()?;
+            let result = simulate
+                .call(call, block, state_overrides, block_overrides)
+                .await?;
+            serde_json::to_value(result).map_err(internal_err)
+        }
+    })?;
+    Ok(module)
+}
+
+fn invalid(message: String) -> ErrorObjectOwned {
+    ErrorObject::owned(INVALID_PARAMS, message, None::<()>)
+}
+
+fn not_supported(message: &str) -> ErrorObjectOwned {
+    ErrorObject::owned(METHOD_NOT_SUPPORTED, message, None::<()>)
+}
+
+impl CallRequest {
+    pub(crate) fn into_runtime(self, max_gas: U256) -> RpcResult<SimulatedCall> {
+        let gas_limit = match self.gas {
+            Some(gas) if gas > max_gas => {
+                return Err(invalid(format!(
+                    "gas limit {gas} is above the maximum of {max_gas}"
+                )))
+            }
+            Some(gas) => gas,
+            None => max_gas,
+        };
+        let data = match (self.input, self.data) {
+            (Some(input), Some(data)) if input != data => {
+                return Err(invalid(
+                    "both \"data\" and \"input\" are set and not equal".into(),
+                ))
+            }
+            (Some(data), _) | (None, Some(data)) => data.0,
+            (None, None) => Vec::new(),
+        };
+        Ok(SimulatedCall {
+            from: self.from.unwrap_or_default(),
+            to: self.to,
+            data,
+            value: self.value.unwrap_or_default(),
+            gas_limit,
+            max_fee_per_gas: self.max_fee_per_gas.or(self.gas_price),
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            nonce: self.nonce,
+            access_list: self
+                .access_list
+                .unwrap_or_default()
+                .into_iter()
+                .map(|item| (item.address, item.storage_keys))
+                .collect(),
+        })
+    }
+}
+
+impl StateOverride {
+    fn into_runtime(self, address: H160) -> RpcResult<AccountOverride> {
+        if self.move_precompile_to_address.is_some() {
+            return Err(not_supported("movePrecompileToAddress is not supported"));
+        }
+        if self.state.is_some() && self.state_diff.is_some() {
+            return Err(invalid(format!(
+                "account {address:?} has both 'state' and 'stateDiff'"
+            )));
+        }
+        Ok(AccountOverride {
+            address,
+            balance: self.balance,
+            nonce: self.nonce,
+            code: self.code.map(|code| code.0),
+            state: self.state.map(|state| state.into_iter().collect()),
+            state_diff: self.state_diff.unwrap_or_default().into_iter().collect(),
+        })
+    }
+}
+
+impl BlockOverride {
+    fn into_runtime(self) -> RpcResult<BlockOverrides> {
+        let unsupported = [
+            ("gasLimit", self.gas_limit.is_some()),
+            ("feeRecipient", self.fee_recipient.is_some()),
+            ("prevRandao", self.prev_randao.is_some()),
+            ("blobBaseFee", self.blob_base_fee.is_some()),
+        ];
+        if let Some((name, _)) = unsupported.iter().find(|(_, set)| *set) {
+            return Err(not_supported(&format!(
+                "block override {name} is not supported"
+            )));
+        }
+        let to_u64 = |name: &str, value: Option<U256>| {
+            value
+                .map(|value| u64::try_from(value).map_err(|_| invalid(format!("{name} too large"))))
+                .transpose()
+        };
+        Ok(BlockOverrides {
+            number: to_u64("number", self.number)?,
+            time: to_u64("time", self.time)?,
+            base_fee_per_gas: self.base_fee_per_gas,
+        })
+    }
+}
+
+/// The error of `eth_call` for a call that did not succeed, as Frontier's.
+pub(crate) fn check_exit(reason: &ExitReason, data: &[u8]) -> RpcResult<()> {
+    match reason {
+        ExitReason::Succeed(_) => Ok(()),
+        ExitReason::Revert(_) => Err(ErrorObject::owned(
+            EXECUTION_REVERTED,
+            revert_message(data),
+            Some(Bytes(data.to_vec())),
+        )),
+        ExitReason::Error(e) => Err(internal_err(format!("evm error: {e:?}"))),
+        ExitReason::Fatal(e) => Err(internal_err(format!("evm fatal: {e:?}"))),
+    }
+}
+
+/// `execution reverted`, followed by the reason when `data` is an `Error(string)`.
+pub(crate) fn revert_message(data: &[u8]) -> String {
+    const SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    const MESSAGE_START: usize = 4 + 32 + 32;
+
+    let reason = match data.get(..MESSAGE_START) {
+        Some(head) if head[..4] == SELECTOR => {
+            let len = U256::from_big_endian(&head[36..]);
+            if len > U256::from(data.len() - MESSAGE_START) {
+                None
+            } else {
+                std::str::from_utf8(&data[MESSAGE_START..MESSAGE_START + len.as_usize()]).ok()
+            }
+        }
+        _ => None,
+    };
+    match reason {
+        Some(reason) => format!("execution reverted: {reason}"),
+        None => "execution reverted".into(),
+    }
+}
+
+/// The response of `eth_simulateV1` for `block`.
+fn respond(block: SimulatedBlockResult, gas_limit: U256) -> RpcResult<SimulatedBlockResponse> {
+    let mut calls = Vec::with_capacity(block.calls.len());
+    let mut gas_used = U256::zero();
+    let mut log_index = 0u64;
+
+    for (index, outcome) in block.calls.into_iter().enumerate() {
+        let (reason, value, used_gas, logs, created) = match outcome {
+            CallOutcome::Call(info) => (
+                info.exit_reason,
+                info.value,
+                info.used_gas.standard,
+                info.logs,
+                None,
+            ),
+            CallOutcome::Deployed(info, code) => (
+                info.exit_reason,
+                code,
+                info.used_gas.standard,
+                info.logs,
+                Some(info.value),
+            ),
+            CallOutcome::Failed(e) => {
+                return Err(ErrorObject::owned(
+                    TRANSACTION_REJECTED,
+                    format!("call {index} of block {}: {e:?}", block.number),
+                    None::<()>,
+                ))
+            }
+        };
+        gas_used = gas_used.saturating_add(used_gas);
+
+        let error = match &reason {
+            ExitReason::Succeed(_) => None,
+            ExitReason::Revert(_) => Some(CallError {
+                code: EXECUTION_REVERTED,
+                message: revert_message(&value),
+                data: Some(Bytes(value.clone())),
+            }),
+            ExitReason::Error(e) => Some(CallError {
+                code: VM_ERROR,
+                message: format!("{e:?}"),
+                data: None,
+            }),
+            ExitReason::Fatal(e) => Some(CallError {
+                code: VM_ERROR,
+                message: format!("{e:?}"),
+                data: None,
+            }),
+        };
+        let logs = logs
+            .into_iter()
+            .map(|log| {
+                log_index += 1;
+                SimulatedLog {
+                    address: log.address,
+                    topics: log.topics,
+                    data: Bytes(log.data),
+                    block_number: block.number.into(),
+                    transaction_index: U256::from(index as u64),
+                    log_index: (log_index - 1).into(),
+                }
+            })
+            .collect();
+
+        calls.push(SimulatedCallResponse {
+            status: U256::from(error.is_none() as u8),
+            contract_address: created.filter(|_| error.is_none()),
+            return_data: Bytes(value),
+            gas_used: used_gas,
+            logs,
+            error,
+        });
+    }
+
+    Ok(SimulatedBlockResponse {
+        number: block.number.into(),
+        timestamp: block.timestamp.into(),
+        gas_limit,
+        gas_used,
+        base_fee_per_gas: block.base_fee_per_gas,
+        calls,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decodes_revert_reasons() {
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend_from_slice(H256::from_low_u64_be(32).as_bytes());
+        data.extend_from_slice(H256::from_low_u64_be(4).as_bytes());
+        data.extend_from_slice(b"nope");
+        data.extend_from_slice(&[0; 28]);
+        assert_eq!(revert_message(&data), "execution reverted: nope");
+
+        // A length beyond the data, a custom error and no data at all.
+        data[4 + 32 + 31] = 0xff;
+        assert_eq!(revert_message(&data), "execution reverted");
+        assert_eq!(
+            revert_message(&[0xde, 0xad, 0xbe, 0xef]),
+            "execution reverted"
+        );
+        assert_eq!(revert_message(&[]), "execution reverted");
+    }
+
+    #[test]
+    fn converts_overrides_for_the_runtime() {
+        let address = H160::repeat_byte(1);
+        let overrides: StateOverrides = serde_json::from_value(json!({
+            "0x0101010101010101010101010101010101010101": {
+                "balance": "0x10",
+                "nonce": "0x2",
+                "code": "0x6000",
+                "stateDiff": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001":
+                        "0x000000000000000000000000000000000000000000000000000000000000002a"
+                }
+            }
+        }))
+        .unwrap();
+        let account = overrides[&address].clone().into_runtime(address).unwrap();
+        assert_eq!(account.balance, Some(U256::from(16)));
+        assert_eq!(account.nonce, Some(U256::from(2)));
+        assert_eq!(account.code, Some(vec![0x60, 0x00]));
+        assert_eq!(account.state, None);
+        assert_eq!(
+            account.state_diff,
+            vec![(H256::from_low_u64_be(1), H256::from_low_u64_be(42))]
+        );
+
+        let both = StateOverride {
+            state: Some(BTreeMap::new()),
+            state_diff: Some(BTreeMap::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            both.into_runtime(address).unwrap_err().code(),
+            INVALID_PARAMS
+        );
+
+        let block: BlockOverride =
+            serde_json::from_value(json!({ "number": "0x64", "time": "0x5" })).unwrap();
+        let block = block.into_runtime().unwrap();
+        assert_eq!(
+            (block.number, block.time, block.base_fee_per_gas),
+            (Some(100), Some(5), None)
+        );
+
+        let coinbase = json!({ "coinbase": "0x0101010101010101010101010101010101010101" });
+        let block: BlockOverride = serde_json::from_value(coinbase).unwrap();
+        assert_eq!(
+            block.into_runtime().unwrap_err().code(),
+            METHOD_NOT_SUPPORTED
+        );
+    }
+
+    #[test]
+    fn calls_default_to_the_maximum_gas() {
+        let max_gas = U256::from(45_000_000);
+        let call = json!({ "to": "0x0101010101010101010101010101010101010101", "input": "0x01" });
+        let call: CallRequest = serde_json::from_value(call).unwrap();
+        let call = call.into_runtime(max_gas).unwrap();
+        assert_eq!((call.gas_limit, call.data), (max_gas, vec![1]));
+
+        let too_much = CallRequest {
+            gas: Some(max_gas + 1),
+            ..Default::default()
+        };
+        assert_eq!(
+            too_much.into_runtime(max_gas).unwrap_err().code(),
+            INVALID_PARAMS
+        );
+
+        let conflicting = CallRequest {
+            input: Some(Bytes(vec![1])),
+            data: Some(Bytes(vec![2])),
+            ..Default::default()
+        };
+        assert_eq!(
+            conflicting.into_runtime(max_gas).unwrap_err().code(),
+            INVALID_PARAMS
+        );
+    }
+}
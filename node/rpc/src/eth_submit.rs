@@ -0,0 +1,171 @@
+//! Readable errors for the transactions the SCS pallets reject.
+//!
+//! Frontier reports a transaction refused by the pool as an internal error with the debug output
+//! of the pool error, such as `submit transaction to pool failed: Pool(InvalidTransaction(
+//! Custom(141)))`. The custom codes of the SCS pallets mean nothing to a wallet, so the methods
+//! submitting transactions turn them into a `-32003` error saying why the transaction was
+//! rejected. The message is compared with the debug output of the typed pool error, so it follows
+//! the formatting of the pool error types.
+
+use std::{error::Error, sync::Arc};
+
+use jsonrpsee::{
+    types::{ErrorObject, ErrorObjectOwned},
+    RpcModule,
+};
+use pallet_evm_circuit_breaker::FROZEN_SOURCE_ERROR_CODE;
+use pallet_evm_deployment_control::DEPLOYER_NOT_ALLOWED_ERROR_CODE;
+use sp_runtime::transaction_validity::InvalidTransaction;
+
+use crate::eth_methods::{forward, positional, TRANSACTION_REJECTED};
+
+/// Methods that submit a transaction to the pool.
+const SUBMIT_METHODS: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Custom codes of the SCS pallets that are reported readably.
+const CUSTOM_CODES: [u8; 2] = [FROZEN_SOURCE_ERROR_CODE, DEPLOYER_NOT_ALLOWED_ERROR_CODE];
+
+/// Why a transaction was rejected with `InvalidTransaction::Custom(code)`, for the codes of the
+/// SCS pallets.
+fn reason(code: u8) -> Option<&'static str> {
+    match code {
+        FROZEN_SOURCE_ERROR_CODE => Some("the sender is frozen by the EVM circuit breaker"),
+        DEPLOYER_NOT_ALLOWED_ERROR_CODE => Some(
+            "contract deployment is permissioned: the sender is not an allowed deployer and the \
+             init code is not approved",
+        ),
+        _ => None,
+    }
+}
+
+/// The message of the error Frontier returns when the pool rejects a transaction with
+/// `InvalidTransaction::Custom(code)`.
+fn pool_rejection(code: u8) -> String {
+    let error = sc_transaction_pool::error::Error::Pool(
+        sc_transaction_pool_api::error::Error::InvalidTransaction(InvalidTransaction::Custom(code)),
+    );
+    format!("submit transaction to pool failed: {error:?}")
+}
+
+/// The custom code of the SCS pallet that rejected the transaction, if `message` is such a
+/// rejection.
+fn custom_code(message: &str) -> Option<u8> {
+    CUSTOM_CODES
+        .into_iter()
+        .find(|&code| message == pool_rejection(code))
+}
+
+/// `error` with a readable message if it is a rejection by an SCS pallet, unchanged otherwise.
+fn readable(error: ErrorObjectOwned) -> ErrorObjectOwned {
+    match custom_code(error.message()).and_then(reason) {
+        Some(reason) => ErrorObject::owned(
+            TRANSACTION_REJECTED,
+            format!("transaction rejected: {reason}"),
+            None::<()>,
+        ),
+        None => error,
+    }
+}
+
+/// Report the rejections of the SCS pallets by the methods of `io` submitting transactions.
+pub fn with_readable_rejections(
+    io: RpcModule<()>,
+) -> Result<RpcModule<()>, Box<dyn Error + Send + Sync>> {
+    let inner = Arc::new(io.clone());
+    let mut module = io;
+    for &method in SUBMIT_METHODS {
+        if module.remove_method(method).is_none() {
+            continue;
+        }
+        let inner = inner.clone();
+        module.register_async_method(method, move |request, _| {
+            let inner = inner.clone();
+            async move {
+                let params = positional(&request)?;
+                forward(&inner, method, params).await.map_err(readable)
+            }
+        })?;
+    }
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth_methods::INTERNAL_ERROR;
+    use serde_json::{json, Value};
+
+    /// A module whose `eth_sendRawTransaction` fails with `message`.
+    fn pool(message: String) -> RpcModule<()> {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("eth_sendRawTransaction", move |request, _| {
+                match request.parse::<Vec<String>>()?.as_slice() {
+                    [raw] if raw == "0x01" => Ok(json!("0x1234")),
+                    _ => Err(ErrorObject::owned(
+                        INTERNAL_ERROR,
+                        message.clone(),
+                        None::<()>,
+                    )),
+                }
+            })
+            .unwrap();
+        with_readable_rejections(module).unwrap()
+    }
+
+    async fn send(module: &RpcModule<()>, raw: &str) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendRawTransaction",
+            "params": [raw],
+        });
+        let (response, _) = module
+            .raw_json_request(&request.to_string(), 1)
+            .await
+            .unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn finds_the_custom_code() {
+        let message = "submit transaction to pool failed: \
+            Pool(InvalidTransaction(Custom(141)))";
+        assert_eq!(pool_rejection(141), message);
+        assert_eq!(custom_code(message), Some(141));
+        assert_eq!(custom_code(&pool_rejection(140)), Some(140));
+        assert_eq!(custom_code(&pool_rejection(7)), None);
+        assert_eq!(
+            custom_code("submit transaction to pool failed: Pool(InvalidTransaction(Stale))"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn rejections_of_the_scs_pallets_are_readable() {
+        for (code, expected) in [
+            (FROZEN_SOURCE_ERROR_CODE, "circuit breaker"),
+            (
+                DEPLOYER_NOT_ALLOWED_ERROR_CODE,
+                "deployment is permissioned",
+            ),
+        ] {
+            let response = send(&pool(pool_rejection(code)), "0x02").await;
+            assert_eq!(response["error"]["code"], TRANSACTION_REJECTED);
+            let message = response["error"]["message"].as_str().unwrap();
+            assert!(message.starts_with("transaction rejected: "), "{message}");
+            assert!(message.contains(expected), "{message}");
+        }
+    }
+
+    #[tokio::test]
+    async fn other_responses_are_unchanged() {
+        let message = pool_rejection(7);
+        let response = send(&pool(message.clone()), "0x02").await;
+        assert_eq!(response["error"]["code"], INTERNAL_ERROR);
+        assert_eq!(response["error"]["message"], message);
+
+        let response = send(&pool(message), "0x01").await;
+        assert_eq!(response["result"], "0x1234");
+    }
+}
@@ -32,7 +32,27 @@
 #![warn(unused_crate_dependencies)]
 
 mod eth;
+mod eth_budget;
+mod eth_estimate;
+mod eth_finality;
+mod eth_methods;
+mod eth_proof;
+mod eth_server;
+mod eth_signer;
+mod eth_simulate;
+mod eth_submit;
+mod statement;
 pub use eth::*;
+pub use eth_budget::{Budget, BudgetLayer, RpcBudgets, DEFAULT_BUDGETS};
+pub use eth_estimate::{EthAccessListApiServer, EthEstimate, ScsEstimateGasAdapter};
+pub use eth_finality::EthFinality;
+pub use eth_proof::{EthProof, EthProofApiServer};
+pub use eth_server::{allow_methods, start_eth_server, EthServerConfig, DEFAULT_ETH_METHODS};
+pub use eth_signer::{EthKeystoreSigner, EthPersonal, EthPersonalApiServer, ETH_KEY_TYPE};
+pub use eth_simulate::{EthSimulate, EthSimulateApiServer};
+pub use statement::{
+    NotifyingStatementStore, StatementSubscription, StatementSubscriptionApiServer,
+};
 use sp_inherents::CreateInherentDataProviders;
 use std::sync::Arc;
 // use sc_consensus_grandpa_rpc::finality::RpcFinalityProofProvider;
@@ -123,8 +143,8 @@ pub struct FullDeps<C, P, SC, B, AuthorityId: AuthorityIdBound, A: ChainApi, CT,
     pub grandpa: GrandpaDeps<B>,
     /// BEEFY specific dependencies.
     pub beefy: BeefyDeps<AuthorityId>,
-    /// Shared statement store reference.
-    pub statement_store: Arc<dyn sp_statement_store::StatementStore>,
+    /// Shared statement store reference, telling the subscriptions about new statements.
+    pub statement_store: Arc<NotifyingStatementStore>,
     /// The backend used by the node.
     pub backend: Arc<B>,
     /// Mixnet API.
@@ -140,11 +160,59 @@ where
     C: StorageProvider<B, BE> + Sync + Send + 'static,
     BE: sc_client_api::Backend<B> + 'static,
 {
-    type EstimateGasAdapter = ();
+    type EstimateGasAdapter = ScsEstimateGasAdapter;
     type RuntimeStorageOverride =
         fc_rpc::frontier_backend_client::SystemAccountId20StorageOverride<B, C, BE>;
 }
 
+/// Instantiate the Ethereum RPC extensions alone, for the Ethereum JSON-RPC server.
+///
+/// Unsafe methods are denied: the keystore signer and the `personal_*` methods are left out.
+pub fn create_eth_rpc<C, P, B, A, CT, CIDP>(
+    deps: EthDeps<C, P, A, CT, CIDP>,
+    subscription_task_executor: SubscriptionTaskExecutor,
+    pubsub_notification_sinks: Arc<
+        fc_mapping_sync::EthereumBlockNotificationSinks<
+            fc_mapping_sync::EthereumBlockNotification<Block>,
+        >,
+    >,
+    pending_consenus_data_provider: Box<dyn ConsensusDataProvider<Block>>,
+    justification_stream: GrandpaJustificationStream<Block>,
+) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
+where
+    C: ProvideRuntimeApi<Block>
+        + CallApiAt<Block>
+        + HeaderBackend<Block>
+        + HeaderMetadata<Block, Error = BlockChainError>
+        + BlockchainEvents<Block>
+        + AuxStore
+        + UsageProvider<Block>
+        + StorageProvider<Block, B>
+        + sc_client_api::ProofProvider<Block>
+        + Sync
+        + Send
+        + 'static,
+    C::Api: BlockBuilder<Block>
+        + fp_rpc::ConvertTransactionRuntimeApi<Block>
+        + fp_rpc::EthereumRuntimeRPCApi<Block>
+        + evm_simulation::EthereumSimulationApi<Block>,
+    P: TransactionPool<Block = Block> + 'static,
+    B: sc_client_api::Backend<Block> + 'static,
+    A: ChainApi<Block = Block> + 'static,
+    CIDP: CreateInherentDataProviders<Block, ()> + Send + 'static,
+    CT: fp_rpc::ConvertTransaction<<Block as BlockT>::Extrinsic> + Send + Sync + 'static,
+{
+    create_eth::<_, _, _, _, _, _, DefaultEthConfig<C, B>>(
+        RpcModule::new(()),
+        deps,
+        subscription_task_executor,
+        pubsub_notification_sinks,
+        pending_consenus_data_provider,
+        justification_stream,
+        DenyUnsafe::Yes,
+    )
+}
+
 /// Instantiate all Full RPC extensions.
 pub fn create_full<C, P, SC, B, AuthorityId, A, CT, CIDP>(
     deps: FullDeps<C, P, SC, B, AuthorityId, A, CT, CIDP>,
@@ -177,7 +245,9 @@ where
     C::Api: sp_api::ApiExt<Block>,
     C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
     C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
+    C::Api: evm_simulation::EthereumSimulationApi<Block>,
     C: BlockchainEvents<Block> + UsageProvider<Block> + StorageProvider<Block, B>,
+    C: sc_client_api::ProofProvider<Block>,
     P: TransactionPool<Block = Block> + 'static,
     SC: SelectChain<Block> + 'static,
     B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -270,7 +340,7 @@ where
             subscription_executor,
             shared_authority_set.clone(),
             shared_voter_state,
-            justification_stream,
+            justification_stream.clone(),
             finality_provider,
         )
         .into_rpc(),
@@ -288,9 +358,17 @@ where
 
     io.merge(StateMigration::new(client.clone(), backend, deny_unsafe).into_rpc())?;
     io.merge(Dev::new(client, deny_unsafe).into_rpc())?;
-    let statement_store =
-        sc_rpc::statement::StatementStore::new(statement_store, deny_unsafe).into_rpc();
-    io.merge(statement_store)?;
+    io.merge(
+        sc_rpc::statement::StatementStore::new(statement_store.clone(), deny_unsafe).into_rpc(),
+    )?;
+    io.merge(
+        StatementSubscription::new(
+            statement_store,
+            subscription_task_executor.clone(),
+            deny_unsafe,
+        )
+        .into_rpc(),
+    )?;
 
     // if let Some(mixnet_api) = mixnet_api {
     // 	let mixnet = sc_rpc::mixnet::Mixnet::new(mixnet_api).into_rpc();
@@ -313,6 +391,8 @@ where
         subscription_task_executor,
         pubsub_notification_sinks,
         pending_consenus_data_provider,
+        justification_stream,
+        deny_unsafe,
     )?;
 
     Ok(io)
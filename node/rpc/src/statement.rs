@@ -0,0 +1,265 @@
+//! Subscriptions to the statement store.
+//!
+//! The statement RPC of `sc-rpc` only lets clients poll the store. [`NotifyingStatementStore`]
+//! wraps the store given to the network, the RPC and the offchain workers, and tells its
+//! subscribers about every statement it accepts, from a peer or a local client alike.
+//! `statement_subscribeStatement` streams those whose topics include all of the requested ones,
+//! SCALE encoded as `statement_submit` takes them, so that a dApp can follow an order book without
+//! polling `statement_broadcasts`. Like the statement RPC of `sc-rpc`, which it complements, it is
+//! an unsafe method: a public node should not relay every statement to anyone who asks.
+
+use std::sync::Arc;
+
+use codec::Encode;
+use futures::{stream, FutureExt, StreamExt};
+use jsonrpsee::{proc_macros::rpc, PendingSubscriptionSink};
+use sc_rpc::{utils::pipe_from_stream, SubscriptionTaskExecutor};
+use sc_rpc_api::DenyUnsafe;
+use sp_core::Bytes;
+use sp_statement_store::{
+    Hash, Result, Statement, StatementSource, StatementStore, SubmitResult, Topic, MAX_TOPICS,
+};
+use tokio::sync::broadcast;
+
+/// Statements queued for a subscriber before it misses some.
+const QUEUE_SIZE: usize = 1_024;
+
+/// A statement store telling its subscribers about the statements it accepts.
+pub struct NotifyingStatementStore {
+    inner: Arc<dyn StatementStore>,
+    accepted: broadcast::Sender<Statement>,
+}
+
+impl NotifyingStatementStore {
+    /// Wrap `inner`.
+    pub fn new(inner: Arc<dyn StatementStore>) -> Self {
+        Self {
+            inner,
+            accepted: broadcast::channel(QUEUE_SIZE).0,
+        }
+    }
+
+    /// Statements accepted from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Statement> {
+        self.accepted.subscribe()
+    }
+}
+
+impl StatementStore for NotifyingStatementStore {
+    fn statements(&self) -> Result<Vec<(Hash, Statement)>> {
+        self.inner.statements()
+    }
+
+    fn statement(&self, hash: &Hash) -> Result<Option<Statement>> {
+        self.inner.statement(hash)
+    }
+
+    fn broadcasts(&self, match_all_topics: &[Topic]) -> Result<Vec<Vec<u8>>> {
+        self.inner.broadcasts(match_all_topics)
+    }
+
+    fn posted(&self, match_all_topics: &[Topic], dest: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+        self.inner.posted(match_all_topics, dest)
+    }
+
+    fn posted_clear(&self, match_all_topics: &[Topic], dest: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+        self.inner.posted_clear(match_all_topics, dest)
+    }
+
+    fn submit(&self, statement: Statement, source: StatementSource) -> SubmitResult {
+        if self.accepted.receiver_count() == 0 {
+            return self.inner.submit(statement, source);
+        }
+        let result = self.inner.submit(statement.clone(), source);
+        if let SubmitResult::New(_) = result {
+            // Fails only without subscribers.
+            let _ = self.accepted.send(statement);
+        }
+        result
+    }
+
+    fn remove(&self, hash: &Hash) -> Result<()> {
+        self.inner.remove(hash)
+    }
+}
+
+/// Whether the topics of `statement` include all of `topics`.
+fn has_topics(statement: &Statement, topics: &[Topic]) -> bool {
+    topics
+        .iter()
+        .all(|topic| (0..MAX_TOPICS).any(|index| statement.topic(index) == Some(*topic)))
+}
+
+/// Subscriptions to the statements of the store.
+#[rpc(server)]
+pub trait StatementSubscriptionApi {
+    /// Statements accepted from now on whose topics include all of `match_all_topics`, SCALE
+    /// encoded. Statements are sent once, in the order the store accepts them.
+    #[subscription(
+        name = "statement_subscribeStatement" => "statement_statement",
+        unsubscribe = "statement_unsubscribeStatement",
+        item = Bytes
+    )]
+    fn subscribe_statement(&self, match_all_topics: Vec<Topic>);
+}
+
+/// Implementation of [`StatementSubscriptionApiServer`].
+pub struct StatementSubscription {
+    store: Arc<NotifyingStatementStore>,
+    executor: SubscriptionTaskExecutor,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl StatementSubscription {
+    /// Serve the subscriptions to `store`, driven by `executor`.
+    pub fn new(
+        store: Arc<NotifyingStatementStore>,
+        executor: SubscriptionTaskExecutor,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
+        Self {
+            store,
+            executor,
+            deny_unsafe,
+        }
+    }
+}
+
+impl StatementSubscriptionApiServer for StatementSubscription {
+    fn subscribe_statement(&self, pending: PendingSubscriptionSink, match_all_topics: Vec<Topic>) {
+        if let Err(e) = self.deny_unsafe.check_if_safe() {
+            let reject = async move { pending.reject(e).await };
+            self.executor
+                .spawn("statement-subscription", Some("rpc"), reject.boxed());
+            return;
+        }
+        let accepted = stream::unfold(self.store.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(statement) => return Some((statement, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        log::warn!("Statement subscriber too slow, missed {missed} statements");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let statements = accepted
+            .filter(move |statement| {
+                futures::future::ready(has_topics(statement, &match_all_topics))
+            })
+            .map(|statement| Bytes(statement.encode()))
+            .boxed();
+        self.executor.spawn(
+            "statement-subscription",
+            Some("rpc"),
+            pipe_from_stream(pending, statements).boxed(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Decode;
+    use jsonrpsee::{rpc_params, RpcModule};
+    use sp_core::{ecdsa::Pair, Pair as _};
+    use sp_statement_store::NetworkPriority;
+    use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+    /// Accepts every statement once.
+    #[derive(Default)]
+    struct MockStore(Mutex<HashSet<Hash>>);
+
+    impl StatementStore for MockStore {
+        fn statements(&self) -> Result<Vec<(Hash, Statement)>> {
+            Ok(vec![])
+        }
+
+        fn statement(&self, _: &Hash) -> Result<Option<Statement>> {
+            Ok(None)
+        }
+
+        fn broadcasts(&self, _: &[Topic]) -> Result<Vec<Vec<u8>>> {
+            Ok(vec![])
+        }
+
+        fn posted(&self, _: &[Topic], _: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+            Ok(vec![])
+        }
+
+        fn posted_clear(&self, _: &[Topic], _: [u8; 32]) -> Result<Vec<Vec<u8>>> {
+            Ok(vec![])
+        }
+
+        fn submit(&self, statement: Statement, _: StatementSource) -> SubmitResult {
+            match self.0.lock().unwrap().insert(statement.hash()) {
+                true => SubmitResult::New(NetworkPriority::High),
+                false => SubmitResult::Known,
+            }
+        }
+
+        fn remove(&self, _: &Hash) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn statement(topics: &[Topic], data: &[u8]) -> Statement {
+        let mut statement = Statement::new();
+        for (index, topic) in topics.iter().enumerate() {
+            statement.set_topic(index, *topic);
+        }
+        statement.set_plain_data(data.to_vec());
+        statement_validation::sign(&mut statement, &Pair::from_seed(&[7; 32]));
+        statement
+    }
+
+    #[test]
+    fn matches_all_topics() {
+        let order = statement(&[[1; 32], [2; 32]], b"order");
+        assert!(has_topics(&order, &[]));
+        assert!(has_topics(&order, &[[2; 32]]));
+        assert!(has_topics(&order, &[[2; 32], [1; 32]]));
+        assert!(!has_topics(&order, &[[1; 32], [3; 32]]));
+    }
+
+    #[tokio::test]
+    async fn streams_new_statements_with_the_topics() {
+        let store = Arc::new(NotifyingStatementStore::new(Arc::new(MockStore::default())));
+        let executor = Arc::new(sp_core::testing::TaskExecutor::new());
+        let mut module = RpcModule::new(());
+        let subscription = StatementSubscription::new(store.clone(), executor, DenyUnsafe::No);
+        module.merge(subscription.into_rpc()).unwrap();
+        let mut subscription = module
+            .subscribe_unbounded("statement_subscribeStatement", rpc_params![vec![[1u8; 32]]])
+            .await
+            .unwrap();
+
+        let book = statement(&[[1; 32], [2; 32]], b"buy");
+        let other = statement(&[[3; 32]], b"sell");
+        let later = statement(&[[1; 32]], b"cancel");
+        let submit =
+            |statement: &Statement| store.submit(statement.clone(), StatementSource::Local);
+        assert!(matches!(submit(&other), SubmitResult::New(_)));
+        assert!(matches!(submit(&book), SubmitResult::New(_)));
+        assert!(matches!(submit(&book), SubmitResult::Known));
+        assert!(matches!(submit(&later), SubmitResult::New(_)));
+
+        for expected in [book, later] {
+            let next = tokio::time::timeout(Duration::from_secs(5), subscription.next::<Bytes>());
+            let (item, _) = next.await.unwrap().unwrap().unwrap();
+            assert_eq!(Statement::decode(&mut &item[..]).unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_unsafe() {
+        let store = Arc::new(NotifyingStatementStore::new(Arc::new(MockStore::default())));
+        let executor = Arc::new(sp_core::testing::TaskExecutor::new());
+        let subscription = StatementSubscription::new(store, executor, DenyUnsafe::Yes);
+        let module = subscription.into_rpc();
+        let subscribe = module
+            .subscribe_unbounded("statement_subscribeStatement", rpc_params![vec![[1u8; 32]]]);
+        assert!(subscribe.await.is_err());
+    }
+}
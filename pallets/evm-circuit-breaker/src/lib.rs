@@ -0,0 +1,162 @@
+//! # EVM circuit breaker
+//!
+//! Emergency brake for individual EVM accounts. Governance can freeze an address so that:
+//!
+//! - any call into its code (`CALL`, `STATICCALL`, `DELEGATECALL`, `CALLCODE`, top level or nested)
+//!   reverts with the reason [`FROZEN_REVERT_REASON`], and
+//! - transactions signed by it are rejected from the pool and by the runner.
+//!
+//! The rest of the EVM keeps working, unlike pausing `Ethereum::transact` through
+//! `pallet_tx_pause`. The calls of this pallet are meant to stay dispatchable while safe mode is
+//! active.
+//!
+//! The runtime wires the pallet in three places:
+//!
+//! - [`CircuitBreakerPrecompiles`] wraps `pallet_evm::Config::PrecompilesType`,
+//! - [`CircuitBreakerRunner`] wraps `pallet_evm::Config::Runner`,
+//! - [`Pallet::validate_source`] is called from `validate_self_contained`.
+//!
+//! ## Gas
+//!
+//! Freezing changes the gas schedule. While at least one address is frozen, every time the EVM
+//! checks whether an address is a precompile (calls, `EXTCODESIZE`, `EXTCODEHASH`, `EXTCODECOPY`)
+//! it costs [`FROZEN_CHECK_GAS`] more, for the read of [`Frozen`]. The charge is a fixed warm read:
+//! the first access to an address in a transaction already pays the EIP-2929 cold account cost, and
+//! later reads of the same key hit the storage overlay. With nothing frozen the schedule is the
+//! stock one.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod precompiles;
+mod runner;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+pub use precompiles::{revert_reason, CircuitBreakerPrecompiles};
+pub use runner::CircuitBreakerRunner;
+
+use polkadot_sdk::{
+    sp_core::H160,
+    sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+/// Revert reason returned for calls into a frozen address.
+pub const FROZEN_REVERT_REASON: &str = "circuit breaker: address is frozen";
+
+/// Extra gas of a precompile check while at least one address is frozen, the EIP-2929 warm
+/// storage read cost.
+pub const FROZEN_CHECK_GAS: u64 = 100;
+
+/// `InvalidTransaction::Custom` code for transactions sent from a frozen address.
+pub const FROZEN_SOURCE_ERROR_CODE: u8 = 140;
+
+#[polkadot_sdk::frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use polkadot_sdk::{
+        frame_support::{self, pallet_prelude::*},
+        frame_system::{self, pallet_prelude::*},
+    };
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to freeze and unfreeze addresses.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Maximum number of addresses that can be frozen at the same time.
+        #[pallet::constant]
+        type MaxFrozen: Get<u32>;
+    }
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// Frozen addresses.
+    #[pallet::storage]
+    pub type Frozen<T: Config> = StorageMap<_, Blake2_128Concat, H160, (), OptionQuery>;
+
+    /// Number of entries in [`Frozen`].
+    ///
+    /// Read before [`Frozen`] so that the common case, nothing frozen, costs a single cached read.
+    #[pallet::storage]
+    pub type FrozenCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Calls into and transactions from `address` are now rejected.
+        Frozen { address: H160 },
+        /// `address` is usable again.
+        Unfrozen { address: H160 },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The address is already frozen.
+        AlreadyFrozen,
+        /// The address is not frozen.
+        NotFrozen,
+        /// `MaxFrozen` addresses are already frozen.
+        TooManyFrozen,
+        /// The transaction source is frozen.
+        SourceFrozen,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Freeze `address`.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
+        pub fn freeze(origin: OriginFor<T>, address: H160) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                !Frozen::<T>::contains_key(address),
+                Error::<T>::AlreadyFrozen
+            );
+            let count = FrozenCount::<T>::get();
+            ensure!(count < T::MaxFrozen::get(), Error::<T>::TooManyFrozen);
+
+            Frozen::<T>::insert(address, ());
+            FrozenCount::<T>::put(count.saturating_add(1));
+            Self::deposit_event(Event::Frozen { address });
+            Ok(())
+        }
+
+        /// Unfreeze `address`.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(2, 2))]
+        pub fn unfreeze(origin: OriginFor<T>, address: H160) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(Frozen::<T>::contains_key(address), Error::<T>::NotFrozen);
+
+            Frozen::<T>::remove(address);
+            FrozenCount::<T>::mutate(|count| *count = count.saturating_sub(1));
+            Self::deposit_event(Event::Unfrozen { address });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Whether `address` is frozen.
+        pub fn is_frozen(address: &H160) -> bool {
+            FrozenCount::<T>::get() > 0 && Frozen::<T>::contains_key(address)
+        }
+
+        /// Reject transactions sent from a frozen address.
+        pub fn validate_source(source: &H160) -> Result<(), TransactionValidityError> {
+            if Self::is_frozen(source) {
+                return Err(InvalidTransaction::Custom(FROZEN_SOURCE_ERROR_CODE).into());
+            }
+            Ok(())
+        }
+    }
+}
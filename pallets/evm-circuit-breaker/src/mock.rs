@@ -0,0 +1,104 @@
+use crate as pallet_evm_circuit_breaker;
+use crate::{CircuitBreakerPrecompiles, CircuitBreakerRunner};
+use pallet_evm::FeeCalculator;
+use polkadot_sdk::{
+    frame_support::{derive_impl, parameter_types, traits::ConstU64, weights::Weight},
+    frame_system::{self, EnsureRoot},
+    pallet_balances, pallet_timestamp,
+    sp_core::{H160, U256},
+    sp_io,
+    sp_runtime::{traits::IdentityLookup, BuildStorage},
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+polkadot_sdk::frame_support::construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Timestamp: pallet_timestamp,
+        EVM: pallet_evm,
+        CircuitBreaker: pallet_evm_circuit_breaker,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountId = H160;
+    type Lookup = IdentityLookup<H160>;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<3000>;
+    type WeightInfo = ();
+}
+
+pub struct ZeroGasPrice;
+impl FeeCalculator for ZeroGasPrice {
+    fn min_gas_price() -> (U256, Weight) {
+        (U256::zero(), Weight::zero())
+    }
+}
+
+/// The stock runner, as wrapped by [`CircuitBreakerRunner`].
+pub type StackRunner = pallet_evm::runner::stack::Runner<Test>;
+
+parameter_types! {
+    pub const MaxFrozen: u32 = 2;
+    pub BlockGasLimit: U256 = U256::from(30_000_000);
+    pub WeightPerGas: Weight = Weight::from_parts(20_000, 0);
+    pub const GasLimitPovSizeRatio: u64 = 0;
+    pub const SuicideQuickClearLimit: u32 = 0;
+    pub Precompiles: CircuitBreakerPrecompiles<Test, ()> = CircuitBreakerPrecompiles::new(());
+}
+
+impl pallet_evm::Config for Test {
+    type FeeCalculator = ZeroGasPrice;
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type CallOrigin = pallet_evm::EnsureAddressRoot<H160>;
+    type WithdrawOrigin = pallet_evm::EnsureAddressNever<H160>;
+    type AddressMapping = pallet_evm::IdentityAddressMapping;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type PrecompilesType = CircuitBreakerPrecompiles<Test, ()>;
+    type PrecompilesValue = Precompiles;
+    type ChainId = ();
+    type BlockGasLimit = BlockGasLimit;
+    type Runner = CircuitBreakerRunner<Test, StackRunner>;
+    type OnChargeTransaction = ();
+    type OnCreate = ();
+    type FindAuthor = ();
+    type GasLimitPovSizeRatio = GasLimitPovSizeRatio;
+    type SuicideQuickClearLimit = SuicideQuickClearLimit;
+    type Timestamp = Timestamp;
+    type WeightInfo = ();
+}
+
+impl pallet_evm_circuit_breaker::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type AdminOrigin = EnsureRoot<H160>;
+    type MaxFrozen = MaxFrozen;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let storage = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        pallet_timestamp::Now::<Test>::put(6_000);
+    });
+    ext
+}
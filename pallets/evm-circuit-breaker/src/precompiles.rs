@@ -0,0 +1,98 @@
+use crate::{Config, Pallet, FROZEN_CHECK_GAS, FROZEN_REVERT_REASON};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use fp_evm::{
+    ExitRevert, IsPrecompileResult, PrecompileFailure, PrecompileHandle, PrecompileResult,
+    PrecompileSet,
+};
+use polkadot_sdk::sp_core::H160;
+
+/// Solidity selector of `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// ABI encode `message` as `Error(string)`, the format `revert("...")` produces and that wallets
+/// and `eth_call` clients decode as a revert reason.
+pub fn revert_reason(message: &str) -> Vec<u8> {
+    let data = message.as_bytes();
+    let padded_len = data.len().div_ceil(32) * 32;
+    let mut output = Vec::with_capacity(4 + 32 + 32 + padded_len);
+    output.extend_from_slice(&ERROR_SELECTOR);
+
+    let mut word = [0u8; 32];
+    word[31] = 0x20;
+    output.extend_from_slice(&word);
+
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(data.len() as u64).to_be_bytes());
+    output.extend_from_slice(&word);
+
+    output.extend_from_slice(data);
+    output.resize(4 + 32 + 32 + padded_len, 0);
+    output
+}
+
+/// Wraps a `PrecompileSet` and reports every frozen address as a precompile that reverts.
+///
+/// Because the EVM asks the precompile set before loading code, this catches top level and nested
+/// calls alike, and the transaction still produces a receipt with a revert reason.
+///
+/// While nothing is frozen the inner set is asked directly, at no extra cost. Otherwise every
+/// address check also reads [`Frozen`](crate::Frozen) and costs [`FROZEN_CHECK_GAS`] more.
+pub struct CircuitBreakerPrecompiles<T, P> {
+    inner: P,
+    _marker: PhantomData<T>,
+}
+
+impl<T, P> CircuitBreakerPrecompiles<T, P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, P: Default> Default for CircuitBreakerPrecompiles<T, P> {
+    fn default() -> Self {
+        Self::new(P::default())
+    }
+}
+
+impl<T, P> PrecompileSet for CircuitBreakerPrecompiles<T, P>
+where
+    T: Config + pallet_evm::Config,
+    P: PrecompileSet,
+{
+    fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+        if Pallet::<T>::is_frozen(&handle.code_address()) {
+            return Some(Err(PrecompileFailure::Revert {
+                exit_status: ExitRevert::Reverted,
+                output: revert_reason(FROZEN_REVERT_REASON),
+            }));
+        }
+        self.inner.execute(handle)
+    }
+
+    fn is_precompile(&self, address: H160, remaining_gas: u64) -> IsPrecompileResult {
+        if crate::FrozenCount::<T>::get() == 0 {
+            return self.inner.is_precompile(address, remaining_gas);
+        }
+
+        if crate::Frozen::<T>::contains_key(address) {
+            return IsPrecompileResult::Answer {
+                is_precompile: true,
+                extra_cost: FROZEN_CHECK_GAS,
+            };
+        }
+        match self.inner.is_precompile(address, remaining_gas) {
+            IsPrecompileResult::Answer {
+                is_precompile,
+                extra_cost,
+            } => IsPrecompileResult::Answer {
+                is_precompile,
+                extra_cost: extra_cost.saturating_add(FROZEN_CHECK_GAS),
+            },
+            other => other,
+        }
+    }
+}
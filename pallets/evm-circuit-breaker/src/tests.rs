@@ -0,0 +1,214 @@
+use crate::{
+    mock::*, revert_reason, Error, Event, FrozenCount, FROZEN_REVERT_REASON,
+    FROZEN_SOURCE_ERROR_CODE,
+};
+use alloc::vec::Vec;
+use fp_evm::{CallInfo, ExitReason, ExitRevert, ExitSucceed};
+use pallet_evm::{Runner, RunnerError};
+use polkadot_sdk::{
+    frame_support::{assert_noop, assert_ok},
+    sp_core::{H160, U256},
+    sp_runtime::{
+        transaction_validity::{InvalidTransaction, TransactionValidityError},
+        DispatchError,
+    },
+};
+
+fn addr(n: u64) -> H160 {
+    H160::from_low_u64_be(n)
+}
+
+#[test]
+fn freeze_and_unfreeze_work() {
+    new_test_ext().execute_with(|| {
+        assert!(!CircuitBreaker::is_frozen(&addr(1)));
+
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(1)));
+        assert!(CircuitBreaker::is_frozen(&addr(1)));
+        assert!(!CircuitBreaker::is_frozen(&addr(2)));
+        assert_eq!(FrozenCount::<Test>::get(), 1);
+        System::assert_last_event(Event::Frozen { address: addr(1) }.into());
+
+        assert_ok!(CircuitBreaker::unfreeze(RuntimeOrigin::root(), addr(1)));
+        assert!(!CircuitBreaker::is_frozen(&addr(1)));
+        assert_eq!(FrozenCount::<Test>::get(), 0);
+        System::assert_last_event(Event::Unfrozen { address: addr(1) }.into());
+    });
+}
+
+#[test]
+fn only_admin_origin_can_freeze() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            CircuitBreaker::freeze(RuntimeOrigin::signed(addr(9)), addr(1)),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            CircuitBreaker::unfreeze(RuntimeOrigin::signed(addr(9)), addr(1)),
+            DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn freeze_checks_state_and_bound() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(1)));
+        assert_noop!(
+            CircuitBreaker::freeze(RuntimeOrigin::root(), addr(1)),
+            Error::<Test>::AlreadyFrozen
+        );
+        assert_noop!(
+            CircuitBreaker::unfreeze(RuntimeOrigin::root(), addr(2)),
+            Error::<Test>::NotFrozen
+        );
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(2)));
+        assert_noop!(
+            CircuitBreaker::freeze(RuntimeOrigin::root(), addr(3)),
+            Error::<Test>::TooManyFrozen
+        );
+    });
+}
+
+#[test]
+fn frozen_source_is_rejected_by_validation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(CircuitBreaker::validate_source(&addr(1)));
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(1)));
+        assert_eq!(
+            CircuitBreaker::validate_source(&addr(1)),
+            Err(TransactionValidityError::Invalid(
+                InvalidTransaction::Custom(FROZEN_SOURCE_ERROR_CODE)
+            ))
+        );
+    });
+}
+
+#[test]
+fn revert_reason_is_abi_encoded() {
+    let output = revert_reason("frozen");
+    assert_eq!(output.len(), 4 + 32 * 3);
+    assert_eq!(&output[..4], &[0x08, 0xc3, 0x79, 0xa0]);
+    assert_eq!(output[4 + 31], 0x20);
+    assert_eq!(output[4 + 32 + 31], 6);
+    assert_eq!(&output[4 + 64..4 + 64 + 6], b"frozen");
+    assert!(output[4 + 64 + 6..].iter().all(|b| *b == 0));
+}
+
+/// Returns the 32 byte word 42.
+const ANSWER: [u8; 10] = [0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+/// Code that calls `target` and returns whether the call succeeded as a 32 byte word.
+fn caller_of(target: H160) -> Vec<u8> {
+    // PUSH1 0 (out size), PUSH1 0 (out offset), PUSH1 0 (in size), PUSH1 0 (in offset),
+    // PUSH1 0 (value), PUSH20 target
+    let mut code = [0x60, 0x00].repeat(5);
+    code.push(0x73);
+    code.extend_from_slice(target.as_bytes());
+    // GAS, CALL, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+    code.extend_from_slice(&[0x5a, 0xf1, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+    code
+}
+
+fn word(value: u64) -> Vec<u8> {
+    let mut word = [0u8; 32];
+    U256::from(value).to_big_endian(&mut word);
+    word.to_vec()
+}
+
+/// Call `target` from `source` through the runner of the mock runtime.
+fn call(source: H160, target: H160) -> Result<CallInfo, RunnerError<DispatchError>> {
+    <Test as pallet_evm::Config>::Runner::call(
+        source,
+        target,
+        Vec::new(),
+        U256::zero(),
+        1_000_000,
+        None,
+        None,
+        None,
+        Vec::new(),
+        true,
+        false,
+        None,
+        None,
+        <Test as pallet_evm::Config>::config(),
+    )
+}
+
+#[test]
+fn calls_into_a_frozen_address_revert() {
+    new_test_ext().execute_with(|| {
+        let (answer, caller) = (addr(1), addr(2));
+        EVM::create_account(answer, ANSWER.to_vec());
+        EVM::create_account(caller, caller_of(answer));
+
+        let info = call(addr(9), answer).unwrap();
+        assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(info.value, word(42));
+        assert_eq!(call(addr(9), caller).unwrap().value, word(1));
+
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), answer));
+        let info = call(addr(9), answer).unwrap();
+        assert_eq!(info.exit_reason, ExitReason::Revert(ExitRevert::Reverted));
+        assert_eq!(info.value, revert_reason(FROZEN_REVERT_REASON));
+        // A nested call fails too, and the calling contract goes on.
+        let info = call(addr(9), caller).unwrap();
+        assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(info.value, word(0));
+
+        assert_ok!(CircuitBreaker::unfreeze(RuntimeOrigin::root(), answer));
+        assert_eq!(call(addr(9), caller).unwrap().value, word(1));
+    });
+}
+
+#[test]
+fn runner_rejects_a_frozen_source() {
+    new_test_ext().execute_with(|| {
+        EVM::create_account(addr(1), ANSWER.to_vec());
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(9)));
+
+        let error = call(addr(9), addr(1)).unwrap_err();
+        assert_eq!(error.error, Error::<Test>::SourceFrozen.into());
+        let created = <Test as pallet_evm::Config>::Runner::create(
+            addr(9),
+            ANSWER.to_vec(),
+            U256::zero(),
+            1_000_000,
+            None,
+            None,
+            None,
+            Vec::new(),
+            true,
+            false,
+            None,
+            None,
+            <Test as pallet_evm::Config>::config(),
+        );
+        assert_eq!(
+            created.unwrap_err().error,
+            Error::<Test>::SourceFrozen.into()
+        );
+
+        // Others still call the contract.
+        assert_eq!(call(addr(8), addr(1)).unwrap().value, word(42));
+    });
+}
+
+#[test]
+fn address_checks_are_charged_only_while_something_is_frozen() {
+    use fp_evm::{IsPrecompileResult, PrecompileSet};
+
+    new_test_ext().execute_with(|| {
+        let cost = |address| match Precompiles::get().is_precompile(address, u64::MAX) {
+            IsPrecompileResult::Answer { extra_cost, .. } => extra_cost,
+            IsPrecompileResult::OutOfGas => panic!("enough gas was given"),
+        };
+        assert_eq!(cost(addr(1)), 0);
+
+        assert_ok!(CircuitBreaker::freeze(RuntimeOrigin::root(), addr(2)));
+        let read = crate::FROZEN_CHECK_GAS;
+        assert_eq!(cost(addr(1)), read);
+        assert_eq!(cost(addr(2)), read);
+    });
+}
@@ -0,0 +1,236 @@
+//! # EVM deployment control
+//!
+//! Permissioned contract deployment for the launch phase of the chain. While [`Enabled`] is set,
+//! a contract may only be created by a top level `CREATE` transaction when either:
+//!
+//! - the sender is in [`Deployers`], or
+//! - the keccak-256 hash of the init code is in [`ApprovedCode`], approved by governance.
+//!
+//! When the switch is off every account may deploy, as on any other EVM chain.
+//!
+//! The check runs twice: [`Pallet::validate_create`] is called from `validate_self_contained` so
+//! that rejected transactions never enter the pool, and [`DeploymentControlRunner`] enforces it at
+//! execution for `EVM::create`/`EVM::create2` and for anything that slipped past the pool. Contracts
+//! created by other contracts (`CREATE`/`CREATE2` opcodes) are not gated: a factory is trusted
+//! once it has itself been deployed.
+//!
+//! Transactions rejected in the pool fail with `InvalidTransaction::Custom(`
+//! [`DEPLOYER_NOT_ALLOWED_ERROR_CODE`]`)`, which `eth_sendRawTransaction` of the node reports
+//! back to the sender as a readable `-32003` error. The status can be queried through
+//! [`DeploymentControlApi`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod runner;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+pub use runner::DeploymentControlRunner;
+
+use polkadot_sdk::{
+    sp_core::{H160, H256},
+    sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+/// `InvalidTransaction::Custom` code for a contract creation that is not permitted.
+pub const DEPLOYER_NOT_ALLOWED_ERROR_CODE: u8 = 141;
+
+polkadot_sdk::sp_api::decl_runtime_apis! {
+    /// Query the permissioned deployment mode.
+    pub trait DeploymentControlApi {
+        /// Whether permissioned deployment is enforced.
+        fn is_enabled() -> bool;
+
+        /// Whether `deployer` may create a contract from init code hashing to `init_code_hash`.
+        fn can_deploy(deployer: H160, init_code_hash: H256) -> bool;
+    }
+}
+
+#[polkadot_sdk::frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use alloc::vec::Vec;
+    use polkadot_sdk::{
+        frame_support::{self, pallet_prelude::*},
+        frame_system::{self, pallet_prelude::*},
+        sp_io::hashing::keccak_256,
+    };
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to toggle the mode and manage deployers and approved code.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+    }
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// Whether permissioned deployment is enforced.
+    #[pallet::storage]
+    pub type Enabled<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// Accounts allowed to deploy any contract.
+    #[pallet::storage]
+    pub type Deployers<T: Config> = StorageMap<_, Blake2_128Concat, H160, (), OptionQuery>;
+
+    /// Keccak-256 hashes of init code that anyone may deploy.
+    #[pallet::storage]
+    pub type ApprovedCode<T: Config> = StorageMap<_, Identity, H256, (), OptionQuery>;
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// Start with permissioned deployment enforced.
+        pub enabled: bool,
+        /// Initial allowlisted deployers.
+        pub deployers: Vec<H160>,
+        #[serde(skip)]
+        pub _config: core::marker::PhantomData<T>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            Enabled::<T>::put(self.enabled);
+            for deployer in &self.deployers {
+                Deployers::<T>::insert(deployer, ());
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Permissioned deployment was switched on or off.
+        EnabledSet { enabled: bool },
+        /// `deployer` may now deploy contracts.
+        DeployerAdded { deployer: H160 },
+        /// `deployer` may no longer deploy contracts.
+        DeployerRemoved { deployer: H160 },
+        /// Init code hashing to `code_hash` may now be deployed by anyone.
+        CodeApproved { code_hash: H256 },
+        /// The approval of `code_hash` was revoked.
+        CodeRevoked { code_hash: H256 },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The account is already an allowlisted deployer.
+        AlreadyDeployer,
+        /// The account is not an allowlisted deployer.
+        NotDeployer,
+        /// The code hash is already approved.
+        AlreadyApproved,
+        /// The code hash is not approved.
+        NotApproved,
+        /// Contract creation is restricted and this deployment is not permitted.
+        DeploymentNotAllowed,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Switch permissioned deployment on or off.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::DbWeight::get().writes(1))]
+        pub fn set_enabled(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            Enabled::<T>::put(enabled);
+            Self::deposit_event(Event::EnabledSet { enabled });
+            Ok(())
+        }
+
+        /// Allow `deployer` to deploy any contract.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn add_deployer(origin: OriginFor<T>, deployer: H160) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                !Deployers::<T>::contains_key(deployer),
+                Error::<T>::AlreadyDeployer
+            );
+            Deployers::<T>::insert(deployer, ());
+            Self::deposit_event(Event::DeployerAdded { deployer });
+            Ok(())
+        }
+
+        /// Remove `deployer` from the allowlist.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn remove_deployer(origin: OriginFor<T>, deployer: H160) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                Deployers::<T>::contains_key(deployer),
+                Error::<T>::NotDeployer
+            );
+            Deployers::<T>::remove(deployer);
+            Self::deposit_event(Event::DeployerRemoved { deployer });
+            Ok(())
+        }
+
+        /// Allow anyone to deploy init code hashing to `code_hash`.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn approve_code(origin: OriginFor<T>, code_hash: H256) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                !ApprovedCode::<T>::contains_key(code_hash),
+                Error::<T>::AlreadyApproved
+            );
+            ApprovedCode::<T>::insert(code_hash, ());
+            Self::deposit_event(Event::CodeApproved { code_hash });
+            Ok(())
+        }
+
+        /// Revoke the approval of `code_hash`.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn revoke_code(origin: OriginFor<T>, code_hash: H256) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(
+                ApprovedCode::<T>::contains_key(code_hash),
+                Error::<T>::NotApproved
+            );
+            ApprovedCode::<T>::remove(code_hash);
+            Self::deposit_event(Event::CodeRevoked { code_hash });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Whether permissioned deployment is enforced.
+        pub fn is_enabled() -> bool {
+            Enabled::<T>::get()
+        }
+
+        /// Whether `deployer` may create a contract from init code hashing to `init_code_hash`.
+        pub fn can_deploy(deployer: &H160, init_code_hash: &H256) -> bool {
+            !Self::is_enabled()
+                || Deployers::<T>::contains_key(deployer)
+                || ApprovedCode::<T>::contains_key(init_code_hash)
+        }
+
+        /// Reject a contract creation from `deployer` with `init` code that is not permitted.
+        pub fn validate_create(
+            deployer: &H160,
+            init: &[u8],
+        ) -> Result<(), TransactionValidityError> {
+            if !Self::is_enabled() {
+                return Ok(());
+            }
+            if Self::can_deploy(deployer, &H256(keccak_256(init))) {
+                Ok(())
+            } else {
+                Err(InvalidTransaction::Custom(DEPLOYER_NOT_ALLOWED_ERROR_CODE).into())
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{Config, Error, Pallet};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use fp_evm::{CallInfo, CreateInfo};
+use pallet_evm::{Runner, RunnerError};
+use polkadot_sdk::{
+    sp_core::{H160, H256, U256},
+    sp_io::hashing::keccak_256,
+    sp_runtime::DispatchError,
+    sp_weights::Weight,
+};
+
+/// Wraps a `pallet_evm::Runner` and refuses contract creations that are not permitted.
+pub struct DeploymentControlRunner<T, R>(PhantomData<(T, R)>);
+
+impl<T, R> DeploymentControlRunner<T, R>
+where
+    T: Config,
+{
+    fn ensure_can_deploy(source: &H160, init: &[u8]) -> Result<(), RunnerError<DispatchError>> {
+        if Pallet::<T>::is_enabled() && !Pallet::<T>::can_deploy(source, &H256(keccak_256(init))) {
+            return Err(RunnerError {
+                error: Error::<T>::DeploymentNotAllowed.into(),
+                weight: Weight::zero(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn map_err<E: Into<DispatchError>>(err: RunnerError<E>) -> RunnerError<DispatchError> {
+    RunnerError {
+        error: err.error.into(),
+        weight: err.weight,
+    }
+}
+
+impl<T, R> Runner<T> for DeploymentControlRunner<T, R>
+where
+    T: Config + pallet_evm::Config,
+    R: Runner<T>,
+{
+    type Error = DispatchError;
+
+    fn validate(
+        source: H160,
+        target: Option<H160>,
+        input: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+        max_fee_per_gas: Option<U256>,
+        max_priority_fee_per_gas: Option<U256>,
+        nonce: Option<U256>,
+        access_list: Vec<(H160, Vec<H256>)>,
+        is_transactional: bool,
+        weight_limit: Option<Weight>,
+        proof_size_base_cost: Option<u64>,
+        evm_config: &fp_evm::Config,
+    ) -> Result<(), RunnerError<Self::Error>> {
+        if target.is_none() {
+            Self::ensure_can_deploy(&source, &input)?;
+        }
+        R::validate(
+            source,
+            target,
+            input,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            nonce,
+            access_list,
+            is_transactional,
+            weight_limit,
+            proof_size_base_cost,
+            evm_config,
+        )
+        .map_err(map_err)
+    }
+
+    fn call(
+        source: H160,
+        target: H160,
+        input: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+        max_fee_per_gas: Option<U256>,
+        max_priority_fee_per_gas: Option<U256>,
+        nonce: Option<U256>,
+        access_list: Vec<(H160, Vec<H256>)>,
+        is_transactional: bool,
+        validate: bool,
+        weight_limit: Option<Weight>,
+        proof_size_base_cost: Option<u64>,
+        config: &fp_evm::Config,
+    ) -> Result<CallInfo, RunnerError<Self::Error>> {
+        R::call(
+            source,
+            target,
+            input,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            nonce,
+            access_list,
+            is_transactional,
+            validate,
+            weight_limit,
+            proof_size_base_cost,
+            config,
+        )
+        .map_err(map_err)
+    }
+
+    fn create(
+        source: H160,
+        init: Vec<u8>,
+        value: U256,
+        gas_limit: u64,
+        max_fee_per_gas: Option<U256>,
+        max_priority_fee_per_gas: Option<U256>,
+        nonce: Option<U256>,
+        access_list: Vec<(H160, Vec<H256>)>,
+        is_transactional: bool,
+        validate: bool,
+        weight_limit: Option<Weight>,
+        proof_size_base_cost: Option<u64>,
+        config: &fp_evm::Config,
+    ) -> Result<CreateInfo, RunnerError<Self::Error>> {
+        Self::ensure_can_deploy(&source, &init)?;
+        R::create(
+            source,
+            init,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            nonce,
+            access_list,
+            is_transactional,
+            validate,
+            weight_limit,
+            proof_size_base_cost,
+            config,
+        )
+        .map_err(map_err)
+    }
+
+    fn create2(
+        source: H160,
+        init: Vec<u8>,
+        salt: H256,
+        value: U256,
+        gas_limit: u64,
+        max_fee_per_gas: Option<U256>,
+        max_priority_fee_per_gas: Option<U256>,
+        nonce: Option<U256>,
+        access_list: Vec<(H160, Vec<H256>)>,
+        is_transactional: bool,
+        validate: bool,
+        weight_limit: Option<Weight>,
+        proof_size_base_cost: Option<u64>,
+        config: &fp_evm::Config,
+    ) -> Result<CreateInfo, RunnerError<Self::Error>> {
+        Self::ensure_can_deploy(&source, &init)?;
+        R::create2(
+            source,
+            init,
+            salt,
+            value,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            nonce,
+            access_list,
+            is_transactional,
+            validate,
+            weight_limit,
+            proof_size_base_cost,
+            config,
+        )
+        .map_err(map_err)
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{mock::*, Error, Event, DEPLOYER_NOT_ALLOWED_ERROR_CODE};
+use polkadot_sdk::{
+    frame_support::{assert_noop, assert_ok},
+    sp_core::{H160, H256},
+    sp_io::hashing::keccak_256,
+    sp_runtime::{
+        transaction_validity::{InvalidTransaction, TransactionValidityError},
+        DispatchError,
+    },
+};
+
+const INIT: &[u8] = &[0x60, 0x00, 0x60, 0x00, 0xf3];
+
+fn addr(n: u64) -> H160 {
+    H160::from_low_u64_be(n)
+}
+
+fn not_allowed() -> Result<(), TransactionValidityError> {
+    Err(InvalidTransaction::Custom(DEPLOYER_NOT_ALLOWED_ERROR_CODE).into())
+}
+
+#[test]
+fn anyone_can_deploy_while_disabled() {
+    new_test_ext().execute_with(|| {
+        assert!(!DeploymentControl::is_enabled());
+        assert!(DeploymentControl::can_deploy(&addr(1), &H256::zero()));
+        assert_ok!(DeploymentControl::validate_create(&addr(1), INIT));
+    });
+}
+
+#[test]
+fn allowlisted_deployers_can_deploy() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(DeploymentControl::set_enabled(RuntimeOrigin::root(), true));
+        System::assert_last_event(Event::EnabledSet { enabled: true }.into());
+        assert_eq!(
+            DeploymentControl::validate_create(&addr(1), INIT),
+            not_allowed()
+        );
+
+        assert_ok!(DeploymentControl::add_deployer(
+            RuntimeOrigin::root(),
+            addr(1)
+        ));
+        System::assert_last_event(Event::DeployerAdded { deployer: addr(1) }.into());
+        assert_ok!(DeploymentControl::validate_create(&addr(1), INIT));
+        assert_eq!(
+            DeploymentControl::validate_create(&addr(2), INIT),
+            not_allowed()
+        );
+
+        assert_ok!(DeploymentControl::remove_deployer(
+            RuntimeOrigin::root(),
+            addr(1)
+        ));
+        assert_eq!(
+            DeploymentControl::validate_create(&addr(1), INIT),
+            not_allowed()
+        );
+    });
+}
+
+#[test]
+fn approved_code_can_be_deployed_by_anyone() {
+    new_test_ext().execute_with(|| {
+        let code_hash = H256(keccak_256(INIT));
+        assert_ok!(DeploymentControl::set_enabled(RuntimeOrigin::root(), true));
+        assert_ok!(DeploymentControl::approve_code(
+            RuntimeOrigin::root(),
+            code_hash
+        ));
+        System::assert_last_event(Event::CodeApproved { code_hash }.into());
+
+        assert_ok!(DeploymentControl::validate_create(&addr(2), INIT));
+        assert_eq!(
+            DeploymentControl::validate_create(&addr(2), &INIT[1..]),
+            not_allowed()
+        );
+
+        assert_ok!(DeploymentControl::revoke_code(
+            RuntimeOrigin::root(),
+            code_hash
+        ));
+        assert_eq!(
+            DeploymentControl::validate_create(&addr(2), INIT),
+            not_allowed()
+        );
+    });
+}
+
+#[test]
+fn management_checks_origin_and_state() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            DeploymentControl::set_enabled(RuntimeOrigin::signed(1), true),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            DeploymentControl::remove_deployer(RuntimeOrigin::root(), addr(1)),
+            Error::<Test>::NotDeployer
+        );
+        assert_ok!(DeploymentControl::add_deployer(
+            RuntimeOrigin::root(),
+            addr(1)
+        ));
+        assert_noop!(
+            DeploymentControl::add_deployer(RuntimeOrigin::root(), addr(1)),
+            Error::<Test>::AlreadyDeployer
+        );
+        assert_noop!(
+            DeploymentControl::revoke_code(RuntimeOrigin::root(), H256::zero()),
+            Error::<Test>::NotApproved
+        );
+    });
+}
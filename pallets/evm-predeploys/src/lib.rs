@@ -0,0 +1,205 @@
+//! # EVM predeploys
+//!
+//! Infrastructure contracts that tooling expects at canonical addresses (CREATE2 factory,
+//! Multicall3, Safe singletons, Permit2, ...).
+//!
+//! - [`parse`] reads the JSON format shared by the bundled set and by chain spec authors, and turns
+//!   it into `fp_evm::GenesisAccount`s for the `evm` genesis section.
+//! - [`Pallet::inject`] lets governance deploy one of the runtime's predeploys on a chain that is
+//!   already running, with exactly the bytecode a fresh genesis would get.
+//!
+//! ## JSON format
+//!
+//! ```json
+//! [
+//!   {
+//!     "name": "CREATE2 factory",
+//!     "address": "0x4e59b44847b379578588920ca78fbf26c0b4956c",
+//!     "code": "0x7fff...",
+//!     "nonce": "0x1",
+//!     "balance": "0x0",
+//!     "storage": { "0x00...04": "0x00...01" }
+//!   }
+//! ]
+//! ```
+//!
+//! `nonce`, `balance` and `storage` are optional.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(test)]
+mod tests;
+
+pub use pallet::*;
+
+use alloc::{collections::btree_map::BTreeMap, string::String, vec::Vec};
+use fp_evm::GenesisAccount;
+use polkadot_sdk::sp_core::{H160, H256, U256};
+use serde::Deserialize;
+
+/// A contract deployed at a fixed address.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Predeploy {
+    /// Human readable name, for logs and events.
+    pub name: String,
+    /// Canonical address.
+    pub address: H160,
+    /// Code, storage, nonce and balance of the account.
+    pub account: GenesisAccount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Entry {
+    name: String,
+    address: H160,
+    code: String,
+    #[serde(default)]
+    nonce: U256,
+    #[serde(default)]
+    balance: U256,
+    #[serde(default)]
+    storage: BTreeMap<H256, H256>,
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if value.len() % 2 != 0 {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parse a list of predeploys in the format described in the crate documentation.
+pub fn parse(json: &str) -> Result<Vec<Predeploy>, serde_json::Error> {
+    let entries: Vec<Entry> = serde_json::from_str(json)?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let code = decode_hex(&entry.code).ok_or_else(|| {
+                <serde_json::Error as serde::de::Error>::custom("`code` is not valid hex")
+            })?;
+            Ok(Predeploy {
+                name: entry.name,
+                address: entry.address,
+                account: GenesisAccount {
+                    nonce: entry.nonce,
+                    balance: entry.balance,
+                    storage: entry.storage,
+                    code,
+                },
+            })
+        })
+        .collect()
+}
+
+/// The `evm.accounts` genesis map for `predeploys`.
+pub fn genesis_accounts(predeploys: &[Predeploy]) -> BTreeMap<H160, GenesisAccount> {
+    predeploys
+        .iter()
+        .map(|p| (p.address, p.account.clone()))
+        .collect()
+}
+
+#[polkadot_sdk::frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use pallet_evm::AddressMapping;
+    use polkadot_sdk::{
+        frame_support::{self, pallet_prelude::*},
+        frame_system::{self, pallet_prelude::*},
+        sp_runtime::traits::UniqueSaturatedInto,
+    };
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config + pallet_evm::Config {
+        /// The overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to inject predeploys.
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// The predeploys this runtime knows about.
+        type Predeploys: Get<&'static [Predeploy]>;
+    }
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// The predeploy at `address` was deployed.
+        Injected { address: H160 },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The runtime has no predeploy at this address.
+        UnknownPredeploy,
+        /// The address already holds code.
+        AlreadyDeployed,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Deploy the predeploy at `address`, as it would have been at genesis.
+        ///
+        /// The balance of the predeploy is not minted.
+        #[pallet::call_index(0)]
+        #[pallet::weight(Pallet::<T>::inject_weight(Pallet::<T>::predeploy(address)))]
+        pub fn inject(origin: OriginFor<T>, address: H160) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let predeploy = Self::predeploy(address).ok_or(Error::<T>::UnknownPredeploy)?;
+            ensure!(
+                pallet_evm::AccountCodes::<T>::decode_len(address).unwrap_or(0) == 0,
+                Error::<T>::AlreadyDeployed
+            );
+
+            Self::deploy(predeploy);
+            Self::deposit_event(Event::Injected { address });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// The predeploy of the runtime at `address`.
+        pub fn predeploy(address: H160) -> Option<&'static Predeploy> {
+            T::Predeploys::get().iter().find(|p| p.address == address)
+        }
+
+        /// Weight of [`Pallet::inject`] for `predeploy`.
+        ///
+        /// Reads the code of the address, the system account and whether the account has code.
+        /// Writes the system account (nonce and sufficients), the code, the code metadata and
+        /// every storage slot of the predeploy.
+        pub fn inject_weight(predeploy: Option<&Predeploy>) -> Weight {
+            let slots = predeploy.map_or(0, |p| p.account.storage.len() as u64);
+            T::DbWeight::get().reads_writes(3, 3 + slots)
+        }
+
+        fn deploy(predeploy: &Predeploy) {
+            let address = predeploy.address;
+            let account_id = T::AddressMapping::into_account_id(address);
+            let nonce = predeploy.account.nonce.low_u64();
+            loop {
+                let current: u64 =
+                    frame_system::Pallet::<T>::account_nonce(&account_id).unique_saturated_into();
+                if current >= nonce {
+                    break;
+                }
+                frame_system::Pallet::<T>::inc_account_nonce(&account_id);
+            }
+
+            pallet_evm::Pallet::<T>::create_account(address, predeploy.account.code.clone());
+            for (key, value) in &predeploy.account.storage {
+                pallet_evm::AccountStorages::<T>::insert(address, key, value);
+            }
+        }
+    }
+}
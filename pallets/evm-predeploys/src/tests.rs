@@ -0,0 +1,63 @@
+use crate::{decode_hex, genesis_accounts, parse};
+use polkadot_sdk::sp_core::{H160, H256, U256};
+
+#[test]
+fn parses_full_and_minimal_entries() {
+    let json = r#"[
+        {
+            "name": "full",
+            "address": "0x0000000000000000000000000000000000000001",
+            "code": "0x6000",
+            "nonce": "0x1",
+            "balance": "0x10",
+            "storage": {
+                "0x0000000000000000000000000000000000000000000000000000000000000004":
+                "0x0000000000000000000000000000000000000000000000000000000000000001"
+            }
+        },
+        {
+            "name": "minimal",
+            "address": "0x0000000000000000000000000000000000000002",
+            "code": "0x"
+        }
+    ]"#;
+
+    let predeploys = parse(json).unwrap();
+    assert_eq!(predeploys.len(), 2);
+
+    let full = &predeploys[0];
+    assert_eq!(full.name, "full");
+    assert_eq!(full.address, H160::from_low_u64_be(1));
+    assert_eq!(full.account.code, vec![0x60, 0x00]);
+    assert_eq!(full.account.nonce, U256::one());
+    assert_eq!(full.account.balance, U256::from(16));
+    assert_eq!(
+        full.account.storage.get(&H256::from_low_u64_be(4)),
+        Some(&H256::from_low_u64_be(1))
+    );
+
+    let minimal = &predeploys[1];
+    assert!(minimal.account.code.is_empty());
+    assert_eq!(minimal.account.nonce, U256::zero());
+    assert!(minimal.account.storage.is_empty());
+
+    let accounts = genesis_accounts(&predeploys);
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[&H160::from_low_u64_be(1)], full.account);
+}
+
+#[test]
+fn rejects_malformed_entries() {
+    let bad_code = r#"[{"name": "x", "address": "0x0000000000000000000000000000000000000001", "code": "0x600"}]"#;
+    assert!(parse(bad_code).is_err());
+
+    let unknown_field = r#"[{"name": "x", "address": "0x0000000000000000000000000000000000000001", "code": "0x", "cod": "0x"}]"#;
+    assert!(parse(unknown_field).is_err());
+}
+
+#[test]
+fn decodes_hex() {
+    assert_eq!(decode_hex("0x"), Some(vec![]));
+    assert_eq!(decode_hex("00ff"), Some(vec![0x00, 0xff]));
+    assert_eq!(decode_hex("0xzz"), None);
+}
@@ -0,0 +1,404 @@
+//! # EVM conformance
+//!
+//! Runs the Cancun `GeneralStateTests` fixtures of `ethereum/tests` against a runtime's
+//! `pallet_evm`. Both runtimes call [`general_state_tests`] from their own tests with their
+//! runtime type and the EVM config of the Cancun fork.
+//!
+//! The fixtures of the suites in [`SUITES`] are vendored under `fixtures/` (see its README for
+//! where they come from). `ETHEREUM_TESTS_DIR` points the harness at a checkout of
+//! <https://github.com/ethereum/tests> instead, and `ETHEREUM_TESTS_FILTER` restricts the run to
+//! test names containing the given string:
+//!
+//! ```text
+//! ETHEREUM_TESTS_DIR=../tests cargo test -p kitchensink-mainnet-runtime general_state_tests
+//! ```
+//!
+//! Every `Cancun` post entry is executed through the runtime's EVM runner. The outcome (rejected
+//! or not), the logs hash and, when the fixture lists it, the post state code, nonce and storage
+//! of each account are compared. Balances and the state root are not: fees go through
+//! `pallet_evm`'s fee handling and there is no coinbase reward, so they differ from Ethereum by
+//! design.
+
+use pallet_evm::{AddressMapping, Runner};
+use polkadot_sdk::{
+    frame_support::traits::Currency,
+    frame_system, pallet_timestamp,
+    sp_core::{H160, H256, U256},
+    sp_io,
+    sp_runtime::{BuildStorage, DispatchError, SaturatedConversion, Storage},
+};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The suites run, relative to the fixtures root.
+pub const SUITES: &[&str] = &[
+    "GeneralStateTests/Cancun/stEIP1153-transientStorage",
+    "GeneralStateTests/Cancun/stEIP5656-MCOPY",
+    "GeneralStateTests/Cancun/stEIP7516-blobBaseFee",
+];
+
+const FORK: &str = "Cancun";
+
+fn hex(value: &Value) -> Vec<u8> {
+    let s = value.as_str().unwrap_or_default().trim_start_matches("0x");
+    let s = if s.len() % 2 == 1 {
+        format!("0{s}")
+    } else {
+        s.to_string()
+    };
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("fixture contains valid hex"))
+        .collect()
+}
+
+fn u256(value: &Value) -> Result<U256, String> {
+    let bytes = hex(value);
+    if bytes.len() > 32 {
+        return Err(format!("{value} does not fit in 256 bits"));
+    }
+    Ok(U256::from_big_endian(&bytes))
+}
+
+fn h160(value: &str) -> H160 {
+    let bytes = hex(&Value::String(value.to_string()));
+    let mut out = [0u8; 20];
+    out[20 - bytes.len()..].copy_from_slice(&bytes);
+    H160(out)
+}
+
+fn h256(value: &Value) -> Result<H256, String> {
+    let mut out = [0u8; 32];
+    u256(value)?.to_big_endian(&mut out);
+    Ok(H256(out))
+}
+
+fn rlp_length(offset: u8, len: usize, out: &mut Vec<u8>) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let be = len.to_be_bytes();
+        let be = &be[be.iter().take_while(|b| **b == 0).count()..];
+        out.push(offset + 55 + be.len() as u8);
+        out.extend_from_slice(be);
+    }
+}
+
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 9);
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        out.push(bytes[0]);
+    } else {
+        rlp_length(0x80, bytes.len(), &mut out);
+        out.extend_from_slice(bytes);
+    }
+    out
+}
+
+fn rlp_list(items: Vec<Vec<u8>>) -> Vec<u8> {
+    let payload = items.concat();
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    rlp_length(0xc0, payload.len(), &mut out);
+    out.extend(payload);
+    out
+}
+
+/// `keccak256(rlp(logs))`, the `logs` field of a fixture post entry.
+fn logs_hash(logs: &[fp_evm::Log]) -> H256 {
+    let encoded = rlp_list(
+        logs.iter()
+            .map(|log| {
+                rlp_list(vec![
+                    rlp_bytes(log.address.as_bytes()),
+                    rlp_list(log.topics.iter().map(|t| rlp_bytes(t.as_bytes())).collect()),
+                    rlp_bytes(&log.data),
+                ])
+            })
+            .collect(),
+    );
+    H256(sp_io::hashing::keccak_256(&encoded))
+}
+
+fn set_up_pre_state<T>(test: &Value) -> Result<(), String>
+where
+    T: pallet_evm::Config + pallet_timestamp::Config + pallet_base_fee::Config,
+{
+    let env = &test["env"];
+    frame_system::Pallet::<T>::set_block_number(
+        u256(&env["currentNumber"])?.low_u64().saturated_into(),
+    );
+    pallet_timestamp::Now::<T>::put(
+        (u256(&env["currentTimestamp"])?.low_u64() * 1000).saturated_into::<T::Moment>(),
+    );
+    if !env["currentBaseFee"].is_null() {
+        pallet_base_fee::BaseFeePerGas::<T>::put(u256(&env["currentBaseFee"])?);
+    }
+
+    let pre = test["pre"].as_object().ok_or("missing pre state")?;
+    for (address, account) in pre {
+        let address = h160(address);
+        let who = T::AddressMapping::into_account_id(address);
+
+        let balance = u256(&account["balance"])?;
+        if balance > U256::from(u128::MAX) {
+            return Err(format!(
+                "balance of {address:?} does not fit the native balance type"
+            ));
+        }
+        let _ = <T as pallet_evm::Config>::Currency::make_free_balance_be(
+            &who,
+            balance.low_u128().saturated_into(),
+        );
+        let nonce = u256(&account["nonce"]).unwrap_or_default().low_u64();
+        frame_system::Account::<T>::mutate(&who, |info| info.nonce = nonce.saturated_into());
+
+        pallet_evm::Pallet::<T>::create_account(address, hex(&account["code"]));
+        for (key, value) in account["storage"].as_object().into_iter().flatten() {
+            pallet_evm::AccountStorages::<T>::insert(
+                address,
+                h256(&Value::String(key.clone()))?,
+                h256(value)?,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_post_entry<T>(
+    test: &Value,
+    post: &Value,
+    config: &'static fp_evm::Config,
+) -> Result<(), String>
+where
+    T: pallet_evm::Config + pallet_timestamp::Config + pallet_base_fee::Config,
+{
+    set_up_pre_state::<T>(test)?;
+
+    let tx = &test["transaction"];
+    let indexes = &post["indexes"];
+    let index = |name: &str| indexes[name].as_u64().unwrap_or_default() as usize;
+
+    let sender = h160(tx["sender"].as_str().ok_or("fixture has no sender")?);
+    let input = hex(&tx["data"][index("data")]);
+    let gas_limit = u256(&tx["gasLimit"][index("gas")])?.low_u64();
+    let value = u256(&tx["value"][index("value")])?;
+    let nonce = u256(&tx["nonce"])?;
+    let (max_fee_per_gas, max_priority_fee_per_gas) = if tx["gasPrice"].is_null() {
+        (
+            u256(&tx["maxFeePerGas"])?,
+            u256(&tx["maxPriorityFeePerGas"])?,
+        )
+    } else {
+        let gas_price = u256(&tx["gasPrice"])?;
+        (gas_price, gas_price)
+    };
+    let mut access_list = Vec::new();
+    for item in tx["accessLists"][index("data")]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let keys = item["storageKeys"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(h256)
+            .collect::<Result<Vec<_>, _>>()?;
+        access_list.push((h160(item["address"].as_str().unwrap_or_default()), keys));
+    }
+
+    let target = tx["to"].as_str().filter(|to| !to.is_empty()).map(h160);
+    let outcome = match target {
+        Some(target) => T::Runner::call(
+            sender,
+            target,
+            input,
+            value,
+            gas_limit,
+            Some(max_fee_per_gas),
+            Some(max_priority_fee_per_gas),
+            Some(nonce),
+            access_list,
+            true,
+            true,
+            None,
+            None,
+            config,
+        )
+        .map(|info| info.logs),
+        None => T::Runner::create(
+            sender,
+            input,
+            value,
+            gas_limit,
+            Some(max_fee_per_gas),
+            Some(max_priority_fee_per_gas),
+            Some(nonce),
+            access_list,
+            true,
+            true,
+            None,
+            None,
+            config,
+        )
+        .map(|info| info.logs),
+    };
+
+    let expect_exception = !post["expectException"].is_null();
+    let logs = match (outcome, expect_exception) {
+        (Ok(logs), false) => logs,
+        (Err(_), true) => return Ok(()),
+        (Ok(_), true) => {
+            return Err(format!(
+                "expected {} but the transaction was accepted",
+                post["expectException"]
+            ))
+        }
+        (Err(e), false) => {
+            let error: DispatchError = e.error.into();
+            return Err(format!("transaction rejected: {error:?}"));
+        }
+    };
+
+    let expected_logs = h256(&post["logs"])?;
+    let actual_logs = logs_hash(&logs);
+    if expected_logs != actual_logs {
+        return Err(format!(
+            "logs hash {actual_logs:?}, expected {expected_logs:?}"
+        ));
+    }
+
+    for (address, account) in post["state"].as_object().into_iter().flatten() {
+        let address = h160(address);
+        let who = T::AddressMapping::into_account_id(address);
+
+        let code = pallet_evm::AccountCodes::<T>::get(address);
+        if code != hex(&account["code"]) {
+            return Err(format!("code of {address:?} differs"));
+        }
+        let nonce: u64 = frame_system::Account::<T>::get(&who).nonce.saturated_into();
+        if U256::from(nonce) != u256(&account["nonce"])? {
+            return Err(format!("nonce of {address:?} is {nonce}"));
+        }
+        for (key, value) in account["storage"].as_object().into_iter().flatten() {
+            let key = h256(&Value::String(key.clone()))?;
+            let actual = pallet_evm::AccountStorages::<T>::get(address, key);
+            if actual != h256(value)? {
+                return Err(format!("storage {key:?} of {address:?} is {actual:?}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn fixture_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            fixture_files(&path, out);
+        } else if path.extension().map_or(false, |e| e == "json") {
+            out.push(path);
+        }
+    }
+}
+
+/// The vendored fixtures, or the `ethereum/tests` checkout `ETHEREUM_TESTS_DIR` points at.
+pub fn fixtures_root() -> PathBuf {
+    std::env::var("ETHEREUM_TESTS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"))
+}
+
+/// Runs every `Cancun` post entry of the [`SUITES`] against `T`, each in fresh externalities
+/// built from `genesis`, and panics listing the entries that fail.
+pub fn general_state_tests<T>(config: &'static fp_evm::Config, genesis: impl Fn() -> Storage)
+where
+    T: pallet_evm::Config + pallet_timestamp::Config + pallet_base_fee::Config,
+{
+    let root = fixtures_root();
+    let filter = std::env::var("ETHEREUM_TESTS_FILTER").unwrap_or_default();
+
+    let mut files = Vec::new();
+    for suite in SUITES {
+        fixture_files(&root.join(suite), &mut files);
+    }
+    assert!(
+        !files.is_empty(),
+        "no fixtures found under {}",
+        root.display()
+    );
+
+    let (mut passed, mut failures) = (0, Vec::new());
+    for file in files {
+        let content = fs::read_to_string(&file).expect("fixture is readable");
+        let fixtures: Value = serde_json::from_str(&content).expect("fixture is valid JSON");
+        for (name, test) in fixtures.as_object().into_iter().flatten() {
+            if !name.contains(&filter) {
+                continue;
+            }
+            for (i, post) in test["post"][FORK]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .enumerate()
+            {
+                let result = sp_io::TestExternalities::new(genesis())
+                    .execute_with(|| run_post_entry::<T>(test, post, config));
+                match result {
+                    Ok(()) => passed += 1,
+                    Err(e) => failures.push(format!("{name}[{i}]: {e}")),
+                }
+            }
+        }
+    }
+
+    for failure in &failures {
+        println!("FAIL {failure}");
+    }
+    println!("{passed} passed, {} failed", failures.len());
+    assert!(passed > 0, "no {FORK} post entries matched");
+    assert!(
+        failures.is_empty(),
+        "{} post entries failed",
+        failures.len()
+    );
+}
+
+/// The genesis storage of `frame_system` alone, enough for the fixtures.
+pub fn system_genesis<T: frame_system::Config>() -> Storage {
+    frame_system::GenesisConfig::<T>::default()
+        .build_storage()
+        .expect("frame_system genesis builds")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_logs_hash_matches_ethereum() {
+        assert_eq!(
+            logs_hash(&[]),
+            h256(&Value::from(
+                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"
+            ))
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn vendored_fixtures_cover_every_suite() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        for suite in SUITES {
+            let mut files = Vec::new();
+            fixture_files(&root.join(suite), &mut files);
+            assert!(!files.is_empty(), "{suite} has no fixtures");
+        }
+    }
+}
@@ -0,0 +1,86 @@
+# Tiny EVM used to cross-check the hand-assembled conformance fixtures.
+from keccak import keccak256
+OPS={'STOP':0x00,'ADD':0x01,'EQ':0x14,'ISZERO':0x15,'POP':0x50,'MLOAD':0x51,'MSTORE':0x52,'SLOAD':0x54,'SSTORE':0x55,'JUMPI':0x57,'MSIZE':0x59,'GAS':0x5a,'JUMPDEST':0x5b,'TLOAD':0x5c,'TSTORE':0x5d,'MCOPY':0x5e,'CALLDATASIZE':0x36,'BLOBBASEFEE':0x4a,'LOG1':0xa1,'CALL':0xf1,'RETURN':0xf3,'REVERT':0xfd}
+def asm(prog):
+    # prog: list of op names, ints (PUSH with minimal width) or ('label',name)/('ref',name)
+    labels={}; 
+    def size(x):
+        if isinstance(x,tuple): return 0 if x[0]=='label' else 2
+        if isinstance(x,int): return 1+max(1,(x.bit_length()+7)//8)
+        if isinstance(x,bytes): return 1+len(x)
+        return 1
+    pc=0
+    for x in prog:
+        if isinstance(x,tuple) and x[0]=='label': labels[x[1]]=pc
+        pc+=size(x)
+    out=bytearray()
+    for x in prog:
+        if isinstance(x,tuple):
+            if x[0]=='ref': out+=bytes([0x60,labels[x[1]]])
+        elif isinstance(x,bytes): out+=bytes([0x5f+len(x)])+x
+        elif isinstance(x,int):
+            n=max(1,(x.bit_length()+7)//8); out+=bytes([0x5f+n])+x.to_bytes(n,'big')
+        else: out.append(OPS[x])
+    return bytes(out)
+class Revert(Exception): pass
+def run(state, trans, addr, code, calldata, logs, depth=0):
+    st=[]; mem=bytearray(); pc=0
+    def ext(o,l):
+        if l==0: return
+        need=(o+l+31)//32*32
+        if len(mem)<need: mem.extend(bytes(need-len(mem)))
+    storage=state.setdefault(addr,{}).setdefault('storage',{})
+    while pc<len(code):
+        op=code[pc]; pc+=1
+        if 0x60<=op<=0x7f:
+            n=op-0x5f; st.append(int.from_bytes(code[pc:pc+n],'big')); pc+=n; continue
+        if op==0x00: return b''
+        elif op==0x01: a,b=st.pop(),st.pop(); st.append((a+b)%2**256)
+        elif op==0x14: a,b=st.pop(),st.pop(); st.append(int(a==b))
+        elif op==0x15: st.append(int(st.pop()==0))
+        elif op==0x50: st.pop()
+        elif op==0x51: o=st.pop(); ext(o,32); st.append(int.from_bytes(mem[o:o+32],'big'))
+        elif op==0x52: o,v=st.pop(),st.pop(); ext(o,32); mem[o:o+32]=v.to_bytes(32,'big')
+        elif op==0x54: st.append(storage.get(st.pop(),0))
+        elif op==0x55: k,v=st.pop(),st.pop(); storage[k]=v
+        elif op==0x57:
+            d,c=st.pop(),st.pop()
+            if c: assert code[d]==0x5b; pc=d
+        elif op==0x59: st.append(len(mem))
+        elif op==0x5a: st.append(10**6)
+        elif op==0x5b: pass
+        elif op==0x5c: st.append(trans.get((addr,st.pop()),0))
+        elif op==0x5d: k,v=st.pop(),st.pop(); trans[(addr,k)]=v
+        elif op==0x5e:
+            d,s,l=st.pop(),st.pop(),st.pop()
+            if l: ext(max(d,s),l); mem[d:d+l]=bytes(mem[s:s+l])
+        elif op==0x36: st.append(len(calldata))
+        elif op==0x4a: st.append(0)
+        elif op==0xa1:
+            o,l,t=st.pop(),st.pop(),st.pop(); ext(o,l); logs.append((addr,[t],bytes(mem[o:o+l])))
+        elif op==0xf1:
+            g,to,val,ao,al,ro,rl=[st.pop() for _ in range(7)]
+            ext(ao,al); ext(ro,rl)
+            import copy
+            snap=(copy.deepcopy(state),dict(trans),len(logs))
+            try:
+                out=run(state,trans,to,state[to]['code'],bytes(mem[ao:ao+al]),logs,depth+1); ok=1
+            except Revert as r:
+                out=r.args[0]; ok=0
+                state.clear(); state.update(snap[0]); trans.clear(); trans.update(snap[1]); del logs[snap[2]:]
+                storage=state[addr]['storage']
+            n=min(rl,len(out)); mem[ro:ro+n]=out[:n]; st.append(ok)
+        elif op==0xf3: o,l=st.pop(),st.pop(); ext(o,l); return bytes(mem[o:o+l])
+        elif op==0xfd: o,l=st.pop(),st.pop(); ext(o,l); raise Revert(bytes(mem[o:o+l]))
+        else: raise Exception('op %x'%op)
+    return b''
+def rlp_b(b): 
+    if len(b)==1 and b[0]<0x80: return b
+    return rlp_len(0x80,len(b))+b
+def rlp_len(off,n):
+    if n<56: return bytes([off+n])
+    be=n.to_bytes((n.bit_length()+7)//8,'big'); return bytes([off+55+len(be)])+be
+def rlp_l(items):
+    p=b''.join(items); return rlp_len(0xc0,len(p))+p
+def logs_hash(logs):
+    return keccak256(rlp_l([rlp_l([rlp_b(bytes.fromhex('%040x'%a)),rlp_l([rlp_b(t.to_bytes(32,'big')) for t in ts]),rlp_b(d)]) for a,ts,d in logs]))
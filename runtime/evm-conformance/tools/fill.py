@@ -0,0 +1,72 @@
+# Writes the fixtures under ../fixtures/GeneralStateTests/Cancun, with the post state and logs
+# hash computed by the reference interpreter in evm.py.
+import json, os, copy, sys
+from evm import *
+SENDER=0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b
+A=0x1000000000000000000000000000000000001000
+B=0x1000000000000000000000000000000000002000
+def h(x,n=None): 
+    return '0x%02x'%x if n is None else '0x'+x.to_bytes(n,'big').hex()
+def call(target,args_size):
+    return [0x20,0,args_size,0,0,target.to_bytes(20,'big'),'GAS','CALL']
+def fixture(name, comment, accounts, target=A, data=b''):
+    pre={}
+    state={}
+    for addr,(code,storage) in accounts.items():
+        state[addr]={'code':code,'storage':dict(storage)}
+    state[SENDER]={'code':b'','storage':{}}
+    pre_state=copy.deepcopy(state)
+    logs=[]; trans={}
+    run(state,trans,target,state[target]['code'],data,logs)
+    def acct(a,s,nonce,bal):
+        return {'balance':bal,'code':'0x'+s['code'].hex(),'nonce':nonce,
+            'storage':{h(k):h(v) for k,v in sorted(s['storage'].items())}}
+    fx={'_info':{'comment':comment},
+        'env':{'currentBaseFee':'0x0a','currentCoinbase':'0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba',
+               'currentGasLimit':'0x055d4a80','currentNumber':'0x01','currentTimestamp':'0x03e8',
+               'currentExcessBlobGas':'0x00'},
+        'pre':{h(a,20):acct(a,s,'0x01' if a!=SENDER else '0x00','0x3635c9adc5dea00000') for a,s in pre_state.items()},
+        'transaction':{'data':['0x'+data.hex()],'gasLimit':['0x0f4240'],'maxFeePerGas':'0x0a','maxPriorityFeePerGas':'0x00',
+            'nonce':'0x00','sender':h(SENDER,20),'to':h(target,20),'value':['0x00']},
+        'post':{'Cancun':[{'indexes':{'data':0,'gas':0,'value':0},
+            'logs':'0x'+logs_hash(logs).hex(),
+            'state':{h(a,20):{'code':'0x'+s['code'].hex(),'nonce':'0x01','storage':{h(k):h(v) for k,v in sorted(s['storage'].items())}} for a,s in state.items() if a!=SENDER}}]}}
+    return {name:fx}
+def write(path,fx):
+    os.makedirs(os.path.dirname(path),exist_ok=True)
+    with open(path,'w') as f: json.dump(fx,f,indent=4); f.write('\n')
+root=os.path.join(os.path.dirname(os.path.abspath(__file__)),'..','fixtures','GeneralStateTests','Cancun')
+
+# EIP-1153: a value written with TSTORE is visible to TLOAD in the same frame and does not touch storage.
+code=asm([0x2a,0,'TSTORE', 0,'TLOAD',0,'SSTORE', 1,'SLOAD',1,'SSTORE', 'STOP'])
+write(root+'/stEIP1153-transientStorage/tloadAfterTstore.json',
+  fixture('tloadAfterTstore','TLOAD returns the value written by TSTORE in the same call frame; persistent slot 1 is left untouched.',{A:(code,{0:0xff,1:0x11})}))
+
+# EIP-1153: transient writes are reverted with the frame, and persist across calls of the same transaction otherwise.
+bcode=asm(['CALLDATASIZE','ISZERO',('ref','revert'),'JUMPI',
+   'CALLDATASIZE',1,'EQ',('ref','set'),'JUMPI',
+   0,'TLOAD',0,'MSTORE',0x20,0,'RETURN',
+   ('label','revert'),'JUMPDEST',7,0,'TSTORE',0,0,'REVERT',
+   ('label','set'),'JUMPDEST',9,0,'TSTORE','STOP'])
+acode=asm(call(B,0)+[0,'SSTORE']
+  +call(B,0x20)+['POP',0,'MLOAD',1,'SSTORE']
+  +call(B,1)+[3,'SSTORE']
+  +call(B,0x20)+['POP',0,'MLOAD',2,'SSTORE','STOP'])
+write(root+'/stEIP1153-transientStorage/transientStorageAcrossCalls.json',
+  fixture('transientStorageAcrossCalls','A reverted TSTORE in a sub call is undone, a committed one is seen by a later call of the same transaction.',{A:(acode,{0:0xff,1:0xff,2:0xff}),B:(bcode,{})}))
+
+# EIP-5656: overlapping MCOPY behaves like memmove, and copies are visible to logs.
+word=int.from_bytes(bytes(range(1,33)),'big')
+code=asm([word.to_bytes(32,'big'),0,'MSTORE', 0x20,0,1,'MCOPY', 0,'MLOAD',0,'SSTORE', 0x20,'MLOAD',1,'SSTORE', 0x5656,0x21,0,'LOG1','STOP'])
+write(root+'/stEIP5656-MCOPY/mcopyOverlapping.json',
+  fixture('mcopyOverlapping','MCOPY with overlapping source and destination copies as if through an intermediate buffer.',{A:(code,{})}))
+
+# EIP-5656: zero length MCOPY at a huge offset does not expand memory.
+code=asm([0,0,b'\xff'*4,'MCOPY','MSIZE',0,'SSTORE', 1,1,'SSTORE','STOP'])
+write(root+'/stEIP5656-MCOPY/mcopyZeroLength.json',
+  fixture('mcopyZeroLength','MCOPY of zero bytes charges no memory expansion, even at an out of range destination.',{A:(code,{0:0xff})}))
+
+# EIP-7516: BLOBBASEFEE is available and, without blob transactions on this chain, returns 0.
+code=asm(['BLOBBASEFEE',0,'SSTORE', 1,1,'SSTORE','STOP'])
+write(root+'/stEIP7516-blobBaseFee/blobBaseFeeIsZero.json',
+  fixture('blobBaseFeeIsZero','BLOBBASEFEE is a valid opcode and returns 0 because the chain has no blob transactions.',{A:(code,{0:0xff})}))
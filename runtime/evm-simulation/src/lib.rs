@@ -0,0 +1,342 @@
+//! # EVM simulation
+//!
+//! Runtime side of `eth_call` with state and block overrides and of `eth_simulateV1`.
+//!
+//! [`simulate`] executes blocks of calls on top of the state of the block the runtime API is
+//! called at. Before the calls of a [`SimulatedBlock`] run, its [`BlockOverrides`] and
+//! [`AccountOverride`]s are written to storage; the calls then go through the runtime's
+//! `pallet_evm::Runner` in order, each one seeing the changes of the ones before it, and the next
+//! block starts from there. Everything happens inside a storage transaction that is always rolled
+//! back, so nothing leaks into the state, not even when the API is called from a block builder.
+//!
+//! Blocks without an explicit number or time follow their parent: one block and two
+//! `MinimumPeriod`s (one slot) later.
+//!
+//! [`estimate_gas`] searches the smallest gas limit with which a call succeeds. The calls run as
+//! transactions do, with the proof size limit derived from the gas limit, so the estimate covers
+//! the proof size of the storage a call reads and writes when `GasLimitPovSizeRatio` makes it
+//! cost more than the EVM gas.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use fp_evm::{CallInfo, CreateInfo, ExitReason};
+use pallet_evm::{AddressMapping, GasWeightMapping, Runner};
+use polkadot_sdk::{
+    frame_support::{
+        storage::{with_transaction, TransactionOutcome},
+        traits::{Currency, Get},
+    },
+    frame_system, pallet_timestamp,
+    sp_core::{H160, H256, U256},
+    sp_runtime::{traits::One, DispatchError, SaturatedConversion},
+};
+use scale_info::TypeInfo;
+
+/// Changes made to an account before the calls of a block run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct AccountOverride {
+    pub address: H160,
+    /// Free balance.
+    pub balance: Option<U256>,
+    pub nonce: Option<U256>,
+    /// Contract code, an empty one removes it.
+    pub code: Option<Vec<u8>>,
+    /// Replaces the whole storage of the account.
+    pub state: Option<Vec<(H256, H256)>>,
+    /// Sets the given slots and keeps the others.
+    pub state_diff: Vec<(H256, H256)>,
+}
+
+/// Block environment of a simulated block, taken from the parent when `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct BlockOverrides {
+    pub number: Option<u64>,
+    /// Timestamp in seconds, as seen by the `TIMESTAMP` opcode.
+    pub time: Option<u64>,
+    pub base_fee_per_gas: Option<U256>,
+}
+
+/// A call, or a contract creation when `to` is `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct SimulatedCall {
+    pub from: H160,
+    pub to: Option<H160>,
+    pub data: Vec<u8>,
+    pub value: U256,
+    pub gas_limit: U256,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub nonce: Option<U256>,
+    pub access_list: Vec<(H160, Vec<H256>)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct SimulatedBlock {
+    pub overrides: BlockOverrides,
+    pub state_overrides: Vec<AccountOverride>,
+    pub calls: Vec<SimulatedCall>,
+}
+
+/// What happened to a call. `Failed` is a call the runner refused to execute, e.g. for a nonce or
+/// a balance too low with validation on; reverts and EVM errors are in the exit reason.
+#[derive(Clone, Debug, Encode, Decode, TypeInfo)]
+pub enum CallOutcome {
+    Call(CallInfo),
+    /// A contract creation and the code it deployed, empty if it failed.
+    Deployed(CreateInfo, Vec<u8>),
+    Failed(DispatchError),
+}
+
+/// What [`estimate_gas`] found.
+#[derive(Clone, Debug, Encode, Decode, TypeInfo)]
+pub struct GasEstimate {
+    /// The smallest gas limit with which the call succeeds, or the limit it was given when it
+    /// fails even with it.
+    pub gas_limit: U256,
+    /// Outcome of the call with `gas_limit`.
+    pub outcome: CallOutcome,
+}
+
+#[derive(Clone, Debug, Encode, Decode, TypeInfo)]
+pub struct SimulatedBlockResult {
+    pub number: u64,
+    /// Timestamp in seconds.
+    pub timestamp: u64,
+    pub base_fee_per_gas: U256,
+    pub calls: Vec<CallOutcome>,
+}
+
+polkadot_sdk::sp_api::decl_runtime_apis! {
+    /// Execute calls against overridden state and block environments.
+    pub trait EthereumSimulationApi {
+        /// Run `blocks` one after the other on top of the state of the block this is called at.
+        ///
+        /// With `validation` the calls are checked as transactions would be: nonces must match and
+        /// the sender must afford `max_fee_per_gas`, which may not be below the base fee.
+        fn simulate(
+            blocks: Vec<SimulatedBlock>,
+            validation: bool,
+        ) -> Result<Vec<SimulatedBlockResult>, DispatchError>;
+
+        /// The smallest gas limit, up to `call.gas_limit`, with which `call` succeeds.
+        fn estimate_gas(call: SimulatedCall) -> Result<GasEstimate, DispatchError>;
+    }
+}
+
+/// Run `blocks` and roll every change back. Fails only when the storage transaction cannot be
+/// opened.
+pub fn simulate<T>(
+    blocks: Vec<SimulatedBlock>,
+    validation: bool,
+) -> Result<Vec<SimulatedBlockResult>, DispatchError>
+where
+    T: pallet_evm::Config + pallet_timestamp::Config + pallet_base_fee::Config,
+{
+    with_transaction(|| {
+        let results = blocks
+            .into_iter()
+            .map(|block| simulate_block::<T>(block, validation))
+            .collect();
+        TransactionOutcome::Rollback(Ok(results))
+    })
+}
+
+/// Binary search of the smallest gas limit with which `call` succeeds, each attempt rolled back.
+pub fn estimate_gas<T: pallet_evm::Config>(
+    call: SimulatedCall,
+) -> Result<GasEstimate, DispatchError> {
+    let attempt = |gas_limit: u64| {
+        with_transaction(|| {
+            let call = SimulatedCall {
+                gas_limit: gas_limit.into(),
+                ..call.clone()
+            };
+            TransactionOutcome::Rollback(Ok::<_, DispatchError>(execute::<T>(call, false)))
+        })
+    };
+
+    let mut high: u64 = call.gas_limit.saturated_into();
+    let mut outcome = attempt(high)?;
+    let Some(used) = used_gas(&outcome) else {
+        return Ok(GasEstimate {
+            gas_limit: high.into(),
+            outcome,
+        });
+    };
+
+    // Below the gas used the call runs out of gas. It usually succeeds with exactly that much,
+    // so that is tried first; refunds and the 63/64 rule of calls may ask for more.
+    let mut low = used.saturating_sub(1);
+    let mut next = used;
+    while low + 1 < high {
+        let attempted = attempt(next)?;
+        if used_gas(&attempted).is_some() {
+            high = next;
+            outcome = attempted;
+        } else {
+            low = next;
+        }
+        next = low + (high - low) / 2;
+    }
+    Ok(GasEstimate {
+        gas_limit: high.into(),
+        outcome,
+    })
+}
+
+/// Gas charged for a call that succeeded, the larger of the EVM gas and the gas of its proof
+/// size.
+fn used_gas(outcome: &CallOutcome) -> Option<u64> {
+    let (reason, used_gas) = match outcome {
+        CallOutcome::Call(info) => (&info.exit_reason, info.used_gas.effective),
+        CallOutcome::Deployed(info, _) => (&info.exit_reason, info.used_gas.effective),
+        CallOutcome::Failed(_) => return None,
+    };
+    matches!(reason, ExitReason::Succeed(_)).then(|| used_gas.saturated_into())
+}
+
+fn simulate_block<T>(block: SimulatedBlock, validation: bool) -> SimulatedBlockResult
+where
+    T: pallet_evm::Config + pallet_timestamp::Config + pallet_base_fee::Config,
+{
+    let SimulatedBlock {
+        overrides,
+        state_overrides,
+        calls,
+    } = block;
+
+    let number = match overrides.number {
+        Some(number) => number.saturated_into(),
+        None => frame_system::Pallet::<T>::block_number() + One::one(),
+    };
+    frame_system::Pallet::<T>::set_block_number(number);
+
+    let timestamp = match overrides.time {
+        Some(seconds) => seconds.saturating_mul(1000),
+        None => {
+            let now: u64 = pallet_timestamp::Now::<T>::get().saturated_into();
+            let period: u64 = T::MinimumPeriod::get().saturated_into();
+            now.saturating_add(period.saturating_mul(2))
+        }
+    };
+    pallet_timestamp::Now::<T>::put(timestamp.saturated_into::<T::Moment>());
+
+    if let Some(base_fee) = overrides.base_fee_per_gas {
+        pallet_base_fee::BaseFeePerGas::<T>::put(base_fee);
+    }
+
+    state_overrides.into_iter().for_each(apply::<T>);
+
+    SimulatedBlockResult {
+        number: number.saturated_into(),
+        timestamp: timestamp / 1000,
+        base_fee_per_gas: pallet_base_fee::BaseFeePerGas::<T>::get(),
+        calls: calls
+            .into_iter()
+            .map(|call| execute::<T>(call, validation))
+            .collect(),
+    }
+}
+
+fn apply<T: pallet_evm::Config>(account: AccountOverride) {
+    let address = account.address;
+    let account_id = T::AddressMapping::into_account_id(address);
+
+    if let Some(balance) = account.balance {
+        let balance: u128 = balance.saturated_into();
+        let _ = <T as pallet_evm::Config>::Currency::make_free_balance_be(
+            &account_id,
+            balance.saturated_into(),
+        );
+    }
+    if let Some(nonce) = account.nonce {
+        let nonce: u128 = nonce.saturated_into();
+        frame_system::Account::<T>::mutate(&account_id, |info| info.nonce = nonce.saturated_into());
+    }
+    if let Some(code) = account.code {
+        pallet_evm::AccountCodesMetadata::<T>::remove(address);
+        if code.is_empty() {
+            pallet_evm::AccountCodes::<T>::remove(address);
+        } else {
+            pallet_evm::AccountCodes::<T>::insert(address, code);
+        }
+    }
+    if let Some(state) = account.state {
+        let _ = pallet_evm::AccountStorages::<T>::clear_prefix(address, u32::MAX, None);
+        for (key, value) in state {
+            pallet_evm::AccountStorages::<T>::insert(address, key, value);
+        }
+    }
+    for (key, value) in account.state_diff {
+        pallet_evm::AccountStorages::<T>::insert(address, key, value);
+    }
+}
+
+fn execute<T: pallet_evm::Config>(call: SimulatedCall, validation: bool) -> CallOutcome {
+    let gas_limit: u64 = call.gas_limit.saturated_into();
+
+    // Same estimate of the encoded transaction as `EthereumRuntimeRPCApi::call` and `create`, the
+    // EIP-1559 envelope being the heaviest.
+    let transaction_len = call.data.len()
+        + if call.to.is_some() { 258 } else { 190 }
+        + call.access_list.encoded_size();
+
+    let (weight_limit, proof_size_base_cost) =
+        match T::GasWeightMapping::gas_to_weight(gas_limit, true) {
+            weight_limit if weight_limit.proof_size() > 0 => {
+                (Some(weight_limit), Some(transaction_len as u64))
+            }
+            _ => (None, None),
+        };
+
+    let outcome = match call.to {
+        Some(to) => T::Runner::call(
+            call.from,
+            to,
+            call.data,
+            call.value,
+            gas_limit,
+            call.max_fee_per_gas,
+            call.max_priority_fee_per_gas,
+            call.nonce,
+            call.access_list,
+            validation,
+            true,
+            weight_limit,
+            proof_size_base_cost,
+            T::config(),
+        )
+        .map(CallOutcome::Call),
+        None => T::Runner::create(
+            call.from,
+            call.data,
+            call.value,
+            gas_limit,
+            call.max_fee_per_gas,
+            call.max_priority_fee_per_gas,
+            call.nonce,
+            call.access_list,
+            validation,
+            true,
+            weight_limit,
+            proof_size_base_cost,
+            T::config(),
+        )
+        // The code is read before the storage transaction around the calls is rolled back.
+        .map(|info| {
+            let code = pallet_evm::AccountCodes::<T>::get(info.value);
+            CallOutcome::Deployed(info, code)
+        }),
+    };
+
+    outcome.unwrap_or_else(|err| CallOutcome::Failed(err.error.into()))
+}
@@ -0,0 +1,304 @@
+use crate::{
+    estimate_gas, mock::*, simulate, AccountOverride, BlockOverrides, CallOutcome, SimulatedBlock,
+    SimulatedCall,
+};
+use fp_evm::ExitReason;
+use polkadot_sdk::sp_core::{H160, H256, U256};
+
+// SLOAD(0) / SLOAD(1), returned as a word.
+const LOAD_SLOT_0: &[u8] = &[
+    0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+];
+const LOAD_SLOT_1: &[u8] = &[
+    0x60, 0x01, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+];
+// TIMESTAMP and NUMBER, returned as two words.
+const ENVIRONMENT: &[u8] = &[
+    0x42, 0x60, 0x00, 0x52, 0x43, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+];
+
+// Deploys the one byte code TIMESTAMP.
+const DEPLOY_TIMESTAMP: &[u8] = &[0x60, 0x42, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+
+// SLOAD of the slots 0 to `slots - 1`, each one cold.
+fn cold_loads(slots: u8) -> Vec<u8> {
+    (0..slots)
+        .flat_map(|slot| [0x60, slot, 0x54, 0x50])
+        .collect()
+}
+
+fn addr(n: u64) -> H160 {
+    H160::from_low_u64_be(n)
+}
+
+fn word(n: u64) -> H256 {
+    H256::from_low_u64_be(n)
+}
+
+fn call(to: H160) -> SimulatedCall {
+    SimulatedCall {
+        from: addr(1),
+        to: Some(to),
+        gas_limit: U256::from(100_000),
+        ..Default::default()
+    }
+}
+
+fn contract(address: H160, code: &[u8]) -> AccountOverride {
+    AccountOverride {
+        address,
+        code: Some(code.to_vec()),
+        ..Default::default()
+    }
+}
+
+fn returned(outcome: &CallOutcome) -> Vec<u8> {
+    match outcome {
+        CallOutcome::Call(info) => {
+            assert!(
+                matches!(info.exit_reason, ExitReason::Succeed(_)),
+                "{info:?}"
+            );
+            info.value.clone()
+        }
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+}
+
+#[test]
+fn overrides_are_seen_by_the_calls_and_rolled_back() {
+    new_test_ext().execute_with(|| {
+        let block = SimulatedBlock {
+            state_overrides: vec![
+                AccountOverride {
+                    state: Some(vec![(word(0), word(42))]),
+                    ..contract(addr(2), LOAD_SLOT_0)
+                },
+                AccountOverride {
+                    address: addr(1),
+                    balance: Some(U256::from(1_000_000)),
+                    nonce: Some(U256::from(5)),
+                    ..Default::default()
+                },
+            ],
+            calls: vec![call(addr(2))],
+            ..Default::default()
+        };
+
+        let results = simulate::<Test>(vec![block], false).unwrap();
+        assert_eq!(returned(&results[0].calls[0]), word(42).as_bytes());
+
+        assert!(!pallet_evm::AccountCodes::<Test>::contains_key(addr(2)));
+        assert_eq!(
+            pallet_evm::AccountStorages::<Test>::get(addr(2), word(0)),
+            H256::zero()
+        );
+        assert_eq!(Balances::free_balance(addr(1)), 0);
+        assert_eq!(System::account_nonce(addr(1)), 0);
+    });
+}
+
+#[test]
+fn creates_return_the_deployed_code() {
+    new_test_ext().execute_with(|| {
+        let create = SimulatedCall {
+            to: None,
+            data: DEPLOY_TIMESTAMP.to_vec(),
+            ..call(addr(0))
+        };
+        let block = SimulatedBlock {
+            calls: vec![create],
+            ..Default::default()
+        };
+
+        let results = simulate::<Test>(vec![block], false).unwrap();
+        let CallOutcome::Deployed(info, code) = &results[0].calls[0] else {
+            panic!("unexpected outcome {:?}", results[0].calls[0]);
+        };
+        assert!(
+            matches!(info.exit_reason, ExitReason::Succeed(_)),
+            "{info:?}"
+        );
+        assert_eq!(code, &[0x42]);
+        assert!(!pallet_evm::AccountCodes::<Test>::contains_key(info.value));
+    });
+}
+
+#[test]
+fn state_replaces_the_storage_and_state_diff_patches_it() {
+    new_test_ext().execute_with(|| {
+        pallet_evm::AccountStorages::<Test>::insert(addr(2), word(1), word(7));
+
+        let patched = SimulatedBlock {
+            state_overrides: vec![AccountOverride {
+                state_diff: vec![(word(0), word(42))],
+                ..contract(addr(2), LOAD_SLOT_1)
+            }],
+            calls: vec![call(addr(2))],
+            ..Default::default()
+        };
+        let replaced = SimulatedBlock {
+            state_overrides: vec![AccountOverride {
+                address: addr(2),
+                state: Some(vec![(word(0), word(42))]),
+                ..Default::default()
+            }],
+            calls: vec![call(addr(2))],
+            ..Default::default()
+        };
+
+        let results = simulate::<Test>(vec![patched, replaced], false).unwrap();
+        assert_eq!(returned(&results[0].calls[0]), word(7).as_bytes());
+        assert_eq!(returned(&results[1].calls[0]), word(0).as_bytes());
+
+        assert_eq!(
+            pallet_evm::AccountStorages::<Test>::get(addr(2), word(1)),
+            word(7)
+        );
+    });
+}
+
+#[test]
+fn blocks_follow_their_parent_unless_overridden() {
+    new_test_ext().execute_with(|| {
+        let block = |overrides| SimulatedBlock {
+            overrides,
+            state_overrides: vec![contract(addr(2), ENVIRONMENT)],
+            calls: vec![call(addr(2))],
+        };
+        let overridden = BlockOverrides {
+            number: Some(100),
+            time: Some(50),
+            base_fee_per_gas: Some(U256::from(7)),
+        };
+
+        let results = simulate::<Test>(
+            vec![
+                block(Default::default()),
+                block(overridden),
+                block(Default::default()),
+            ],
+            false,
+        )
+        .unwrap();
+
+        let environment = results
+            .iter()
+            .map(|result| {
+                (
+                    result.timestamp,
+                    result.number,
+                    result.base_fee_per_gas.as_u64(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            environment,
+            vec![(12, 2, 1_000_000_000), (50, 100, 7), (56, 101, 7)]
+        );
+
+        for result in &results {
+            let mut expected = word(result.timestamp).as_bytes().to_vec();
+            expected.extend_from_slice(word(result.number).as_bytes());
+            assert_eq!(returned(&result.calls[0]), expected);
+        }
+
+        assert_eq!(System::block_number(), 1);
+        assert_eq!(Timestamp::get(), 6_000);
+    });
+}
+
+#[test]
+fn validation_checks_calls_as_transactions() {
+    new_test_ext().execute_with(|| {
+        let priced = SimulatedCall {
+            max_fee_per_gas: Some(U256::from(1_000_000_000)),
+            ..call(addr(3))
+        };
+        let block = |state_overrides| SimulatedBlock {
+            state_overrides,
+            calls: vec![priced.clone()],
+            ..Default::default()
+        };
+        let funded = AccountOverride {
+            address: addr(1),
+            balance: Some(U256::from(u64::MAX)),
+            ..Default::default()
+        };
+
+        let results = simulate::<Test>(vec![block(vec![])], true).unwrap();
+        assert!(matches!(results[0].calls[0], CallOutcome::Failed(_)));
+
+        let results = simulate::<Test>(vec![block(vec![funded])], true).unwrap();
+        returned(&results[0].calls[0]);
+
+        let results = simulate::<Test>(vec![block(vec![])], false).unwrap();
+        assert!(matches!(results[0].calls[0], CallOutcome::Failed(_)));
+        let unpriced = SimulatedBlock {
+            calls: vec![call(addr(3))],
+            ..Default::default()
+        };
+        let results = simulate::<Test>(vec![unpriced], false).unwrap();
+        returned(&results[0].calls[0]);
+    });
+}
+
+#[test]
+fn estimates_cover_the_proof_size_of_cold_storage() {
+    for ratio in [0, 64] {
+        new_test_ext().execute_with(|| {
+            GasLimitPovSizeRatio::set(&ratio);
+            pallet_evm::AccountCodes::<Test>::insert(addr(2), cold_loads(100));
+            let call = SimulatedCall {
+                gas_limit: U256::from(10_000_000),
+                ..call(addr(2))
+            };
+
+            let estimate = estimate_gas::<Test>(call.clone()).unwrap();
+            let CallOutcome::Call(info) = &estimate.outcome else {
+                panic!("unexpected outcome {:?}", estimate.outcome);
+            };
+            assert!(
+                matches!(info.exit_reason, ExitReason::Succeed(_)),
+                "{info:?}"
+            );
+            assert_eq!(estimate.gas_limit > info.used_gas.standard, ratio > 0);
+
+            let with = |gas_limit: U256| SimulatedBlock {
+                calls: vec![SimulatedCall {
+                    gas_limit,
+                    ..call.clone()
+                }],
+                ..Default::default()
+            };
+            let results = simulate::<Test>(
+                vec![with(estimate.gas_limit), with(estimate.gas_limit - 1)],
+                false,
+            )
+            .unwrap();
+            returned(&results[0].calls[0]);
+            assert!(matches!(
+                &results[1].calls[0],
+                CallOutcome::Call(info) if !matches!(info.exit_reason, ExitReason::Succeed(_))
+            ));
+        });
+    }
+}
+
+#[test]
+fn estimates_of_failing_calls_keep_their_limit() {
+    new_test_ext().execute_with(|| {
+        pallet_evm::AccountCodes::<Test>::insert(addr(2), cold_loads(100));
+        let call = SimulatedCall {
+            gas_limit: U256::from(30_000),
+            ..call(addr(2))
+        };
+
+        let estimate = estimate_gas::<Test>(call).unwrap();
+        assert_eq!(estimate.gas_limit, U256::from(30_000));
+        assert!(matches!(
+            estimate.outcome,
+            CallOutcome::Call(info) if matches!(info.exit_reason, ExitReason::Error(_))
+        ));
+    });
+}
@@ -0,0 +1,127 @@
+//! Hardfork selection for the EVM.
+//!
+//! Frontier defaults to the Shanghai rules. Cancun (`TSTORE`/`TLOAD`, `MCOPY`, `BLOBBASEFEE`, the
+//! EIP-6780 `SELFDESTRUCT`) is switched on from `dynamic_params::evm::CancunActivationBlock`
+//! onwards, so every node flips at the same block once governance schedules it.
+
+use super::*;
+use fp_evm::Config as EvmConfig;
+
+/// Rules used before the Cancun activation block.
+pub static SHANGHAI_CONFIG: EvmConfig = EvmConfig::shanghai();
+
+/// Rules used from the Cancun activation block onwards.
+pub static CANCUN_CONFIG: EvmConfig = EvmConfig::cancun();
+
+/// The EVM rules in force at block `number`.
+pub fn config_at(number: BlockNumber) -> &'static EvmConfig {
+    if number >= dynamic_params::evm::CancunActivationBlock::get() {
+        &CANCUN_CONFIG
+    } else {
+        &SHANGHAI_CONFIG
+    }
+}
+
+/// The EVM rules in force for the block being built or queried.
+pub fn active() -> &'static EvmConfig {
+    config_at(System::block_number())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `PUSH1 42 PUSH1 0 TSTORE PUSH1 0 TLOAD PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN`
+    const TRANSIENT_STORAGE_CODE: [u8; 16] = [
+        0x60, 0x2a, 0x60, 0x00, 0x5d, 0x60, 0x00, 0x5c, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00,
+        0xf3,
+    ];
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        let storage = frame_system::GenesisConfig::<Runtime>::default()
+            .build_storage()
+            .unwrap();
+        let mut ext = sp_io::TestExternalities::new(storage);
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+
+    fn call(target: H160, config: &EvmConfig) -> pallet_evm::CallInfo {
+        <Runtime as pallet_evm::Config>::Runner::call(
+            H160::repeat_byte(0x11),
+            target,
+            Vec::new(),
+            U256::zero(),
+            1_000_000,
+            None,
+            None,
+            None,
+            Vec::new(),
+            false,
+            false,
+            None,
+            None,
+            config,
+        )
+        .map_err(|e| e.error.into())
+        .unwrap_or_else(|e: sp_runtime::DispatchError| panic!("runner failed: {e:?}"))
+    }
+
+    #[test]
+    fn cancun_is_off_until_scheduled() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                dynamic_params::evm::CancunActivationBlock::get(),
+                BlockNumber::MAX
+            );
+            assert!(core::ptr::eq(active(), &SHANGHAI_CONFIG));
+            assert!(core::ptr::eq(
+                <Runtime as pallet_evm::Config>::config(),
+                &SHANGHAI_CONFIG
+            ));
+        });
+    }
+
+    #[test]
+    fn cancun_activates_at_the_scheduled_block() {
+        new_test_ext().execute_with(|| {
+            frame_support::assert_ok!(Parameters::set_parameter(
+                RuntimeOrigin::root(),
+                RuntimeParameters::Evm(dynamic_params::evm::Parameters::CancunActivationBlock(
+                    dynamic_params::evm::CancunActivationBlock,
+                    Some(10),
+                )),
+            ));
+            assert!(core::ptr::eq(config_at(9), &SHANGHAI_CONFIG));
+            assert!(core::ptr::eq(config_at(10), &CANCUN_CONFIG));
+
+            System::set_block_number(10);
+            assert!(core::ptr::eq(
+                <Runtime as pallet_evm::Config>::config(),
+                &CANCUN_CONFIG
+            ));
+        });
+    }
+
+    #[test]
+    fn transient_storage_requires_cancun() {
+        new_test_ext().execute_with(|| {
+            let target = H160::repeat_byte(0x22);
+            pallet_evm::Pallet::<Runtime>::create_account(target, TRANSIENT_STORAGE_CODE.to_vec());
+
+            let info = call(target, &SHANGHAI_CONFIG);
+            assert!(!info.exit_reason.is_succeed());
+
+            let info = call(target, &CANCUN_CONFIG);
+            assert!(info.exit_reason.is_succeed());
+            assert_eq!(U256::from_big_endian(&info.value), U256::from(42));
+        });
+    }
+    #[test]
+    fn cancun_general_state_tests() {
+        evm_conformance::general_state_tests::<Runtime>(
+            &CANCUN_CONFIG,
+            evm_conformance::system_genesis::<Runtime>,
+        );
+    }
+}
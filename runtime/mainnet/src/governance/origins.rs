@@ -0,0 +1,69 @@
+//! Custom origins for governance interventions.
+
+pub use pallet_custom_origins::*;
+
+#[frame_support::pallet]
+pub mod pallet_custom_origins {
+    use polkadot_sdk::{frame_support, frame_support::pallet_prelude::*, frame_system};
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {}
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[derive(PartialEq, Eq, Clone, MaxEncodedLen, Encode, Decode, TypeInfo, RuntimeDebug)]
+    #[pallet::origin]
+    pub enum Origin {
+        /// Origin able to spend from the treasury and manage asset rates.
+        Treasurer,
+        /// Origin for cancelling slashes and managing election and nomination pool settings.
+        StakingAdmin,
+        /// Origin for EVM administration: pausing calls and managing EVM-specific pallets.
+        EvmAdmin,
+        /// Origin for general administration of the chain, such as storage deposit parameters
+        /// and whitelisting calls for the `whitelisted_caller` track.
+        GeneralAdmin,
+        /// Origin able to cancel and kill referenda.
+        ReferendumCanceller,
+        /// Origin able to dispatch a whitelisted call.
+        WhitelistedCaller,
+    }
+
+    macro_rules! decl_unit_ensures {
+        ( $name:ident: $success_type:ty = $success:expr ) => {
+            pub struct $name;
+            impl<O: Into<Result<Origin, O>> + From<Origin>> EnsureOrigin<O> for $name {
+                type Success = $success_type;
+                fn try_origin(o: O) -> Result<Self::Success, O> {
+                    o.into().and_then(|o| match o {
+                        Origin::$name => Ok($success),
+                        r => Err(O::from(r)),
+                    })
+                }
+                #[cfg(feature = "runtime-benchmarks")]
+                fn try_successful_origin() -> Result<O, ()> {
+                    Ok(O::from(Origin::$name))
+                }
+            }
+        };
+        ( $name:ident ) => { decl_unit_ensures! { $name : () = () } };
+        ( $name:ident: $success_type:ty = $success:expr, $( $rest:tt )* ) => {
+            decl_unit_ensures! { $name: $success_type = $success }
+            decl_unit_ensures! { $( $rest )* }
+        };
+        ( $name:ident, $( $rest:tt )* ) => {
+            decl_unit_ensures! { $name }
+            decl_unit_ensures! { $( $rest )* }
+        };
+        () => {}
+    }
+    decl_unit_ensures!(
+        Treasurer,
+        StakingAdmin,
+        EvmAdmin,
+        GeneralAdmin,
+        ReferendumCanceller,
+        WhitelistedCaller,
+    );
+}
@@ -48,9 +48,9 @@ use frame_support::{
             GetSalary, PayFromAccount,
         },
         AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU16, ConstU32, Contains, Currency,
-        EitherOfDiverse, EnsureOriginWithArg, EqualPrivilegeOnly, Imbalance, InsideBoth,
-        InstanceFilter, KeyOwnerProofSystem, LinearStoragePrice, LockIdentifier, Nothing,
-        OnFinalize, OnUnbalanced, WithdrawReasons,
+        EitherOfDiverse, EnsureOrigin, EnsureOriginWithArg, EqualPrivilegeOnly, Imbalance,
+        InsideBoth, InstanceFilter, KeyOwnerProofSystem, LinearStoragePrice, LockIdentifier,
+        Nothing, OnFinalize, OnUnbalanced, WithdrawReasons,
     },
     weights::{
         constants::{
@@ -106,6 +106,8 @@ use pallet_ethereum::{Call::transact, Transaction as EthereumTransaction};
 use pallet_evm::{EnsureAccountId20, IdentityAddressMapping};
 mod precompiles;
 use frame_support::traits::FindAuthor;
+use pallet_evm_circuit_breaker::{CircuitBreakerPrecompiles, CircuitBreakerRunner};
+use pallet_evm_deployment_control::DeploymentControlRunner;
 use precompiles::FrontierPrecompiles;
 use sp_runtime::{
     create_runtime_str,
@@ -156,6 +158,19 @@ mod voter_bags;
 /// Runtime API definition for assets.
 pub mod assets_api;
 
+/// OpenGov origins, tracks and migrations.
+pub mod governance;
+
+/// Hardfork selection for the EVM.
+pub mod evm_config;
+
+/// Canonical EVM contracts deployed at genesis.
+pub mod predeploys;
+use governance::{
+    pallet_custom_origins, EvmAdminOrRoot, GeneralAdminOrRoot, ReferendumCancellerOrRoot,
+    StakingAdminOrRoot, TracksInfo, TreasurerOrRoot, TreasurySpender, WhitelistedCallerOrRoot,
+};
+
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -262,7 +277,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to 0. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 272,
+    spec_version: 282,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
     transaction_version: 2,
@@ -346,7 +361,10 @@ pub struct SafeModeWhitelistedCalls;
 impl Contains<RuntimeCall> for SafeModeWhitelistedCalls {
     fn contains(call: &RuntimeCall) -> bool {
         match call {
-            RuntimeCall::System(_) | RuntimeCall::SafeMode(_) | RuntimeCall::TxPause(_) => true,
+            RuntimeCall::System(_)
+            | RuntimeCall::SafeMode(_)
+            | RuntimeCall::TxPause(_)
+            | RuntimeCall::EvmCircuitBreaker(_) => true,
             _ => false,
         }
     }
@@ -354,11 +372,12 @@ impl Contains<RuntimeCall> for SafeModeWhitelistedCalls {
 
 /// Calls that cannot be paused by the tx-pause pallet.
 pub struct TxPauseWhitelistedCalls;
-/// Whitelist `Balances::transfer_keep_alive`, all others are pauseable.
+/// Whitelist `Balances::transfer_keep_alive` and the EVM circuit breaker, all others are pauseable.
 impl Contains<RuntimeCallNameOf<Runtime>> for TxPauseWhitelistedCalls {
     fn contains(full_name: &RuntimeCallNameOf<Runtime>) -> bool {
         match (full_name.0.as_slice(), full_name.1.as_slice()) {
             (b"Balances", b"transfer_keep_alive") => true,
+            (b"EvmCircuitBreaker", _) => true,
             _ => false,
         }
     }
@@ -367,8 +386,8 @@ impl Contains<RuntimeCallNameOf<Runtime>> for TxPauseWhitelistedCalls {
 impl pallet_tx_pause::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type RuntimeCall = RuntimeCall;
-    type PauseOrigin = EnsureRoot<AccountId>;
-    type UnpauseOrigin = EnsureRoot<AccountId>;
+    type PauseOrigin = EvmAdminOrRoot;
+    type UnpauseOrigin = EvmAdminOrRoot;
     type WhitelistedCalls = TxPauseWhitelistedCalls;
     type MaxNameLen = ConstU32<256>;
     type WeightInfo = pallet_tx_pause::weights::SubstrateWeight<Runtime>;
@@ -626,7 +645,7 @@ impl pallet_base_fee::Config for Runtime {
 parameter_types! {
     pub BlockGasLimit: U256 = U256::from(BLOCK_GAS_LIMIT);
     pub const GasLimitPovSizeRatio: u64 = BLOCK_GAS_LIMIT.saturating_div(MAX_POV_SIZE);
-    pub PrecompilesValue: FrontierPrecompiles<Runtime> = FrontierPrecompiles::<_>::new();
+    pub PrecompilesValue: Precompiles = CircuitBreakerPrecompiles::new(FrontierPrecompiles::<_>::new());
     pub WeightPerGas: Weight = Weight::from_parts(weight_per_gas(BLOCK_GAS_LIMIT, NORMAL_DISPATCH_RATIO, MILLISECS_PER_BLOCK), 0);
     pub SuicideQuickClearLimit: u32 = 0;
 }
@@ -641,11 +660,14 @@ impl pallet_evm::Config for Runtime {
     type AddressMapping = IdentityAddressMapping;
     type Currency = Balances;
     type RuntimeEvent = RuntimeEvent;
-    type PrecompilesType = FrontierPrecompiles<Self>;
+    type PrecompilesType = Precompiles;
     type PrecompilesValue = PrecompilesValue;
     type ChainId = EVMChainId;
     type BlockGasLimit = BlockGasLimit;
-    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type Runner = CircuitBreakerRunner<
+        Self,
+        DeploymentControlRunner<Self, pallet_evm::runner::stack::Runner<Self>>,
+    >;
     type OnChargeTransaction = ();
     type OnCreate = ();
     type FindAuthor = FindAuthorTruncated<Babe>;
@@ -653,6 +675,34 @@ impl pallet_evm::Config for Runtime {
     type SuicideQuickClearLimit = SuicideQuickClearLimit;
     type Timestamp = Timestamp;
     type WeightInfo = pallet_evm::weights::SubstrateWeight<Self>;
+
+    fn config() -> &'static fp_evm::Config {
+        evm_config::active()
+    }
+}
+
+/// The precompile set, with frozen addresses reverting ahead of it.
+pub type Precompiles = CircuitBreakerPrecompiles<Runtime, FrontierPrecompiles<Runtime>>;
+
+parameter_types! {
+    pub const MaxFrozenEvmAddresses: u32 = 256;
+}
+
+impl pallet_evm_circuit_breaker::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type AdminOrigin = EvmAdminOrRoot;
+    type MaxFrozen = MaxFrozenEvmAddresses;
+}
+
+impl pallet_evm_deployment_control::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type AdminOrigin = EvmAdminOrRoot;
+}
+
+impl pallet_evm_predeploys::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type AdminOrigin = EvmAdminOrRoot;
+    type Predeploys = predeploys::BundledPredeploys;
 }
 
 impl pallet_preimage::Config for Runtime {
@@ -871,7 +921,7 @@ impl pallet_staking::Config for Runtime {
     type SlashDeferDuration = SlashDeferDuration;
     /// A super-majority of the council can cancel the slash.
     type AdminOrigin = EitherOfDiverse<
-        EnsureRoot<AccountId>,
+        StakingAdminOrRoot,
         pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 3, 4>,
     >;
     type SessionInterface = Self;
@@ -895,7 +945,7 @@ impl pallet_staking::Config for Runtime {
 
 impl pallet_fast_unstake::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
-    type ControlOrigin = frame_system::EnsureRoot<AccountId>;
+    type ControlOrigin = StakingAdminOrRoot;
     type BatchSize = ConstU32<64>;
     type Deposit = ConstU128<{ DOLLARS }>;
     type Currency = Balances;
@@ -1052,7 +1102,7 @@ impl pallet_election_provider_multi_phase::Config for Runtime {
     type Fallback = onchain::OnChainExecution<OnChainSeqPhragmen>;
     type GovernanceFallback = onchain::OnChainExecution<OnChainSeqPhragmen>;
     type Solver = SequentialPhragmen<AccountId, SolutionAccuracyOf<Self>, OffchainRandomBalancing>;
-    type ForceOrigin = EnsureRootOrHalfCouncil;
+    type ForceOrigin = EitherOfDiverse<EnsureRootOrHalfCouncil, governance::StakingAdmin>;
     type MaxWinners = MaxActiveValidators;
     type ElectionBounds = ElectionBoundsMultiPhase;
     type BenchmarkingConfig = ElectionProviderBenchmarkConfig;
@@ -1109,7 +1159,7 @@ impl pallet_nomination_pools::Config for Runtime {
     type PalletId = NominationPoolsPalletId;
     type MaxPointsToBalance = MaxPointsToBalance;
     type AdminOrigin = EitherOfDiverse<
-        EnsureRoot<AccountId>,
+        StakingAdminOrRoot,
         pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 3, 4>,
     >;
 }
@@ -1134,47 +1184,7 @@ parameter_types! {
     pub const UndecidingTimeout: BlockNumber = 28 * DAYS;
 }
 
-pub struct TracksInfo;
-impl pallet_referenda::TracksInfo<Balance, BlockNumber> for TracksInfo {
-    type Id = u16;
-    type RuntimeOrigin = <RuntimeOrigin as frame_support::traits::OriginTrait>::PalletsOrigin;
-    fn tracks() -> &'static [(Self::Id, pallet_referenda::TrackInfo<Balance, BlockNumber>)] {
-        static DATA: [(u16, pallet_referenda::TrackInfo<Balance, BlockNumber>); 1] = [(
-            0u16,
-            pallet_referenda::TrackInfo {
-                name: "root",
-                max_deciding: 1,
-                decision_deposit: 10,
-                prepare_period: 4,
-                decision_period: 4,
-                confirm_period: 2,
-                min_enactment_period: 4,
-                min_approval: pallet_referenda::Curve::LinearDecreasing {
-                    length: Perbill::from_percent(100),
-                    floor: Perbill::from_percent(50),
-                    ceil: Perbill::from_percent(100),
-                },
-                min_support: pallet_referenda::Curve::LinearDecreasing {
-                    length: Perbill::from_percent(100),
-                    floor: Perbill::from_percent(0),
-                    ceil: Perbill::from_percent(100),
-                },
-            },
-        )];
-        &DATA[..]
-    }
-    fn track_for(id: &Self::RuntimeOrigin) -> Result<Self::Id, ()> {
-        if let Ok(system_origin) = frame_system::RawOrigin::try_from(id.clone()) {
-            match system_origin {
-                frame_system::RawOrigin::Root => Ok(0),
-                _ => Err(()),
-            }
-        } else {
-            Err(())
-        }
-    }
-}
-pallet_referenda::impl_tracksinfo_get!(TracksInfo, Balance, BlockNumber);
+impl pallet_custom_origins::Config for Runtime {}
 
 impl pallet_referenda::Config for Runtime {
     type WeightInfo = pallet_referenda::weights::SubstrateWeight<Self>;
@@ -1183,8 +1193,8 @@ impl pallet_referenda::Config for Runtime {
     type Scheduler = Scheduler;
     type Currency = pallet_balances::Pallet<Self>;
     type SubmitOrigin = EnsureSigned<AccountId>;
-    type CancelOrigin = EnsureRoot<AccountId>;
-    type KillOrigin = EnsureRoot<AccountId>;
+    type CancelOrigin = ReferendumCancellerOrRoot;
+    type KillOrigin = ReferendumCancellerOrRoot;
     type Slash = ();
     type Votes = pallet_conviction_voting::VotesOf<Runtime>;
     type Tally = pallet_conviction_voting::TallyOf<Runtime>;
@@ -1440,7 +1450,7 @@ impl pallet_treasury::Config for Runtime {
     type SpendFunds = Bounties;
     type WeightInfo = pallet_treasury::weights::SubstrateWeight<Runtime>;
     type MaxApprovals = MaxApprovals;
-    type SpendOrigin = EnsureWithSuccess<EnsureRoot<AccountId>, AccountId, MaxBalance>;
+    type SpendOrigin = TreasurySpender;
     type AssetKind = u32;
     type Beneficiary = AccountId;
     type BeneficiaryLookup = sp_runtime::traits::IdentityLookup<AccountId>;
@@ -1452,9 +1462,9 @@ impl pallet_treasury::Config for Runtime {
 }
 
 impl pallet_asset_rate::Config for Runtime {
-    type CreateOrigin = EnsureRoot<AccountId>;
-    type RemoveOrigin = EnsureRoot<AccountId>;
-    type UpdateOrigin = EnsureRoot<AccountId>;
+    type CreateOrigin = TreasurerOrRoot;
+    type RemoveOrigin = TreasurerOrRoot;
+    type UpdateOrigin = TreasurerOrRoot;
     type Currency = Balances;
     type AssetKind = u32;
     type RuntimeEvent = RuntimeEvent;
@@ -2147,8 +2157,8 @@ impl pallet_transaction_storage::Config for Runtime {
 impl pallet_whitelist::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type RuntimeCall = RuntimeCall;
-    type WhitelistOrigin = EnsureRoot<AccountId>;
-    type DispatchWhitelistedOrigin = EnsureRoot<AccountId>;
+    type WhitelistOrigin = GeneralAdminOrRoot;
+    type DispatchWhitelistedOrigin = WhitelistedCallerOrRoot;
     type Preimages = Preimage;
     type WeightInfo = pallet_whitelist::weights::SubstrateWeight<Runtime>;
 }
@@ -2244,13 +2254,16 @@ impl frame_benchmarking_pallet_pov::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
 }
 
+// Statements are validated by `statement_validation`: signed by Ethereum keys, with an allowance
+// of one statement per `StatementCost` and one byte per `StatementByteCost` of native balance,
+// and none below `StatementCost`. The maximums leave room for the orders of an order book.
 parameter_types! {
     pub StatementCost: Balance = 1 * DOLLARS;
     pub StatementByteCost: Balance = 100 * MILLICENTS;
     pub const MinAllowedStatements: u32 = 4;
-    pub const MaxAllowedStatements: u32 = 10;
+    pub const MaxAllowedStatements: u32 = 256;
     pub const MinAllowedBytes: u32 = 1024;
-    pub const MaxAllowedBytes: u32 = 4096;
+    pub const MaxAllowedBytes: u32 = 256 * 1024;
 }
 
 impl pallet_statement::Config for Runtime {
@@ -2381,6 +2394,16 @@ pub mod dynamic_params {
         #[codec(index = 1)]
         pub static ByteDeposit: Balance = 1 * CENTS;
     }
+
+    #[dynamic_pallet_params]
+    #[codec(index = 1)]
+    pub mod evm {
+        /// First block executed with the Cancun rules, see [`crate::evm_config`].
+        ///
+        /// Defaults to never; an `evm_admin` referendum schedules it at a future block.
+        #[codec(index = 0)]
+        pub static CancunActivationBlock: BlockNumber = BlockNumber::MAX;
+    }
 }
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -2403,7 +2426,11 @@ impl EnsureOriginWithArg<RuntimeOrigin, RuntimeParametersKey> for DynamicParamet
     ) -> Result<Self::Success, RuntimeOrigin> {
         match key {
             RuntimeParametersKey::Storage(_) => {
-                frame_system::ensure_root(origin.clone()).map_err(|_| origin)?;
+                GeneralAdminOrRoot::ensure_origin(origin.clone()).map_err(|_| origin)?;
+                return Ok(());
+            }
+            RuntimeParametersKey::Evm(_) => {
+                EvmAdminOrRoot::ensure_origin(origin.clone()).map_err(|_| origin)?;
                 return Ok(());
             }
         }
@@ -2695,6 +2722,18 @@ mod runtime {
 
     #[runtime::pallet_index(83)]
     pub type BaseFee = pallet_base_fee;
+
+    #[runtime::pallet_index(84)]
+    pub type Origins = pallet_custom_origins;
+
+    #[runtime::pallet_index(85)]
+    pub type EvmCircuitBreaker = pallet_evm_circuit_breaker;
+
+    #[runtime::pallet_index(86)]
+    pub type EvmDeploymentControl = pallet_evm_deployment_control;
+
+    #[runtime::pallet_index(87)]
+    pub type EvmPredeploys = pallet_evm_predeploys;
 }
 
 // We don't have a limit in the Relay Chain.
@@ -2703,6 +2742,8 @@ const IDENTITY_MIGRATION_KEY_LIMIT: u64 = u64::MAX;
 // All migrations executed on runtime upgrade as a nested tuple of types implementing
 // `OnRuntimeUpgrade`. Note: These are examples and do not need to be run directly
 // after the genesis block.
+// `governance::migrations::RemoveSudo` joins this tuple in the release that drops `Sudo` from
+// `construct_runtime!`.
 type Migrations = (
     pallet_nomination_pools::migration::versioned::V6ToV7<Runtime>,
     pallet_alliance::migration::Migration<Runtime>,
@@ -2738,6 +2779,25 @@ impl<B: BlockT> fp_rpc::ConvertTransaction<<B as BlockT>::Extrinsic> for Transac
     }
 }
 
+/// Runtime level checks on Ethereum transactions, ahead of `pallet_ethereum`'s own validation.
+fn validate_ethereum_transaction(
+    call: &pallet_ethereum::Call<Runtime>,
+    source: &H160,
+) -> Result<(), TransactionValidityError> {
+    EvmCircuitBreaker::validate_source(source)?;
+    if let pallet_ethereum::Call::transact { transaction } = call {
+        let (action, input) = match transaction {
+            EthereumTransaction::Legacy(t) => (&t.action, &t.input),
+            EthereumTransaction::EIP2930(t) => (&t.action, &t.input),
+            EthereumTransaction::EIP1559(t) => (&t.action, &t.input),
+        };
+        if let pallet_ethereum::TransactionAction::Create = action {
+            EvmDeploymentControl::validate_create(source, input)?;
+        }
+    }
+    Ok(())
+}
+
 impl fp_self_contained::SelfContainedCall for RuntimeCall {
     type SignedInfo = H160;
 
@@ -2762,7 +2822,12 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
         len: usize,
     ) -> Option<TransactionValidity> {
         match self {
-            RuntimeCall::Ethereum(call) => call.validate_self_contained(info, dispatch_info, len),
+            RuntimeCall::Ethereum(call) => {
+                if let Err(e) = validate_ethereum_transaction(call, info) {
+                    return Some(Err(e));
+                }
+                call.validate_self_contained(info, dispatch_info, len)
+            }
             _ => None,
         }
     }
@@ -2775,6 +2840,9 @@ impl fp_self_contained::SelfContainedCall for RuntimeCall {
     ) -> Option<Result<(), TransactionValidityError>> {
         match self {
             RuntimeCall::Ethereum(call) => {
+                if let Err(e) = validate_ethereum_transaction(call, info) {
+                    return Some(Err(e));
+                }
                 call.pre_dispatch_self_contained(info, dispatch_info, len)
             }
             _ => None,
@@ -2971,7 +3039,7 @@ impl_runtime_apis! {
             source: sp_statement_store::runtime_api::StatementSource,
             statement: sp_statement_store::Statement,
         ) -> Result<sp_statement_store::runtime_api::ValidStatement, sp_statement_store::runtime_api::InvalidStatement> {
-            Statement::validate_statement(source, statement)
+            statement_validation::validate_statement::<Runtime>(source, statement)
         }
     }
 
@@ -3664,6 +3732,31 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_evm_deployment_control::DeploymentControlApi<Block> for Runtime {
+        fn is_enabled() -> bool {
+            EvmDeploymentControl::is_enabled()
+        }
+
+        fn can_deploy(deployer: H160, init_code_hash: H256) -> bool {
+            EvmDeploymentControl::can_deploy(&deployer, &init_code_hash)
+        }
+    }
+
+    impl evm_simulation::EthereumSimulationApi<Block> for Runtime {
+        fn simulate(
+            blocks: Vec<evm_simulation::SimulatedBlock>,
+            validation: bool,
+        ) -> Result<Vec<evm_simulation::SimulatedBlockResult>, sp_runtime::DispatchError> {
+            evm_simulation::simulate::<Runtime>(blocks, validation)
+        }
+
+        fn estimate_gas(
+            call: evm_simulation::SimulatedCall,
+        ) -> Result<evm_simulation::GasEstimate, sp_runtime::DispatchError> {
+            evm_simulation::estimate_gas::<Runtime>(call)
+        }
+    }
+
     impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
         fn convert_transaction(transaction: EthereumTransaction) -> <Block as BlockT>::Extrinsic {
             UncheckedExtrinsic::new_unsigned(
@@ -3816,4 +3909,52 @@ mod tests {
             size,
         );
     }
+
+    #[test]
+    fn every_custom_origin_has_a_track() {
+        use governance::pallet_custom_origins::Origin;
+        use pallet_referenda::TracksInfo as _;
+
+        let ids: Vec<u16> = TracksInfo::tracks().iter().map(|(id, _)| *id).collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(ids, sorted, "track ids must be unique and ascending");
+
+        let root: OriginCaller = frame_system::RawOrigin::Root.into();
+        assert_eq!(TracksInfo::track_for(&root), Ok(0));
+        for origin in [
+            Origin::WhitelistedCaller,
+            Origin::StakingAdmin,
+            Origin::Treasurer,
+            Origin::ReferendumCanceller,
+            Origin::EvmAdmin,
+            Origin::GeneralAdmin,
+        ] {
+            let id = TracksInfo::track_for(&origin.into()).expect("custom origin has a track");
+            assert!(ids.contains(&id));
+        }
+    }
+
+    #[test]
+    fn general_admin_whitelists_calls() {
+        use frame_support::traits::EnsureOrigin;
+        use governance::pallet_custom_origins::Origin;
+
+        type WhitelistOrigin = <Runtime as pallet_whitelist::Config>::WhitelistOrigin;
+        assert!(WhitelistOrigin::ensure_origin(RuntimeOrigin::root()).is_ok());
+        assert!(WhitelistOrigin::ensure_origin(Origin::GeneralAdmin.into()).is_ok());
+        assert!(WhitelistOrigin::ensure_origin(Origin::WhitelistedCaller.into()).is_err());
+    }
+
+    #[test]
+    fn remove_sudo_clears_the_sudo_key() {
+        use frame_support::traits::OnRuntimeUpgrade;
+
+        sp_io::TestExternalities::default().execute_with(|| {
+            pallet_sudo::Key::<Runtime>::put(AccountId::from([1u8; 20]));
+            governance::migrations::RemoveSudo::on_runtime_upgrade();
+            assert_eq!(pallet_sudo::Key::<Runtime>::get(), None);
+        });
+    }
 }
@@ -0,0 +1,257 @@
+//! # Statement validation
+//!
+//! Runtime side of the statement store for SCS accounts, which are Ethereum addresses
+//! (`AccountId20`) rather than sr25519 keys.
+//!
+//! [`validate_statement`] replaces `pallet_statement::Pallet::validate_statement` in the
+//! `ValidateStatement` runtime API. A statement must carry a `Secp256k1Ecdsa` proof made with an
+//! Ethereum key over the keccak-256 hash of its [`signature_material`], either directly or as an
+//! EIP-191 `personal_sign` message, so that wallets can sign statements. The signer is the
+//! Ethereum address of the public key of the proof. Other proofs are rejected: sr25519 and ed25519
+//! keys have no account here.
+//!
+//! The allowance of the signer, the statements and bytes it may keep in the store, is derived
+//! from its native balance with the parameters of `pallet_statement`: one statement per
+//! `StatementCost` and one byte per `ByteCost`, within `MinAllowed*` and `MaxAllowed*`. Accounts
+//! holding less than one `StatementCost` get no allowance at all, so that statements cannot be
+//! spammed from empty accounts.
+//!
+//! [`signature_material`]: Statement::signature_material
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use fp_account::{AccountId20, EthereumSigner};
+use polkadot_sdk::{
+    frame_support::traits::{fungible::Inspect, Get},
+    frame_system, pallet_statement,
+    sp_core::ecdsa,
+    sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::keccak_256},
+    sp_runtime::{traits::IdentifyAccount, SaturatedConversion},
+    sp_statement_store::{
+        runtime_api::{InvalidStatement, StatementSource, ValidStatement},
+        Proof, Statement,
+    },
+};
+
+/// Parameters of the allowance of an account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Balance needed per statement.
+    pub statement_cost: u128,
+    /// Balance needed per byte.
+    pub byte_cost: u128,
+    pub min_statements: u32,
+    pub max_statements: u32,
+    pub min_bytes: u32,
+    pub max_bytes: u32,
+}
+
+impl Limits {
+    /// The limits set by the `pallet_statement` configuration of `T`.
+    pub fn of<T>() -> Self
+    where
+        T: pallet_statement::Config,
+        <T as frame_system::Config>::AccountId: From<polkadot_sdk::sp_statement_store::AccountId>,
+    {
+        Self {
+            statement_cost: T::StatementCost::get().saturated_into(),
+            byte_cost: T::ByteCost::get().saturated_into(),
+            min_statements: T::MinAllowedStatements::get(),
+            max_statements: T::MaxAllowedStatements::get(),
+            min_bytes: T::MinAllowedBytes::get(),
+            max_bytes: T::MaxAllowedBytes::get(),
+        }
+    }
+
+    /// Statements and bytes an account holding `balance` may keep in the store.
+    pub fn allowance(&self, balance: u128) -> ValidStatement {
+        if balance < self.statement_cost.max(1) {
+            return ValidStatement {
+                max_count: 0,
+                max_size: 0,
+            };
+        }
+        let within = |cost: u128, min: u32, max: u32| match balance.checked_div(cost) {
+            Some(allowed) => allowed.saturated_into::<u32>().clamp(min, max),
+            None => max,
+        };
+        ValidStatement {
+            max_count: within(
+                self.statement_cost,
+                self.min_statements,
+                self.max_statements,
+            ),
+            max_size: within(self.byte_cost, self.min_bytes, self.max_bytes),
+        }
+    }
+}
+
+/// The message an Ethereum wallet signs with `personal_sign` over the 32 bytes of `digest`.
+fn eip191(digest: &[u8; 32]) -> [u8; 32] {
+    let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+    message.extend_from_slice(digest);
+    keccak_256(&message)
+}
+
+/// The Ethereum address that signed `statement`.
+pub fn ethereum_signer(statement: &Statement) -> Result<AccountId20, InvalidStatement> {
+    let (signature, signer) = match statement.proof() {
+        Some(Proof::Secp256k1Ecdsa { signature, signer }) => (signature, signer),
+        Some(_) => return Err(InvalidStatement::BadProof),
+        None => return Err(InvalidStatement::NoProof),
+    };
+    let digest = keccak_256(&statement.signature_material());
+    let signed = [digest, eip191(&digest)].iter().any(|message| {
+        secp256k1_ecdsa_recover_compressed(signature, message).is_ok_and(|key| key == *signer)
+    });
+    if !signed {
+        return Err(InvalidStatement::BadProof);
+    }
+    Ok(EthereumSigner::from(ecdsa::Public::from_raw(*signer)).into_account())
+}
+
+/// Validate `statement` for the runtime `T`, see the crate documentation.
+pub fn validate_statement<T>(
+    _source: StatementSource,
+    statement: Statement,
+) -> Result<ValidStatement, InvalidStatement>
+where
+    T: pallet_statement::Config,
+    <T as frame_system::Config>::AccountId:
+        From<polkadot_sdk::sp_statement_store::AccountId> + From<AccountId20>,
+{
+    let account = ethereum_signer(&statement)?;
+    let balance = T::Currency::balance(&account.into());
+    Ok(Limits::of::<T>().allowance(balance.saturated_into()))
+}
+
+/// Sign `statement` with the Ethereum key `pair`, as [`validate_statement`] expects.
+#[cfg(feature = "std")]
+pub fn sign(statement: &mut Statement, pair: &ecdsa::Pair) {
+    let signature = pair.sign_prehashed(&keccak_256(&statement.signature_material()));
+    statement.set_proof(Proof::Secp256k1Ecdsa {
+        signature: signature.0,
+        signer: pair.public().0,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polkadot_sdk::sp_core::{Pair, H160};
+
+    const LIMITS: Limits = Limits {
+        statement_cost: 1_000,
+        byte_cost: 10,
+        min_statements: 4,
+        max_statements: 10,
+        min_bytes: 1024,
+        max_bytes: 4096,
+    };
+
+    fn pair() -> ecdsa::Pair {
+        ecdsa::Pair::from_seed(&[7; 32])
+    }
+
+    fn order() -> Statement {
+        let mut statement = Statement::new();
+        statement.set_topic(0, keccak_256(b"orders/SCS-USDT"));
+        statement.set_plain_data(b"buy 10 SCS at 1.5".to_vec());
+        statement
+    }
+
+    /// The address of `pair`, as Ethereum derives it from the uncompressed public key.
+    fn address(pair: &ecdsa::Pair) -> AccountId20 {
+        let message = [0u8; 32];
+        let signature = pair.sign_prehashed(&message);
+        let public =
+            polkadot_sdk::sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &message).unwrap();
+        H160::from_slice(&keccak_256(&public)[12..]).into()
+    }
+
+    #[test]
+    fn accepts_statements_signed_by_ethereum_keys() {
+        let mut statement = order();
+        sign(&mut statement, &pair());
+        assert_eq!(ethereum_signer(&statement), Ok(address(&pair())));
+    }
+
+    #[test]
+    fn accepts_personal_sign_signatures() {
+        let mut statement = order();
+        let digest = eip191(&keccak_256(&statement.signature_material()));
+        let mut signature = pair().sign_prehashed(&digest).0;
+        // Wallets return `v` as 27 or 28.
+        signature[64] += 27;
+        statement.set_proof(Proof::Secp256k1Ecdsa {
+            signature,
+            signer: pair().public().0,
+        });
+        assert_eq!(ethereum_signer(&statement), Ok(address(&pair())));
+    }
+
+    #[test]
+    fn rejects_other_proofs_and_changed_statements() {
+        assert_eq!(ethereum_signer(&order()), Err(InvalidStatement::NoProof));
+
+        let mut statement = order();
+        sign(&mut statement, &pair());
+        statement.set_plain_data(b"buy 1000 SCS at 1.5".to_vec());
+        assert_eq!(ethereum_signer(&statement), Err(InvalidStatement::BadProof));
+
+        let mut statement = order();
+        let other = ecdsa::Pair::from_seed(&[8; 32]);
+        sign(&mut statement, &pair());
+        if let Some(Proof::Secp256k1Ecdsa { signature, .. }) = statement.proof().cloned() {
+            statement.set_proof(Proof::Secp256k1Ecdsa {
+                signature,
+                signer: other.public().0,
+            });
+        }
+        assert_eq!(ethereum_signer(&statement), Err(InvalidStatement::BadProof));
+
+        let mut statement = order();
+        statement.set_proof(Proof::Sr25519 {
+            signature: [0; 64],
+            signer: [0; 32],
+        });
+        assert_eq!(ethereum_signer(&statement), Err(InvalidStatement::BadProof));
+    }
+
+    #[test]
+    fn allowance_follows_the_balance() {
+        let none = ValidStatement {
+            max_count: 0,
+            max_size: 0,
+        };
+        assert_eq!(LIMITS.allowance(0), none);
+        assert_eq!(LIMITS.allowance(999), none);
+        assert_eq!(
+            LIMITS.allowance(1_000),
+            ValidStatement {
+                max_count: 4,
+                max_size: 1024
+            }
+        );
+        assert_eq!(
+            LIMITS.allowance(20_000),
+            ValidStatement {
+                max_count: 10,
+                max_size: 2000
+            }
+        );
+        assert_eq!(
+            LIMITS.allowance(u128::MAX),
+            ValidStatement {
+                max_count: 10,
+                max_size: 4096
+            }
+        );
+
+        let free_bytes = Limits {
+            byte_cost: 0,
+            ..LIMITS
+        };
+        assert_eq!(free_bytes.allowance(1_000).max_size, 4096);
+    }
+}
@@ -0,0 +1,42 @@
+//! Migrations that complete the handover from `pallet_sudo` to OpenGov.
+
+use super::*;
+use frame_support::traits::OnRuntimeUpgrade;
+
+parameter_types! {
+    pub const SudoPalletName: &'static str = "Sudo";
+}
+
+/// Removes all storage of `pallet_sudo`, including the sudo `Key`.
+///
+/// This is the last step of the handover. Add it to `Migrations` in the same release that removes
+/// `Sudo` from `construct_runtime!`, once the custom origins and tracks are live and at least one
+/// referendum has been approved. Under `try-runtime` the upgrade fails if either is not the case.
+pub type RemoveSudo = (
+    EnsureGovernanceIsLive,
+    frame_support::migrations::RemovePallet<
+        SudoPalletName,
+        <Runtime as frame_system::Config>::DbWeight,
+    >,
+);
+
+/// Try-runtime guard of [`RemoveSudo`]; it does nothing on a real upgrade.
+pub struct EnsureGovernanceIsLive;
+
+impl OnRuntimeUpgrade for EnsureGovernanceIsLive {
+    #[cfg(feature = "try-runtime")]
+    fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+        use frame_support::{ensure, traits::PalletsInfoAccess};
+
+        ensure!(
+            !AllPalletsWithSystem::infos().iter().any(|info| info.name == SudoPalletName::get()),
+            "`Sudo` must be removed from `construct_runtime!` in the release that runs `RemoveSudo`"
+        );
+        ensure!(
+            pallet_referenda::ReferendumInfoFor::<Runtime>::iter_values()
+                .any(|info| matches!(info, pallet_referenda::ReferendumInfo::Approved(..))),
+            "no referendum has been approved yet, governance is not live"
+        );
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,45 @@
+//! OpenGov configuration: custom origins, referendum tracks and the origins that the rest of the
+//! runtime is configured with.
+
+use super::*;
+use frame_support::traits::EitherOf;
+
+mod origins;
+pub use origins::{
+    pallet_custom_origins, EvmAdmin, GeneralAdmin, ReferendumCanceller, StakingAdmin, Treasurer,
+    WhitelistedCaller,
+};
+mod tracks;
+pub use tracks::TracksInfo;
+
+pub mod migrations;
+
+/// Root, or a referendum passed on the `staking_admin` track.
+pub type StakingAdminOrRoot = EitherOf<EnsureRoot<AccountId>, StakingAdmin>;
+
+/// Root, or a referendum passed on the `evm_admin` track.
+pub type EvmAdminOrRoot = EitherOf<EnsureRoot<AccountId>, EvmAdmin>;
+
+/// Root, or a referendum passed on the `general_admin` track.
+pub type GeneralAdminOrRoot = EitherOf<EnsureRoot<AccountId>, GeneralAdmin>;
+
+/// Root, or a referendum passed on the `treasurer` track.
+pub type TreasurerOrRoot = EitherOf<EnsureRoot<AccountId>, Treasurer>;
+
+/// Root, or a referendum passed on the `referendum_canceller` track.
+pub type ReferendumCancellerOrRoot = EitherOf<EnsureRoot<AccountId>, ReferendumCanceller>;
+
+/// Root, or a referendum passed on the `whitelisted_caller` track.
+pub type WhitelistedCallerOrRoot = EitherOf<EnsureRoot<AccountId>, WhitelistedCaller>;
+
+parameter_types! {
+    /// The most a single `treasurer` referendum is allowed to spend.
+    pub const MaxTreasurerSpend: Balance = 10_000_000 * DOLLARS;
+}
+
+/// Origin allowed to spend from the treasury: root without limit, the `treasurer` track up to
+/// [`MaxTreasurerSpend`].
+pub type TreasurySpender = EitherOf<
+    EnsureWithSuccess<EnsureRoot<AccountId>, AccountId, MaxBalance>,
+    EnsureWithSuccess<Treasurer, AccountId, MaxTreasurerSpend>,
+>;
@@ -0,0 +1,146 @@
+//! Canonical EVM contracts, deployed at genesis and injectable through governance.
+//!
+//! The bundled set lives in `predeploys/predeploys.json`; `scripts/fetch_predeploys.sh` refreshes
+//! it from an Ethereum node.
+
+use super::*;
+use once_cell::race::OnceBox;
+pub use pallet_evm_predeploys::{genesis_accounts, parse, Predeploy};
+
+/// The bundled predeploys, in the format read by [`parse`].
+pub const PREDEPLOYS_JSON: &str = include_str!("predeploys/predeploys.json");
+
+/// The bundled predeploys.
+pub fn bundled() -> Vec<Predeploy> {
+    parse(PREDEPLOYS_JSON).expect("bundled predeploys are valid; qed")
+}
+
+/// [`bundled`] as the `Predeploys` of `pallet_evm_predeploys`, parsed once per runtime instance.
+pub struct BundledPredeploys;
+impl Get<&'static [Predeploy]> for BundledPredeploys {
+    fn get() -> &'static [Predeploy] {
+        static BUNDLED: OnceBox<Vec<Predeploy>> = OnceBox::new();
+        BUNDLED.get_or_init(|| Box::new(bundled()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    /// Canonical address and `keccak256` of the runtime code of every bundled predeploy.
+    const CANONICAL: [(&str, &str); 8] = [
+        // CREATE2 factory
+        (
+            "4e59b44847b379578588920ca78fbf26c0b4956c",
+            "2fa86add0aed31f33a762c9d88e807c475bd51d0f52bd0955754b2608f7e4989",
+        ),
+        // Multicall3
+        (
+            "ca11bde05977b3631167028862be2a173976ca11",
+            "d5c15df687b16f2ff992fc8d767b4216323184a2bbc6ee2f9c398c318e770891",
+        ),
+        // Permit2
+        (
+            "000000000022d473030f116ddee9f6b43ac78ba3",
+            "c67d1657868aa5146eaf24fb879fb1fdec3d2d493b3683a61c9c2f4fb2851131",
+        ),
+        // Safe 1.3.0
+        (
+            "d9db270c1b5e3bd161e8c8503c55ceabee709552",
+            "bba688fbdb21ad2bb58bc320638b43d94e7d100f6f3ebaab0a4e4de6304b1c2e",
+        ),
+        // SafeL2 1.3.0
+        (
+            "3e5c63644e683549055b9be8653de26e0b4cd36e",
+            "21842597390c4c6e3c1239e434a682b054bd9548eee5e9b1d6a4482731023c0f",
+        ),
+        // SafeProxyFactory 1.3.0
+        (
+            "a6b71e26c5e0845f74c812102ca7114b6a896ab2",
+            "337d7f54be11b6ed55fef7b667ea5488db53db8320a05d1146aa4bd169a39a9b",
+        ),
+        // MultiSend 1.3.0
+        (
+            "a238cbeb142c10ef7ad8442c6d1f9e89e07e7761",
+            "0208282bd262360d0320862c5ac70f375f5ed3b9d89a83a615b4d398415bdc83",
+        ),
+        // MultiSendCallOnly 1.3.0
+        (
+            "40a2accbd92bca938b02010e17a5b8929b49130d",
+            "a9865ac2d9c7a1591619b188c4d88167b50df6cc0c5327fcbd1c8c75f7c066ad",
+        ),
+    ];
+
+    /// The Safe singletons, whose constructor sets `threshold` (slot 4) to lock them.
+    const SAFE_SINGLETONS: [&str; 2] = [
+        "d9db270c1b5e3bd161e8c8503c55ceabee709552",
+        "3e5c63644e683549055b9be8653de26e0b4cd36e",
+    ];
+
+    fn address(hex: &str) -> H160 {
+        H160::from_str(hex).unwrap()
+    }
+
+    #[test]
+    fn bundled_predeploys_are_valid() {
+        let predeploys = bundled();
+        assert_eq!(predeploys.len(), CANONICAL.len());
+        for (canonical, code_hash) in CANONICAL {
+            let predeploy = predeploys
+                .iter()
+                .find(|p| p.address == address(canonical))
+                .unwrap_or_else(|| panic!("{canonical} is bundled"));
+            assert_eq!(
+                H256(sp_io::hashing::keccak_256(&predeploy.account.code)),
+                H256::from_str(code_hash).unwrap(),
+                "code of {canonical}"
+            );
+            assert_eq!(predeploy.account.nonce, U256::one());
+        }
+
+        for singleton in SAFE_SINGLETONS {
+            let safe = predeploys
+                .iter()
+                .find(|p| p.address == address(singleton))
+                .unwrap();
+            assert_eq!(
+                safe.account.storage.get(&H256::from_low_u64_be(4)),
+                Some(&H256::from_low_u64_be(1)),
+                "threshold of {singleton}"
+            );
+        }
+        assert!(predeploys
+            .iter()
+            .filter(|p| !SAFE_SINGLETONS.iter().any(|s| p.address == address(s)))
+            .all(|p| p.account.storage.is_empty()));
+    }
+
+    #[test]
+    fn inject_is_weighed_by_the_storage_written() {
+        let db = <Runtime as frame_system::Config>::DbWeight::get();
+        let factory = H160::from_str("4e59b44847b379578588920ca78fbf26c0b4956c").unwrap();
+        let factory = EvmPredeploys::predeploy(factory).expect("CREATE2 factory is bundled");
+        assert_eq!(
+            EvmPredeploys::inject_weight(Some(factory)),
+            db.reads_writes(3, 3)
+        );
+
+        let mut safe = factory.clone();
+        safe.account.storage = (1..=2u64)
+            .map(|slot| (H256::from_low_u64_be(slot), H256::from_low_u64_be(1)))
+            .collect();
+        assert_eq!(
+            EvmPredeploys::inject_weight(Some(&safe)),
+            db.reads_writes(3, 5)
+        );
+
+        let singleton =
+            EvmPredeploys::predeploy(address(SAFE_SINGLETONS[0])).expect("Safe is bundled");
+        assert_eq!(
+            EvmPredeploys::inject_weight(Some(singleton)),
+            db.reads_writes(3, 4)
+        );
+    }
+}
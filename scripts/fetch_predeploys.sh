@@ -0,0 +1,55 @@
+#!/bin/bash
+
+# Fetch the runtime code of the canonical predeploys from an Ethereum node and write them in the
+# format read by `pallet_evm_predeploys::parse`.
+#
+# usage: ./scripts/fetch_predeploys.sh https://ethereum-rpc.example > predeploys.json
+#
+# Review the output and copy it to runtime/{mainnet,testnet}/src/predeploys/predeploys.json.
+
+set -euo pipefail
+
+RPC=${1:?usage: $0 <ethereum rpc url>}
+
+# name|address|storage slots to copy (comma separated)
+PREDEPLOYS=(
+    "CREATE2 factory|0x4e59b44847b379578588920ca78fbf26c0b4956c|"
+    "Multicall3|0xca11bde05977b3631167028862be2a173976ca11|"
+    "Permit2|0x000000000022d473030f116ddee9f6b43ac78ba3|"
+    # The Safe singletons set `threshold` (slot 4) in their constructor to lock themselves.
+    "Safe 1.3.0|0xd9db270c1b5e3bd161e8c8503c55ceabee709552|0x4"
+    "SafeL2 1.3.0|0x3e5c63644e683549055b9be8653de26e0b4cd36e|0x4"
+    "SafeProxyFactory 1.3.0|0xa6b71e26c5e0845f74c812102ca7114b6a896ab2|"
+    "CompatibilityFallbackHandler 1.3.0|0xf48f2b2d2a534e402487b3ee7c18c33aec0fe5e4|"
+    "MultiSend 1.3.0|0xa238cbeb142c10ef7ad8442c6d1f9e89e07e7761|"
+    "MultiSendCallOnly 1.3.0|0x40a2accbd92bca938b02010e17a5b8929b49130d|"
+)
+
+rpc() {
+    curl -sf -H 'Content-Type: application/json' \
+        -d "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"$1\",\"params\":$2}" "$RPC" | jq -r .result
+}
+
+entries=()
+for predeploy in "${PREDEPLOYS[@]}"; do
+    IFS='|' read -r name address slots <<< "$predeploy"
+
+    code=$(rpc eth_getCode "[\"$address\",\"latest\"]")
+    if [ -z "$code" ] || [ "$code" = "0x" ] || [ "$code" = "null" ]; then
+        echo "no code at $address ($name)" >&2
+        exit 1
+    fi
+
+    storage="{}"
+    for slot in ${slots//,/ }; do
+        key=$(printf '0x%064x' "$slot")
+        value=$(rpc eth_getStorageAt "[\"$address\",\"$key\",\"latest\"]")
+        storage=$(jq -c --arg k "$key" --arg v "$value" '. + {($k): $v}' <<< "$storage")
+    done
+
+    entries+=("$(jq -n --arg name "$name" --arg address "$address" --arg code "$code" \
+        --argjson storage "$storage" \
+        '{name: $name, address: $address, code: $code, nonce: "0x1", storage: $storage}')")
+done
+
+printf '%s\n' "${entries[@]}" | jq -s .
@@ -1,18 +1,6 @@
 #!/bin/bash
 
-# This script lists the files in the current directory
-#ls
+# Prints a new secret phrase, the session keys derived from it and the `set_keys` call.
+# Insert them on the validator with `scs-node session-keys insert --suri "<phrase>"`.
 
-mkdir keystore
-
-./target/release/scs-node key generate --output-type json --scheme ed25519 | tee keystore/grandpa.json
-
-./target/release/scs-node key generate --output-type json --scheme sr25519 | tee keystore/babe.json
-
-./target/release/scs-node key generate --output-type json --scheme sr25519 | tee keystore/im_online.json
-
-./target/release/scs-node key generate --output-type json --scheme sr25519 | tee keystore/authority_discovery.json
-
-./target/release/scs-node key generate --output-type json --scheme sr25519 | tee keystore/mixnet.json
-
-./target/release/scs-node key generate --output-type json --scheme ecdsa | tee keystore/beefy.json
+./target/release/scs-node session-keys generate
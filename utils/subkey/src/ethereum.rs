@@ -0,0 +1,320 @@
+//! Ethereum style keys for `AccountId20` chains.
+//!
+//! Accounts are derived as wallets do: BIP-39 mnemonic, BIP-32 path `m/44'/60'/0'/0/<index>`,
+//! secp256k1 key and the Keccak-256 address of its public key, printed with the EIP-55 checksum.
+//! The same mnemonic gives the same addresses as MetaMask or Ledger Live, and the address is the
+//! `AccountId20` of the runtime. Private keys can be exported to and imported from Web3 Secret
+//! Storage (V3) JSON keystores.
+
+use bip32::{DerivationPath, XPrv};
+use bip39::Mnemonic;
+use fp_account::AccountId20;
+use polkadot_sdk::{
+	sc_cli::Error,
+	sp_core::{crypto::ByteArray, ecdsa, hashing::keccak_256, Pair},
+};
+use std::path::PathBuf;
+
+/// The standard Ethereum derivation path, without the account index.
+const DEFAULT_PATH: &str = "m/44'/60'/0'/0";
+
+/// Ethereum key utilities.
+#[derive(Debug, clap::Subcommand)]
+pub enum EthereumCmd {
+	/// Generate a new mnemonic and print the first accounts derived from it.
+	Generate(GenerateCmd),
+
+	/// Print the address and private key of a mnemonic or private key.
+	Inspect(InspectCmd),
+
+	/// Encrypt the key of a mnemonic or private key into a V3 JSON keystore.
+	ExportKeystore(ExportKeystoreCmd),
+
+	/// Decrypt a V3 JSON keystore and print its address and private key.
+	ImportKeystore(ImportKeystoreCmd),
+}
+
+/// Where the key comes from.
+#[derive(Debug, Clone, clap::Args)]
+pub struct SecretParams {
+	/// BIP-39 mnemonic or `0x` prefixed private key. Prompted for if omitted.
+	#[arg(long)]
+	pub secret: Option<String>,
+
+	/// Optional BIP-39 passphrase ("25th word").
+	#[arg(long, default_value = "")]
+	pub passphrase: String,
+
+	/// Index of the account on the derivation path.
+	#[arg(long, default_value_t = 0)]
+	pub index: u32,
+
+	/// Derivation path prefix; the account is at `<PATH>/<INDEX>`.
+	#[arg(long, default_value = DEFAULT_PATH)]
+	pub path: String,
+}
+
+/// The `ethereum generate` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct GenerateCmd {
+	/// Number of words of the mnemonic: 12, 15, 18, 21 or 24.
+	#[arg(long, default_value_t = 12)]
+	pub words: usize,
+
+	/// Number of accounts to print.
+	#[arg(long, default_value_t = 1)]
+	pub count: u32,
+}
+
+/// The `ethereum inspect` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct InspectCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub secret: SecretParams,
+}
+
+/// The `ethereum export-keystore` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportKeystoreCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub secret: SecretParams,
+
+	/// Directory the keystore file is written to.
+	#[arg(long, default_value = ".")]
+	pub output: PathBuf,
+}
+
+/// The `ethereum import-keystore` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ImportKeystoreCmd {
+	/// The V3 JSON keystore file.
+	pub file: PathBuf,
+}
+
+/// An account derived from a mnemonic or given as a private key.
+pub struct Account {
+	/// `None` when the account was given as a private key.
+	pub path: Option<String>,
+	pub pair: ecdsa::Pair,
+}
+
+impl Account {
+	/// Account of a raw secp256k1 private key.
+	pub fn from_private_key(key: &[u8]) -> Result<Self, Error> {
+		let pair = ecdsa::Pair::from_seed_slice(key)
+			.map_err(|_| Error::Input("Invalid secp256k1 private key".into()))?;
+		Ok(Account { path: None, pair })
+	}
+
+	/// Account at `<path>/<index>` of a BIP-39 mnemonic.
+	pub fn from_mnemonic(
+		mnemonic: &str,
+		passphrase: &str,
+		path: &str,
+		index: u32,
+	) -> Result<Self, Error> {
+		let mnemonic = Mnemonic::parse_normalized(mnemonic)
+			.map_err(|e| Error::Input(format!("Invalid mnemonic: {e}")))?;
+		let path = format!("{}/{index}", path.trim_end_matches('/'));
+		let derivation: DerivationPath = path
+			.parse()
+			.map_err(|e| Error::Input(format!("Invalid derivation path `{path}`: {e}")))?;
+		let xprv = XPrv::derive_from_path(mnemonic.to_seed(passphrase), &derivation)
+			.map_err(|e| Error::Input(format!("Derivation failed: {e}")))?;
+
+		let mut account = Self::from_private_key(&xprv.private_key().to_bytes())?;
+		account.path = Some(path);
+		Ok(account)
+	}
+
+	/// The account, as used by the runtime.
+	pub fn account_id(&self) -> AccountId20 {
+		self.pair.public().into()
+	}
+
+	/// The private key.
+	pub fn private_key(&self) -> [u8; 32] {
+		self.pair.seed()
+	}
+}
+
+/// `address` with the EIP-55 mixed case checksum.
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+	let lower = array_bytes::bytes2hex("", address);
+	let hash = keccak_256(lower.as_bytes());
+	let checksummed: String = lower
+		.chars()
+		.enumerate()
+		.map(|(i, c)| {
+			let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+			if nibble >= 8 {
+				c.to_ascii_uppercase()
+			} else {
+				c
+			}
+		})
+		.collect();
+	format!("0x{checksummed}")
+}
+
+fn print_account(account: &Account) {
+	if let Some(path) = &account.path {
+		println!("Path:        {path}");
+	}
+	println!("Address:     {}", to_checksum_address(&account.account_id().0));
+	println!("Public key:  {}", array_bytes::bytes2hex("0x", account.pair.public().to_raw_vec()));
+	println!("Private key: {}", array_bytes::bytes2hex("0x", account.private_key()));
+}
+
+fn read_secret(value: &Option<String>, prompt: &str) -> Result<String, Error> {
+	match value {
+		Some(value) => Ok(value.clone()),
+		None => Ok(rpassword::prompt_password(prompt)?),
+	}
+}
+
+impl SecretParams {
+	fn account(&self) -> Result<Account, Error> {
+		let secret = read_secret(&self.secret, "Mnemonic or private key: ")?;
+		let secret = secret.trim();
+		match secret.strip_prefix("0x") {
+			Some(hex) => {
+				let key = array_bytes::hex2bytes(hex)
+					.map_err(|_| Error::Input("Invalid private key hex".into()))?;
+				Account::from_private_key(&key)
+			},
+			None => Account::from_mnemonic(secret, &self.passphrase, &self.path, self.index),
+		}
+	}
+}
+
+impl EthereumCmd {
+	/// Run the selected subcommand.
+	pub fn run(&self) -> Result<(), Error> {
+		match self {
+			EthereumCmd::Generate(cmd) => cmd.run(),
+			EthereumCmd::Inspect(cmd) => cmd.run(),
+			EthereumCmd::ExportKeystore(cmd) => cmd.run(),
+			EthereumCmd::ImportKeystore(cmd) => cmd.run(),
+		}
+	}
+}
+
+impl GenerateCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), Error> {
+		let mnemonic = Mnemonic::generate(self.words)
+			.map_err(|e| Error::Input(format!("Failed to generate a mnemonic: {e}")))?;
+		println!("Mnemonic:    {mnemonic}");
+		for index in 0..self.count {
+			println!();
+			print_account(&Account::from_mnemonic(&mnemonic.to_string(), "", DEFAULT_PATH, index)?);
+		}
+		Ok(())
+	}
+}
+
+impl InspectCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), Error> {
+		print_account(&self.secret.account()?);
+		Ok(())
+	}
+}
+
+impl ExportKeystoreCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), Error> {
+		let account = self.secret.account()?;
+		let password = rpassword::prompt_password("Keystore password: ")?;
+		if password != rpassword::prompt_password("Repeat the password: ")? {
+			return Err(Error::Input("Passwords do not match".into()))
+		}
+
+		let address = to_checksum_address(&account.account_id().0);
+		let name = format!("UTC--{}", address.trim_start_matches("0x").to_lowercase());
+		eth_keystore::encrypt_key(
+			&self.output,
+			&mut rand::thread_rng(),
+			account.private_key(),
+			password,
+			Some(&name),
+		)
+		.map_err(|e| Error::Input(format!("Failed to write the keystore: {e}")))?;
+
+		println!("Address:  {address}");
+		println!("Keystore: {}", self.output.join(name).display());
+		Ok(())
+	}
+}
+
+impl ImportKeystoreCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<(), Error> {
+		let password = rpassword::prompt_password("Keystore password: ")?;
+		let key = eth_keystore::decrypt_key(&self.file, password)
+			.map_err(|e| Error::Input(format!("Failed to decrypt the keystore: {e}")))?;
+		print_account(&Account::from_private_key(&key)?);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+	#[test]
+	fn derives_the_same_accounts_as_wallets() {
+		let account = Account::from_mnemonic(MNEMONIC, "", DEFAULT_PATH, 0).unwrap();
+		assert_eq!(
+			to_checksum_address(&account.account_id().0),
+			"0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+		);
+		assert_eq!(
+			array_bytes::bytes2hex("0x", account.private_key()),
+			"0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+		);
+
+		let account = Account::from_mnemonic(MNEMONIC, "", DEFAULT_PATH, 1).unwrap();
+		assert_eq!(
+			to_checksum_address(&account.account_id().0),
+			"0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+		);
+	}
+
+	#[test]
+	fn checksums_addresses_as_eip55() {
+		for address in [
+			"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+			"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+			"0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+			"0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+		] {
+			let bytes: [u8; 20] = array_bytes::hex2array(address).unwrap();
+			assert_eq!(to_checksum_address(&bytes), address);
+		}
+	}
+
+	#[test]
+	fn keystore_round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let account = Account::from_mnemonic(MNEMONIC, "", DEFAULT_PATH, 0).unwrap();
+		eth_keystore::encrypt_key(
+			dir.path(),
+			&mut rand::thread_rng(),
+			account.private_key(),
+			"password",
+			Some("key"),
+		)
+		.unwrap();
+
+		let key = eth_keystore::decrypt_key(dir.path().join("key"), "password").unwrap();
+		let imported = Account::from_private_key(&key).unwrap();
+		assert_eq!(imported.account_id(), account.account_id());
+		assert!(eth_keystore::decrypt_key(dir.path().join("key"), "wrong").is_err());
+	}
+}
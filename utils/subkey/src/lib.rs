@@ -308,8 +308,10 @@
 //! much longer for `Alice` who has a much longer name, thus the chances to generate a random
 //! address that contains the chain `alice` will be much smaller.
 
+pub mod ethereum;
+
 use clap::Parser;
-use sc_cli::{
+use polkadot_sdk::sc_cli::{
 	Error, GenerateCmd, GenerateKeyCmdCommon, InspectKeyCmd, InspectNodeKeyCmd, SignCmd, VanityCmd,
 	VerifyCmd,
 };
@@ -343,6 +345,10 @@ pub enum Subkey {
 
 	/// Verify a signature for a message, provided on STDIN, with a given (public or secret) key.
 	Verify(VerifyCmd),
+
+	/// Ethereum style keys: BIP-44 `m/44'/60'` accounts, EIP-55 addresses and V3 JSON keystores.
+	#[command(subcommand)]
+	Ethereum(ethereum::EthereumCmd),
 }
 
 /// Run the subkey command, given the appropriate runtime.
@@ -355,5 +361,6 @@ pub fn run() -> Result<(), Error> {
 		Subkey::Vanity(cmd) => cmd.run(),
 		Subkey::Verify(cmd) => cmd.run(),
 		Subkey::Sign(cmd) => cmd.run(),
+		Subkey::Ethereum(cmd) => cmd.run(),
 	}
 }